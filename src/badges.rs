@@ -0,0 +1,102 @@
+use crate::json;
+use crate::model::ProjectList;
+use crate::project_file_name;
+use crate::render::{self, RenderOptions, SectionKind};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+const BADGE_DIR: &str = "badges";
+const LABEL: &str = "changelog";
+
+/// shields.io's endpoint badge schema.
+#[derive(Serialize)]
+struct Endpoint<'a> {
+    #[serde(rename = "schemaVersion")]
+    schema_version: u8,
+    label: &'a str,
+    message: &'a str,
+    color: &'a str,
+}
+
+/// Writes `badges/<project>.json` for shields.io's endpoint badges and a
+/// ready-made `badges/<project>.svg` for each project, counting the
+/// features and fixes in the changelog.
+pub fn write(projects: &ProjectList, options: &RenderOptions) {
+    fs::create_dir_all(BADGE_DIR).expect("Failed to create badges directory");
+
+    for project in &projects.projects {
+        let sections = render::sections(project, options);
+        let count = |kind| {
+            sections
+                .sections
+                .iter()
+                .find(|section| section.kind == kind)
+                .map_or(0, |section| section.total())
+        };
+        let features = count(SectionKind::Features);
+        let fixes = count(SectionKind::BugFixes);
+
+        let message = format!(
+            "{} {}, {} {}",
+            features,
+            if features == 1 { "feature" } else { "features" },
+            fixes,
+            if fixes == 1 { "fix" } else { "fixes" }
+        );
+        let color = if features + fixes > 0 {
+            "blue"
+        } else {
+            "lightgrey"
+        };
+
+        let directory = Path::new(BADGE_DIR);
+        let name = project_file_name(&project.name);
+        let endpoint = Endpoint {
+            schema_version: 1,
+            label: LABEL,
+            message: &message,
+            color,
+        };
+        write_file(
+            &directory.join(format!("{}.json", name)),
+            json::to_string_pretty(&endpoint).expect("Failed to serialize JSON"),
+        );
+        write_file(
+            &directory.join(format!("{}.svg", name)),
+            svg(LABEL, &message, color),
+        );
+    }
+}
+
+fn write_file(path: &Path, contents: String) {
+    fs::write(path, contents).unwrap_or_else(|_| panic!("Failed to write {}", path.display()));
+}
+
+/// A flat badge in the shields.io style. Text widths are estimated at
+/// Verdana 11px's average advance, which is close enough for short text.
+fn svg(label: &str, message: &str, color: &str) -> String {
+    let fill = match color {
+        "blue" => "#007ec6",
+        _ => "#9f9f9f",
+    };
+    let width = |text: &str| text.chars().count() * 7 + 10;
+    let (label_width, message_width) = (width(label), width(message));
+    let total = label_width + message_width;
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{total}\" height=\"20\" role=\"img\" aria-label=\"{label}: {message}\">\n\
+         <title>{label}: {message}</title>\n\
+         <rect width=\"{label_width}\" height=\"20\" fill=\"#555\"/>\n\
+         <rect x=\"{label_width}\" width=\"{message_width}\" height=\"20\" fill=\"{fill}\"/>\n\
+         <g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" font-size=\"11\">\n\
+         <text x=\"{label_x}\" y=\"14\">{label}</text>\n\
+         <text x=\"{message_x}\" y=\"14\">{message}</text>\n\
+         </g>\n\
+         </svg>\n",
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+        label = render::escape_html(label),
+        message = render::escape_html(message),
+    )
+}
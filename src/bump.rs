@@ -0,0 +1,197 @@
+//! Writes the version each project's changes call for into its Cargo.toml
+//! or package.json, so the release commit can follow the notes.
+
+use crate::config::Config;
+use crate::json;
+use crate::model::{Project, ProjectList};
+use crate::render::RenderOptions;
+use crate::version::{self, Bump, Scheme};
+use std::fs;
+use toml::Value;
+
+pub struct Change<'a> {
+    pub project: &'a Project,
+    /// `Cargo.toml` or `package.json`, in the project's directory.
+    pub file: &'static str,
+    pub from: String,
+    pub to: String,
+    pub bump: Bump,
+}
+
+/// The change to each project's manifest, and a line on each project that
+/// has changes but no version to bump.
+pub fn plan<'a>(
+    projects: &'a ProjectList,
+    options: &RenderOptions,
+    config: &Config,
+) -> Result<(Vec<Change<'a>>, Vec<String>), String> {
+    let today = options.generated_at.date_naive();
+    let mut changes = vec![];
+    let mut skipped = vec![];
+
+    for project in &projects.projects {
+        let Some(bump) = version::recommend(project, options) else {
+            continue;
+        };
+        let scheme = Scheme::parse(&config.release(&project.name).scheme)?;
+
+        let (file, from) = match declared_version(project) {
+            Ok(declared) => declared,
+            Err(reason) => {
+                skipped.push(format!("{}: {}", project.name, reason));
+                continue;
+            }
+        };
+        let Some(to) = version::next(&scheme, Some(&from), bump, today) else {
+            skipped.push(format!(
+                "{}: {} is not a version in {}",
+                project.name, from, file
+            ));
+            continue;
+        };
+
+        changes.push(Change {
+            project,
+            file,
+            from,
+            to,
+            bump,
+        });
+    }
+
+    Ok((changes, skipped))
+}
+
+/// Rewrites the version in place, leaving the rest of the file as it was.
+pub fn apply(change: &Change) -> Result<(), String> {
+    let path = change.project.path.join(change.file);
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let updated = match change.file {
+        "Cargo.toml" => set_cargo_version(&text, &change.from, &change.to),
+        _ => set_npm_version(&text, &change.from, &change.to),
+    }
+    .ok_or_else(|| {
+        format!(
+            "No version {} to replace in {}",
+            change.from,
+            path.display()
+        )
+    })?;
+
+    fs::write(&path, updated).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn declared_version(project: &Project) -> Result<(&'static str, String), String> {
+    let cargo = fs::read_to_string(project.path.join("Cargo.toml")).ok();
+    let package = cargo
+        .and_then(|text| toml::from_str::<Value>(&text).ok())
+        .and_then(|cargo| cargo.get("package").cloned());
+    if let Some(package) = package {
+        return match package.get("version") {
+            Some(Value::String(version)) => Ok(("Cargo.toml", version.clone())),
+            Some(_) => Err("Cargo.toml takes its version from the workspace".to_string()),
+            None => Err("Cargo.toml has no version".to_string()),
+        };
+    }
+
+    let npm = fs::read_to_string(project.path.join("package.json"))
+        .ok()
+        .and_then(|text| json::parse(&text).ok());
+    match npm.as_ref().map(|npm| npm.get("version")) {
+        Some(Some(Value::String(version))) => Ok(("package.json", version.clone())),
+        Some(_) => Err("package.json has no version".to_string()),
+        None => Err("no Cargo.toml or package.json with a version".to_string()),
+    }
+}
+
+/// The `version = "..."` line of the `[package]` table.
+fn set_cargo_version(text: &str, from: &str, to: &str) -> Option<String> {
+    let mut in_package = false;
+    let mut done = false;
+    let mut lines = vec![];
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            in_package = trimmed.starts_with("[package]");
+        }
+
+        let is_version = trimmed
+            .strip_prefix("version")
+            .is_some_and(|rest| rest.trim_start().starts_with('='));
+        let quoted = format!("\"{}\"", from);
+        if in_package && !done && is_version && line.contains(&quoted) {
+            lines.push(line.replacen(&quoted, &format!("\"{}\"", to), 1));
+            done = true;
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    done.then(|| lines.concat())
+}
+
+/// The first `"version": "..."` member, the top-level one in any
+/// package.json npm writes.
+fn set_npm_version(text: &str, from: &str, to: &str) -> Option<String> {
+    let mut offset = 0;
+
+    while let Some(found) = text[offset..].find("\"version\"") {
+        let key_end = offset + found + "\"version\"".len();
+        let rest = text[key_end..].trim_start().strip_prefix(':');
+        let value = format!("\"{}\"", from);
+
+        if let Some(rest) = rest
+            .map(str::trim_start)
+            .filter(|rest| rest.starts_with(&value))
+        {
+            let start = text.len() - rest.len();
+            return Some(format!(
+                "{}\"{}\"{}",
+                &text[..start],
+                to,
+                &text[start + value.len()..]
+            ));
+        }
+        offset = key_end;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_versions_are_replaced_in_the_package_table() {
+        let text = "[package]\nname = \"core\"\nversion = \"2.0.0-rc.1\" # released\n\n\
+                    [dependencies]\nserde = { version = \"2.0.0-rc.1\" }\n";
+
+        assert_eq!(
+            set_cargo_version(text, "2.0.0-rc.1", "2.0.0").as_deref(),
+            Some(
+                "[package]\nname = \"core\"\nversion = \"2.0.0\" # released\n\n\
+                 [dependencies]\nserde = { version = \"2.0.0-rc.1\" }\n"
+            )
+        );
+        assert_eq!(set_cargo_version(text, "1.0.0", "1.0.1"), None);
+    }
+
+    #[test]
+    fn npm_versions_are_replaced_at_the_top_level() {
+        let text = "{\n  \"name\": \"web\",\n  \"version\" : \"2024.05.1\",\n  \
+                    \"engines\": { \"version\": \"2024.05.1\" }\n}\n";
+
+        assert_eq!(
+            set_npm_version(text, "2024.05.1", "2024.05.2").as_deref(),
+            Some(
+                "{\n  \"name\": \"web\",\n  \"version\" : \"2024.05.2\",\n  \
+                 \"engines\": { \"version\": \"2024.05.1\" }\n}\n"
+            )
+        );
+        assert_eq!(set_npm_version(text, "1.0.0", "1.0.1"), None);
+    }
+}
@@ -0,0 +1,80 @@
+//! API responses kept on disk between runs, so running again the same day
+//! neither waits on hosts nor spends their rate limits. Each response is a
+//! file under a directory per host, and expires with its age.
+
+use crate::config::CacheConfig;
+use crate::http;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    /// `None` when the cache is turned off or there is nowhere to keep it.
+    pub fn new(config: &CacheConfig) -> Option<DiskCache> {
+        if config.ttl_hours == 0 {
+            return None;
+        }
+
+        Some(DiskCache {
+            dir: config.dir.clone().or_else(default_dir)?,
+            ttl: Duration::from_secs(config.ttl_hours * 60 * 60),
+        })
+    }
+
+    /// The response to `url`, unless it is missing or expired.
+    pub fn get(&self, url: &str) -> Option<String> {
+        let path = self.path(url)?;
+        let age = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > self.ttl {
+            return None;
+        }
+
+        fs::read_to_string(path).ok()
+    }
+
+    /// Keeps a response. The cache only saves time, so failing to write it
+    /// is not an error.
+    pub fn put(&self, url: &str, response: &str) {
+        let Some(path) = self.path(url) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(path, response);
+    }
+
+    /// The host's directory, holding the rest of the URL encoded as one file
+    /// name; `None` for a URL too long to be one.
+    fn path(&self, url: &str) -> Option<PathBuf> {
+        let host = http::host(url);
+        let rest = url.split_once(host).map_or(url, |(_, rest)| rest);
+        let name = http::url_encode(rest);
+
+        (!host.is_empty() && name.len() <= 200).then(|| self.dir.join(host).join(name))
+    }
+}
+
+/// `$XDG_CACHE_HOME`, `~/Library/Caches` on macOS or `%LOCALAPPDATA%` on
+/// Windows, falling back to `~/.cache`.
+fn default_dir() -> Option<PathBuf> {
+    let base = if let Some(dir) = std::env::var_os("XDG_CACHE_HOME") {
+        PathBuf::from(dir)
+    } else if cfg!(windows) {
+        PathBuf::from(std::env::var_os("LOCALAPPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Caches")
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".cache")
+    };
+
+    Some(base.join("chenjirogu"))
+}
@@ -0,0 +1,98 @@
+use crate::config::Config;
+use crate::shell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Marks hooks written here, which may be replaced on reinstall; any other
+/// `commit-msg` hook is left alone.
+const MARKER: &str = "# Installed by chenjirogu hooks install";
+
+/// Writes a `commit-msg` hook into each configured git repository that
+/// rejects messages breaking the project's `[convention]`. The hook runs
+/// this executable from the current directory, where it finds the config.
+/// Returns a line per project on what was done.
+pub fn install(config: &Config) -> Result<Vec<String>, String> {
+    let executable = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate the chenjirogu executable: {}", e))?;
+    let config_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to read the working directory: {}", e))?;
+    let mut report = vec![];
+
+    for (name, path) in &config.paths {
+        // Mercurial keeps hooks in its config instead.
+        let Some(hook) = hook_path(path) else {
+            report.push(format!("{}: skipped, not a git repository", name));
+            continue;
+        };
+        if let Ok(existing) = fs::read_to_string(&hook) {
+            if !existing.contains(MARKER) {
+                report.push(format!(
+                    "{}: skipped, {} already exists",
+                    name,
+                    hook.display()
+                ));
+                continue;
+            }
+        }
+
+        if let Some(dir) = hook.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(&hook, script(name, &executable, &config_dir))
+            .map_err(|e| format!("Failed to write {}: {}", hook.display(), e))?;
+        make_executable(&hook)?;
+
+        report.push(format!("{}: installed {}", name, hook.display()));
+    }
+
+    Ok(report)
+}
+
+/// Asks git, which knows about worktrees and `core.hooksPath`.
+fn hook_path(repository: &Path) -> Option<PathBuf> {
+    if repository.join(".hg").is_dir() {
+        return None;
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(["rev-parse", "--git-path", "hooks/commit-msg"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    Some(repository.join(path))
+}
+
+// Git runs hooks with sh on every platform, Git for Windows included.
+// The message file is made absolute before changing to the config's
+// directory.
+fn script(project: &str, executable: &Path, config_dir: &Path) -> String {
+    format!(
+        "#!/bin/sh\n\
+         {}\n\
+         message=$(cd \"$(dirname \"$1\")\" && pwd)/$(basename \"$1\")\n\
+         cd {} && exec {} lint-commits --project {} --message-file \"$message\"\n",
+        MARKER,
+        shell::quote(&config_dir.to_string_lossy()),
+        shell::quote(&executable.to_string_lossy()),
+        shell::quote(project)
+    )
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_: &Path) -> Result<(), String> {
+    Ok(())
+}
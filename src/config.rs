@@ -0,0 +1,786 @@
+use crate::exit::{self, fail};
+use crate::json;
+use crate::remote::Hosts;
+use crate::render::SectionKind;
+use crate::yaml;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// Working copies by project name. `~` stands for the home directory,
+    /// and relative paths are taken from the working directory.
+    pub paths: BTreeMap<String, PathBuf>,
+    pub confluence: Option<ConfluenceConfig>,
+    pub notion: Option<NotionConfig>,
+    #[serde(default)]
+    pub bots: BotsConfig,
+    /// Pull request labels mapped to the section they file a commit under,
+    /// e.g. `"kind/bug" = "bugfixes"`. Used instead of commit prefixes for
+    /// commits enriched with `--enrich-prs`.
+    #[serde(default)]
+    pub labels: BTreeMap<String, SectionKind>,
+    #[serde(default)]
+    pub gitea: GiteaConfig,
+    #[serde(default)]
+    pub bitbucket: BitbucketConfig,
+    pub linear: Option<LinearConfig>,
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub sprint: SprintConfig,
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+    /// Per-project settings, keyed like `[paths]`.
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectConfig>,
+    /// Colors of the text format on a terminal.
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Formats to write on every run when no `--format` is given.
+    #[serde(default)]
+    pub outputs: Vec<OutputConfig>,
+    /// Rewrites applied to entry text so the changelog reads consistently.
+    #[serde(default)]
+    pub normalize: NormalizeConfig,
+    /// Style checks on entries; set to lint every changelog.
+    pub lint: Option<LintConfig>,
+    /// The commit message convention, unless a project sets its own.
+    #[serde(default)]
+    pub convention: ConventionConfig,
+    /// How releases are tagged and numbered. Once set, compare links start
+    /// at the previous release.
+    pub release: Option<ReleaseConfig>,
+    /// Proxy and TLS settings for API requests.
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// Self-hosted instances by domain, e.g. `[hosts."git.corp.example"]`,
+    /// for links and API calls on hosts that cannot be recognized by name.
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostConfig>,
+    /// Where API lookups are kept between runs.
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Rosters of authors by team, e.g. `platform = ["Ana", "Bob"]`, for
+    /// `--team`. Unrelated to the `team` projects are grouped under.
+    #[serde(default)]
+    pub teams: BTreeMap<String, Vec<String>>,
+    /// What `handoff` lists besides fixes and reverts.
+    #[serde(default)]
+    pub oncall: OnCallConfig,
+}
+
+impl Config {
+    /// `[hosts]`, with the older `[gitea] hosts` and `[bitbucket]
+    /// server_hosts` lists folded in.
+    pub fn hosts(&self) -> Hosts {
+        let listed = |hosts: &[String], kind| {
+            hosts
+                .iter()
+                .map(move |host| (host.clone(), HostConfig { kind, api: None }))
+                .collect::<Vec<_>>()
+        };
+        let mut configured: BTreeMap<String, HostConfig> = BTreeMap::new();
+        configured.extend(listed(&self.gitea.hosts, HostKind::Gitea));
+        configured.extend(listed(
+            &self.bitbucket.server_hosts,
+            HostKind::BitbucketServer,
+        ));
+        configured.extend(self.hosts.clone());
+
+        Hosts {
+            configured,
+            http: self.http.clone(),
+        }
+    }
+
+    /// The convention commits of the named project must follow.
+    pub fn convention(&self, project: &str) -> &ConventionConfig {
+        self.projects
+            .get(project)
+            .and_then(|project| project.convention.as_ref())
+            .unwrap_or(&self.convention)
+    }
+
+    /// How the named project's releases are tagged. A workspace package
+    /// like `app/core` falls back to the settings of `app`.
+    pub fn release(&self, project: &str) -> ReleaseConfig {
+        let repository = project.split('/').next().unwrap_or(project);
+        [project, repository]
+            .iter()
+            .find_map(|name| self.projects.get(*name)?.release.clone())
+            .or_else(|| self.release.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Sprint cadence for the `this-sprint` and `last-sprint` ranges.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SprintConfig {
+    /// Length in days.
+    pub length: i64,
+    /// First day of any one sprint, as `YYYY-MM-DD`.
+    pub start: Option<String>,
+}
+
+impl Default for SprintConfig {
+    fn default() -> Self {
+        SprintConfig {
+            length: 14,
+            start: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Days off as `YYYY-MM-DD`, skipped by `--business-days`.
+    pub holidays: Vec<String>,
+    /// Start and end hour of the working day; commits outside it, or on
+    /// weekends, count as after hours in `stats --time-of-day`.
+    pub working_hours: [u32; 2],
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            holidays: vec![],
+            working_hours: [9, 18],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// By default the platform's cache directory, e.g. `~/.cache/chenjirogu`.
+    pub dir: Option<PathBuf>,
+    /// How long a lookup is reused; 0 turns the cache off.
+    pub ttl_hours: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            dir: None,
+            ttl_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct OnCallConfig {
+    /// Keys of incident tickets, e.g. `INC` for `INC-42`, in any case.
+    pub incident_keys: Vec<String>,
+}
+
+impl Default for OnCallConfig {
+    fn default() -> Self {
+        OnCallConfig {
+            incident_keys: vec!["INC".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Group the project is listed under, e.g. the owning team.
+    pub team: Option<String>,
+    /// One line on what the project is, for readers outside the team.
+    pub description: Option<String>,
+    /// Shown before the project name in headings.
+    pub emoji: Option<String>,
+    pub homepage: Option<String>,
+    /// Replaces the top-level `[convention]` for this project.
+    pub convention: Option<ConventionConfig>,
+    /// Replaces the top-level `[release]` for this project.
+    pub release: Option<ReleaseConfig>,
+    /// List each Cargo or npm workspace package the commits touched as a
+    /// project of its own, named `project/package`.
+    pub workspace: bool,
+}
+
+/// How releases are tagged and numbered.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReleaseConfig {
+    /// Tag names, with `{version}` for the version and `{pkg}` for the
+    /// project or workspace package name, e.g. `release/{version}` or
+    /// `{pkg}-v{version}`. Both `v1.2.3` and `1.2.3` are recognized when
+    /// unset, and new tags get the `v`.
+    pub tag_pattern: Option<String>,
+    /// `semver`, or a calendar version format like `YYYY.0M.MICRO` built
+    /// from YYYY, YY, 0Y, MM, 0M, WW, 0W, DD, 0D and MICRO.
+    pub scheme: String,
+    /// Count pre-release tags like `v2.0.0-rc.1` as releases. When off,
+    /// `--since-release` covers everything since the last stable release,
+    /// as the notes of a final release should.
+    pub prereleases: bool,
+}
+
+impl Default for ReleaseConfig {
+    fn default() -> Self {
+        ReleaseConfig {
+            tag_pattern: None,
+            scheme: "semver".to_string(),
+            prereleases: false,
+        }
+    }
+}
+
+/// Which conventional commit subjects `lint-commits` accepts.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ConventionConfig {
+    /// Allowed types, like `feat` in `feat(api): ...`.
+    pub types: Vec<String>,
+    /// Allowed scopes, like `api` in `feat(api): ...`; any when empty.
+    /// Commits without a scope always pass.
+    pub scopes: Vec<String>,
+    /// Group commits with a scope outside `scopes` as `unscoped` in the
+    /// changelog and `hotspots`, instead of under their own scope.
+    pub group_unknown_scopes: bool,
+    /// Longest subject line, in characters.
+    pub max_subject_length: Option<usize>,
+}
+
+impl Default for ConventionConfig {
+    fn default() -> Self {
+        let types = [
+            "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore",
+            "revert", "sec", "security",
+        ];
+        ConventionConfig {
+            types: types.iter().map(|kind| kind.to_string()).collect(),
+            scopes: vec![],
+            group_unknown_scopes: false,
+            max_subject_length: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HostConfig {
+    #[serde(rename = "type")]
+    pub kind: HostKind,
+    /// REST API root, when not where the kind of host keeps it, e.g.
+    /// `https://git.corp.example/api/v3` for GitHub Enterprise.
+    pub api: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKind {
+    GithubEnterprise,
+    Gitlab,
+    Gitea,
+    BitbucketServer,
+}
+
+/// How API requests get through a corporate network. Without a `proxy`,
+/// `HTTPS_PROXY` and `NO_PROXY` from the environment apply.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// e.g. `http://proxy.corp.example:3128`.
+    pub proxy: Option<String>,
+    /// Hosts reached without the proxy, as in `NO_PROXY`.
+    pub no_proxy: Vec<String>,
+    /// PEM file of the certificate authorities to trust instead of the
+    /// system's, e.g. one with an internal root.
+    pub ca_bundle: Option<PathBuf>,
+    /// TLS settings by host name, e.g. `[http.hosts."git.corp.example"]`.
+    pub hosts: BTreeMap<String, TlsConfig>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// Replaces the `[http]` bundle for this host.
+    pub ca_bundle: Option<PathBuf>,
+    /// Skips certificate verification; a last resort for a self-signed
+    /// host on a trusted network.
+    pub insecure: bool,
+    /// PEM certificate and key for hosts that require client certificates.
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct GiteaConfig {
+    /// Self-hosted Gitea/Forgejo hosts, e.g. `git.example.com`. Codeberg and
+    /// hosts with `gitea` or `forgejo` in the name need no entry.
+    pub hosts: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct BitbucketConfig {
+    /// Bitbucket Server hosts whose remotes do not use the `scm/` clone
+    /// path, such as SSH remotes.
+    pub server_hosts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct BotsConfig {
+    /// Drop bot commits unless `--include-bots` is given.
+    pub exclude: bool,
+    /// Extra author names to treat as bots, besides any `*[bot]` author.
+    pub authors: Vec<String>,
+    /// Keep excluded bots' dependency bumps, rolled up into a
+    /// Dependencies section.
+    pub rollup: bool,
+}
+
+impl Default for BotsConfig {
+    fn default() -> Self {
+        BotsConfig {
+            exclude: true,
+            authors: vec![],
+            rollup: false,
+        }
+    }
+}
+
+impl BotsConfig {
+    pub fn is_bot(&self, author_name: &str) -> bool {
+        author_name.ends_with("[bot]") || self.authors.iter().any(|a| a == author_name)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinearConfig {
+    /// Workspace slug from `linear.app/<workspace>`.
+    pub workspace: String,
+    /// Team keys such as `ENG`. Listing them avoids false positives like
+    /// `UTF-8` and also links lowercase ids from branch names.
+    #[serde(default)]
+    pub teams: Vec<String>,
+}
+
+/// Styles for each part of the text format, e.g. `features = "bold green"`;
+/// see `render::Theme`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub title: Option<String>,
+    pub group: Option<String>,
+    pub project: Option<String>,
+    pub about: Option<String>,
+    pub security: Option<String>,
+    pub features: Option<String>,
+    pub bugfixes: Option<String>,
+    pub dependencies: Option<String>,
+    pub link: Option<String>,
+}
+
+/// An `[[outputs]]` entry, e.g. `format = "html"` and
+/// `path = "public/changelog.html"`.
+#[derive(Debug, Deserialize)]
+pub struct OutputConfig {
+    /// A `--format` name.
+    pub format: String,
+    /// Defaults to `changelog.<ext>`, or the `changelog` directory for
+    /// mdbook and `--split-per-project`.
+    pub path: Option<PathBuf>,
+}
+
+/// Entry text rewrites; see `render::Normalize`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NormalizeConfig {
+    /// Applied in order, before the switches below.
+    pub rules: Vec<RewriteRule>,
+    /// Upper-case the first letter of each entry.
+    pub capitalize: bool,
+    /// Drop a single trailing `.` from each entry.
+    pub strip_trailing_period: bool,
+}
+
+/// A `[[normalize.rules]]` entry, e.g. `find = "^[A-Z]+-\\d+:\\s*"` to strip
+/// ticket prefixes. `replace` may refer to groups as `$1` and defaults to
+/// removing the match.
+#[derive(Debug, Deserialize)]
+pub struct RewriteRule {
+    pub find: String,
+    #[serde(default)]
+    pub replace: String,
+}
+
+/// Entry style checks; see `lint::lint`. Entries starting lowercase are
+/// always flagged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Longest entry, in characters.
+    pub max_length: Option<usize>,
+    /// Words and phrases that must not appear, matched case-insensitively,
+    /// e.g. `["WIP", "hack"]`.
+    pub banned_words: Vec<String>,
+    /// Misspellings and their corrections, e.g. `teh = "the"`, which
+    /// `--fix` applies.
+    pub corrections: BTreeMap<String, String>,
+}
+
+/// A `[[plugins]]` entry: a command that transforms the collected projects
+/// before rendering.
+#[derive(Debug, Deserialize)]
+pub struct PluginConfig {
+    pub command: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Run before collecting, e.g. `git pull` in every project.
+    pub pre: Vec<Hook>,
+    /// Run after the changelog is written or published, e.g. `pandoc`.
+    pub post: Vec<Hook>,
+}
+
+/// A shell command run around generation, given as a string or as a table
+/// with options.
+#[derive(Debug, Deserialize)]
+#[serde(from = "HookSpec")]
+pub struct Hook {
+    pub command: String,
+    pub on_failure: FailurePolicy,
+    /// Run once in each project's directory instead of once overall.
+    pub per_project: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HookSpec {
+    Command(String),
+    Table {
+        command: String,
+        #[serde(default)]
+        on_failure: FailurePolicy,
+        #[serde(default)]
+        per_project: bool,
+    },
+}
+
+impl From<HookSpec> for Hook {
+    fn from(spec: HookSpec) -> Self {
+        match spec {
+            HookSpec::Command(command) => Hook {
+                command,
+                on_failure: FailurePolicy::default(),
+                per_project: false,
+            },
+            HookSpec::Table {
+                command,
+                on_failure,
+                per_project,
+            } => Hook {
+                command,
+                on_failure,
+                per_project,
+            },
+        }
+    }
+}
+
+/// What a failing hook does to the run.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailurePolicy {
+    #[default]
+    Abort,
+    Warn,
+    Ignore,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfluenceConfig {
+    /// Site root, e.g. `https://example.atlassian.net/wiki`.
+    pub base_url: String,
+    pub space: String,
+    /// Page the changelog is created under the first time it is published.
+    pub parent_id: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotionConfig {
+    /// Page the changelog blocks are appended to.
+    pub page_id: Option<String>,
+    /// Database a new changelog page is created in; takes precedence over
+    /// `page_id`.
+    pub database_id: Option<String>,
+    /// Name of the database's title property, `Name` by default.
+    pub title_property: Option<String>,
+}
+
+/// Config files looked for in the working directory, in order. The
+/// extension picks the parser; the schema is the same for all of them.
+const CONFIG_FILES: [&str; 4] = ["config.toml", "config.yaml", "config.yml", "config.json"];
+
+/// Builds the effective config from, lowest precedence first: the config
+/// file, `CHENJIROGU_*` environment variables and `--set key=value`
+/// arguments. Environment variables nest with `__`, so
+/// `CHENJIROGU_BOTS__EXCLUDE=false` sets `bots.exclude`; `--set` takes a
+/// dotted path. Values are read as TOML (`false`, `3`, `["a"]`), falling
+/// back to a plain string.
+pub fn read_config(overrides: &[String]) -> Config {
+    let path = CONFIG_FILES.iter().find(|path| Path::new(path).exists());
+
+    // Without a file the overrides alone can make up the config.
+    let mut value = match path {
+        Some(path) => load(Path::new(path), &mut vec![]).unwrap_or_else(|e| fail(exit::CONFIG, e)),
+        None if overrides.is_empty() && env_overrides().next().is_none() => fail(
+            exit::CONFIG,
+            format!(
+                "Failed to open config file: none of {} found",
+                CONFIG_FILES.join(", ")
+            ),
+        ),
+        None => Value::Table(Default::default()),
+    };
+
+    for (key, raw) in env_overrides() {
+        let path: Vec<&str> = key.split("__").collect();
+        set(&mut value, &path, override_value(&raw), true);
+    }
+
+    for assignment in overrides {
+        let (key, raw) = assignment.split_once('=').unwrap_or_else(|| {
+            fail(
+                exit::CONFIG,
+                format!("Invalid --set {}: expected key=value", assignment),
+            )
+        });
+        let path: Vec<&str> = key.split('.').collect();
+        set(&mut value, &path, override_value(raw), false);
+    }
+
+    let mut config: Config = value.try_into().unwrap_or_else(|e| {
+        fail(
+            exit::CONFIG,
+            format!("Invalid config {}: {}", path.unwrap_or(&"overrides"), e),
+        )
+    });
+    for path in config.paths.values_mut() {
+        *path = expand_home(path);
+    }
+    let files = config.http.hosts.values_mut().flat_map(|tls| {
+        [
+            &mut tls.ca_bundle,
+            &mut tls.client_cert,
+            &mut tls.client_key,
+        ]
+    });
+    let files = files.chain([&mut config.http.ca_bundle, &mut config.cache.dir]);
+    for path in files.flatten() {
+        *path = expand_home(path);
+    }
+    config
+}
+
+/// `CHENJIROGU_*` variables as config keys, in the case they were given,
+/// and raw values.
+fn env_overrides() -> impl Iterator<Item = (String, String)> {
+    std::env::vars().filter_map(|(name, value)| {
+        let key = name.strip_prefix("CHENJIROGU_")?;
+        Some((key.to_string(), value))
+    })
+}
+
+fn override_value(raw: &str) -> Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Sets the value at `path`, creating tables on the way. With
+/// `fold_case`, for environment variables that cannot spell a key's case,
+/// each key matches an existing one in any case and is lowercased
+/// otherwise, as every key of the schema is.
+fn set(value: &mut Value, path: &[&str], leaf: Value, fold_case: bool) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+
+    let mut table = value;
+    for key in parents {
+        if !table.is_table() {
+            *table = Value::Table(Default::default());
+        }
+        let table_mut = table.as_table_mut().expect("made a table above");
+        let key = existing_key(table_mut, key, fold_case);
+        table = table_mut
+            .entry(key)
+            .or_insert_with(|| Value::Table(Default::default()));
+    }
+
+    if !table.is_table() {
+        *table = Value::Table(Default::default());
+    }
+    let table = table.as_table_mut().expect("made a table above");
+    let last = existing_key(table, last, fold_case);
+    table.insert(last, leaf);
+}
+
+fn existing_key(table: &toml::Table, key: &str, fold_case: bool) -> String {
+    if !fold_case || table.contains_key(key) {
+        return key.to_string();
+    }
+    table
+        .keys()
+        .find(|existing| existing.eq_ignore_ascii_case(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_lowercase())
+}
+
+/// Reads a config file with the files it includes merged in. `include`
+/// lists paths relative to the including file, or to the home directory
+/// with `~/`. Included files come first, so the including file wins on
+/// conflicting keys; arrays such as `plugins` are concatenated.
+fn load(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Value, String> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        return Err(format!("{} is included in a cycle", path.display()));
+    }
+
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to open config file {}: {}", path.display(), e))?;
+    let mut value =
+        parse(path, &text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+    let includes = match &mut value {
+        Value::Table(table) => table.remove("include"),
+        _ => None,
+    };
+    let includes: Vec<String> = match includes {
+        None => vec![],
+        Some(includes) => includes
+            .try_into()
+            .map_err(|_| format!("{}: include must be a list of paths", path.display()))?,
+    };
+
+    stack.push(canonical);
+    let mut merged = Value::Table(Default::default());
+    for include in includes {
+        let include = resolve(path, &include);
+        merge(&mut merged, load(&include, stack)?);
+    }
+    stack.pop();
+
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+fn resolve(including: &Path, include: &str) -> PathBuf {
+    // Joining an absolute path, such as one under the home directory,
+    // replaces the base.
+    including
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(expand_home(Path::new(include)))
+}
+
+/// Replaces a leading `~/`, or `~\` as typed on Windows, with the home
+/// directory.
+fn expand_home(path: &Path) -> PathBuf {
+    let text = path.to_string_lossy();
+    let rest = text.strip_prefix("~/").or_else(|| text.strip_prefix("~\\"));
+
+    match (
+        rest,
+        std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")),
+    ) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(overlay)) => base.extend(overlay),
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn parse(path: &Path, text: &str) -> Result<Value, String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => yaml::parse(text),
+        Some("json") => json::parse(text),
+        _ => toml::from_str(text).map_err(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(text: &str) -> Value {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn environment_keys_match_existing_keys_in_any_case() {
+        let mut value = config("[teams]\nPlatform = [\"Ana\"]\n");
+
+        set(
+            &mut value,
+            &["TEAMS", "PLATFORM"],
+            override_value("[\"Bob\"]"),
+            true,
+        );
+
+        assert_eq!(value["teams"]["Platform"], config("x = [\"Bob\"]")["x"]);
+        assert_eq!(value.as_table().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn new_environment_keys_are_lowercased() {
+        let mut value = config("");
+
+        set(
+            &mut value,
+            &["BOTS", "EXCLUDE"],
+            override_value("false"),
+            true,
+        );
+
+        assert_eq!(value["bots"]["exclude"], Value::Boolean(false));
+    }
+
+    #[test]
+    fn set_arguments_keep_their_case() {
+        let mut value = config("[paths]\nweb = \"/src/web\"\n");
+
+        set(
+            &mut value,
+            &["paths", "Web"],
+            override_value("/src/Web"),
+            false,
+        );
+
+        assert_eq!(value["paths"].as_table().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::config::{ConfluenceConfig, HttpConfig};
+use crate::http::{self, basic_auth, url_encode};
+use crate::json;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct SearchResults {
+    results: Vec<Page>,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    id: String,
+    version: Option<Version>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Version {
+    number: i64,
+}
+
+#[derive(Serialize)]
+struct PageRequest<'a> {
+    #[serde(rename = "type")]
+    kind: &'a str,
+    title: &'a str,
+    space: Space<'a>,
+    body: Body<'a>,
+    ancestors: Option<Vec<Ancestor<'a>>>,
+    version: Option<Version>,
+}
+
+#[derive(Serialize)]
+struct Space<'a> {
+    key: &'a str,
+}
+
+#[derive(Serialize)]
+struct Body<'a> {
+    storage: Storage<'a>,
+}
+
+#[derive(Serialize)]
+struct Storage<'a> {
+    value: &'a str,
+    representation: &'a str,
+}
+
+#[derive(Serialize)]
+struct Ancestor<'a> {
+    id: &'a str,
+}
+
+/// Creates the changelog page, or updates it in place when a page with the
+/// same title already exists in the space. `CONFLUENCE_TOKEN` is sent as a
+/// bearer token, or with `CONFLUENCE_USER` as basic auth for Atlassian Cloud.
+pub fn publish(
+    config: &ConfluenceConfig,
+    http_config: &HttpConfig,
+    storage: &str,
+) -> Result<(), String> {
+    let token =
+        std::env::var("CONFLUENCE_TOKEN").map_err(|_| "CONFLUENCE_TOKEN is not set".to_string())?;
+    let authorization = match std::env::var("CONFLUENCE_USER") {
+        Ok(user) => format!("Authorization: Basic {}", basic_auth(&user, &token)),
+        Err(_) => format!("Authorization: Bearer {}", token),
+    };
+    let headers = [authorization];
+
+    let base_url = config.base_url.trim_end_matches('/');
+    let title = config.title.as_deref().unwrap_or("Changelog");
+
+    let search = http::request(
+        http_config,
+        "GET",
+        &format!(
+            "{}/rest/api/content?spaceKey={}&title={}&expand=version",
+            base_url,
+            url_encode(&config.space),
+            url_encode(title)
+        ),
+        &headers,
+        None,
+    )?;
+    let search: SearchResults = json::from_str(&search)?;
+    let existing = search.results.into_iter().next();
+
+    let mut page = PageRequest {
+        kind: "page",
+        title,
+        space: Space { key: &config.space },
+        body: Body {
+            storage: Storage {
+                value: storage,
+                representation: "storage",
+            },
+        },
+        ancestors: None,
+        version: None,
+    };
+
+    match existing {
+        Some(existing) => {
+            page.version = Some(Version {
+                number: existing.version.map_or(1, |v| v.number) + 1,
+            });
+
+            http::request(
+                http_config,
+                "PUT",
+                &format!("{}/rest/api/content/{}", base_url, existing.id),
+                &headers,
+                Some(&json::to_string(&page)?),
+            )?;
+        }
+        None => {
+            page.ancestors = config.parent_id.as_deref().map(|id| vec![Ancestor { id }]);
+
+            http::request(
+                http_config,
+                "POST",
+                &format!("{}/rest/api/content", base_url),
+                &headers,
+                Some(&json::to_string(&page)?),
+            )?;
+        }
+    }
+
+    Ok(())
+}
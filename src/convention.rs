@@ -0,0 +1,122 @@
+use crate::config::ConventionConfig;
+
+/// A conventional commit subject, `type(scope)!: description`.
+pub struct Subject<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub description: &'a str,
+}
+
+impl<'a> Subject<'a> {
+    /// `None` unless the line starts with a lowercase type, an optional
+    /// `(scope)` and `!`, and `: `.
+    pub fn parse(line: &'a str) -> Option<Subject<'a>> {
+        let (prefix, description) = line.split_once(": ")?;
+        let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+        let (kind, scope) = match prefix.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+            None => (prefix, None),
+        };
+
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase()) {
+            return None;
+        }
+
+        Some(Subject {
+            kind,
+            scope: scope.filter(|scope| !scope.is_empty()),
+            description,
+        })
+    }
+}
+
+/// How the subject line of `message` breaks the convention; empty when it
+/// follows it. Subjects git writes itself, for merges and reverts, pass.
+pub fn check(message: &str, convention: &ConventionConfig) -> Vec<String> {
+    let mut problems = vec![];
+    let subject = message.lines().next().unwrap_or_default();
+
+    if subject.starts_with("Merge ") || subject.starts_with("Revert \"") {
+        return problems;
+    }
+
+    match Subject::parse(subject) {
+        None => problems.push("not in type(scope): description form".to_string()),
+        Some(parsed) => {
+            if !convention.types.iter().any(|kind| kind == parsed.kind) {
+                problems.push(format!(
+                    "unknown type {}, expected one of {}",
+                    parsed.kind,
+                    convention.types.join(", ")
+                ));
+            }
+            if let Some(scope) = unknown_scope(subject, convention) {
+                problems.push(format!(
+                    "unknown scope {}, expected one of {}",
+                    scope,
+                    convention.scopes.join(", ")
+                ));
+            }
+            if parsed.description.trim().is_empty() {
+                problems.push("no description".to_string());
+            }
+        }
+    }
+
+    if let Some(max) = convention.max_subject_length {
+        let length = subject.chars().count();
+        if length > max {
+            problems.push(format!("subject is {} characters, over {}", length, max));
+        }
+    }
+
+    problems
+}
+
+/// The scope of a conventional subject when `[convention] scopes` does not
+/// list it.
+pub fn unknown_scope<'a>(message: &'a str, convention: &ConventionConfig) -> Option<&'a str> {
+    let scope = Subject::parse(message)?.scope?;
+    (!convention.scopes.is_empty() && !convention.scopes.iter().any(|known| known == scope))
+        .then_some(scope)
+}
+
+/// A message as git will record it from the commit-msg hook's file, without
+/// the `#` help lines and anything below the `git commit -v` scissors.
+pub fn strip_comments(message: &str) -> String {
+    message
+        .lines()
+        .take_while(|line| !line.starts_with("# ------------------------ >8"))
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What the commit-msg hook runs on each message, with the convention a
+    // project gets without any `[convention]`.
+    #[test]
+    fn default_convention_accepts_security_subjects() {
+        let convention = ConventionConfig::default();
+
+        assert!(check("security: escape titles in the feed", &convention).is_empty());
+        assert!(check(
+            "sec(auth): expire reset tokens\n\nCVE-2024-1234",
+            &convention
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn default_convention_rejects_unknown_types() {
+        let problems = check("oops: something", &ConventionConfig::default());
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("unknown type oops"));
+    }
+}
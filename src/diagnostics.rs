@@ -0,0 +1,128 @@
+//! Problems that do not stop a run but leave something out of its result,
+//! collected as projects are processed and reported together once the run
+//! is done. Data exports carry them too, so a pipeline can tell a quiet
+//! changelog from an incomplete one.
+
+use crate::convention::Subject;
+use crate::model::ProjectList;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(transparent)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    /// Commits left out because their subjects are not conventional.
+    UnparseableSubject,
+    /// A project without a remote, whose entries cannot be linked.
+    MissingRemote,
+    /// A project folded into another it is a clone of, or left out as
+    /// every one of its commits is listed under another.
+    SkippedProject,
+    /// A repository left out as its history could not be read.
+    SkippedRepository,
+    /// Commits left out as already listed under another project.
+    DuplicateCommits,
+    /// Pull requests that could not be looked up.
+    Enrichment,
+}
+
+impl Diagnostics {
+    pub fn push(&mut self, kind: DiagnosticKind, project: &str, message: String) {
+        self.0.push(Diagnostic {
+            kind,
+            project: Some(project.to_string()),
+            message,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[Diagnostic] {
+        &self.0
+    }
+
+    /// Every diagnostic, one per line under a count; empty without any.
+    pub fn report(&self) -> String {
+        if self.0.is_empty() {
+            return String::new();
+        }
+
+        let mut report = match self.0.len() {
+            1 => "1 warning:\n".to_string(),
+            count => format!("{} warnings:\n", count),
+        };
+        for diagnostic in &self.0 {
+            report.push_str(&format!("  {}\n", diagnostic));
+        }
+        report
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.project {
+            Some(project) => write!(f, "{}: {}", project, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Notes each project's commits that no section takes because their
+/// subjects are not conventional commits. With `labelled`, commits with a
+/// pull request are filed by its labels instead, so they are not counted.
+pub fn check_subjects(project_list: &mut ProjectList, labelled: bool) {
+    let mut found = vec![];
+    for project in &project_list.projects {
+        let unparseable: Vec<_> = project
+            .commits
+            .iter()
+            .filter(|commit| {
+                let filed = commit.is_skipped()
+                    || commit.is_security_fix()
+                    || commit.dependency_bump().is_some()
+                    || (labelled && commit.pull_request.is_some());
+                !filed && Subject::parse(&commit.message).is_none()
+            })
+            .collect();
+        let Some(first) = unparseable.first() else {
+            continue;
+        };
+
+        let example = format!(
+            "{} {:?}",
+            first.hash.get(..8).unwrap_or(&first.hash),
+            first.message
+        );
+        let message = match unparseable.len() {
+            1 => format!(
+                "left out {}, whose subject is not a conventional commit",
+                example
+            ),
+            count => format!(
+                "left out {} commits whose subjects are not conventional commits, e.g. {}",
+                count, example
+            ),
+        };
+        found.push((project.name.clone(), message));
+    }
+
+    for (project, message) in found {
+        project_list
+            .diagnostics
+            .push(DiagnosticKind::UnparseableSubject, &project, message);
+    }
+}
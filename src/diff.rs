@@ -0,0 +1,67 @@
+use crate::model::{Commit, ProjectList};
+use std::collections::{BTreeMap, HashSet};
+
+/// Lists, per project, the commits present in only one of two exports.
+/// Returns the report and whether the exports differ at all.
+pub fn diff(old: &ProjectList, new: &ProjectList) -> (String, bool) {
+    let mut projects: BTreeMap<&str, (Vec<&Commit>, Vec<&Commit>)> = BTreeMap::new();
+
+    for project in &old.projects {
+        projects
+            .entry(&project.name)
+            .or_default()
+            .0
+            .extend(&project.commits);
+    }
+    for project in &new.projects {
+        projects
+            .entry(&project.name)
+            .or_default()
+            .1
+            .extend(&project.commits);
+    }
+
+    let mut report = String::new();
+    let mut added_total = 0;
+    let mut removed_total = 0;
+
+    for (name, (old_commits, new_commits)) in &projects {
+        let old_hashes: HashSet<&str> = old_commits.iter().map(|c| c.hash.as_str()).collect();
+        let new_hashes: HashSet<&str> = new_commits.iter().map(|c| c.hash.as_str()).collect();
+
+        let added: Vec<_> = new_commits
+            .iter()
+            .filter(|c| !old_hashes.contains(c.hash.as_str()))
+            .collect();
+        let removed: Vec<_> = old_commits
+            .iter()
+            .filter(|c| !new_hashes.contains(c.hash.as_str()))
+            .collect();
+
+        if added.is_empty() && removed.is_empty() {
+            continue;
+        }
+
+        report.push_str(&format!("{}\n", name));
+        for commit in &added {
+            report.push_str(&format!("  + {} {}\n", short(&commit.hash), commit.message));
+        }
+        for commit in &removed {
+            report.push_str(&format!("  - {} {}\n", short(&commit.hash), commit.message));
+        }
+
+        added_total += added.len();
+        removed_total += removed.len();
+    }
+
+    report.push_str(&format!(
+        "{} added, {} removed\n",
+        added_total, removed_total
+    ));
+
+    (report, added_total + removed_total > 0)
+}
+
+fn short(hash: &str) -> &str {
+    hash.get(0..8).unwrap_or(hash)
+}
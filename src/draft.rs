@@ -0,0 +1,199 @@
+//! Release notes as an editable file of entries. `--draft` writes one in
+//! place of the changelog; once its wording is polished and unwanted
+//! entries are deleted, `finalize` renders it like collected data.
+
+use crate::diagnostics::Diagnostics;
+use crate::model::{Commit, Project, ProjectList};
+use crate::render::{self, RenderOptions, SectionKind};
+use crate::{json, yaml};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize)]
+pub struct Draft {
+    /// The date the changelog is for, RFC 3339.
+    pub generated_at: String,
+    projects: Vec<DraftProject>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DraftProject {
+    name: String,
+    remote: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    team: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    emoji: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+    sections: Vec<DraftSection>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DraftSection {
+    kind: SectionKind,
+    entries: Vec<DraftEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DraftEntry {
+    /// The text as it will read; for dependencies, the package.
+    message: String,
+    /// The commit the entry links to.
+    hash: String,
+    date: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    breaking_change: Option<String>,
+    /// The versions of a dependency update.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    to: String,
+}
+
+impl Draft {
+    /// The entries each project's changelog would list.
+    pub fn new(projects: &ProjectList, options: &RenderOptions) -> Draft {
+        let projects = projects
+            .projects
+            .iter()
+            .map(|project| DraftProject {
+                name: project.name.clone(),
+                remote: project.remote.clone(),
+                team: project.team.clone(),
+                description: project.description.clone(),
+                emoji: project.emoji.clone(),
+                homepage: project.homepage.clone(),
+                sections: render::sections(project, options)
+                    .sections
+                    .into_iter()
+                    .map(|section| DraftSection {
+                        kind: section.kind,
+                        entries: section.entries.iter().map(entry).collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Draft {
+            generated_at: options.generated_at.to_rfc3339(),
+            projects,
+        }
+    }
+
+    /// YAML for a `.yaml` or `.yml` path, TOML otherwise.
+    pub fn to_string(&self, path: &str) -> Result<String, String> {
+        if is_yaml(path) {
+            yaml::to_string(self)
+        } else {
+            toml::to_string_pretty(self).map_err(|e| e.to_string())
+        }
+    }
+
+    pub fn parse(text: &str, path: &str) -> Result<Draft, String> {
+        if is_yaml(path) {
+            yaml::from_str(text)
+        } else if path.ends_with(".json") {
+            json::from_str(text)
+        } else {
+            toml::from_str(text).map_err(|e| e.to_string())
+        }
+    }
+
+    /// Projects whose commits render as the draft's entries: each gets a
+    /// subject that files it under its section again.
+    pub fn into_projects(self) -> ProjectList {
+        let projects = self
+            .projects
+            .into_iter()
+            .map(|project| Project {
+                name: project.name,
+                path: PathBuf::new(),
+                commits: project
+                    .sections
+                    .into_iter()
+                    .flat_map(|section| {
+                        let kind = section.kind;
+                        section.entries.into_iter().map(move |e| commit(kind, e))
+                    })
+                    .collect(),
+                remote: project.remote,
+                team: project.team,
+                description: project.description,
+                emoji: project.emoji,
+                homepage: project.homepage,
+                version: None,
+                anchor_group: None,
+            })
+            .collect();
+
+        ProjectList {
+            projects,
+            diagnostics: Diagnostics::default(),
+        }
+    }
+}
+
+fn entry(entry: &render::Entry) -> DraftEntry {
+    let (message, from, to) = match &entry.bump {
+        Some(bump) => (bump.package.to_string(), bump.from, bump.to),
+        None => (entry.message.to_string(), "", ""),
+    };
+
+    DraftEntry {
+        message,
+        hash: entry.commit.hash.clone(),
+        date: entry.commit.date.clone(),
+        breaking_change: entry.breaking_change.clone(),
+        from: from.to_string(),
+        to: to.to_string(),
+    }
+}
+
+fn commit(kind: SectionKind, entry: DraftEntry) -> Commit {
+    // Subjects with a second `: ` are not filed under a section, so the
+    // text is kept whole in a trailer instead.
+    let subject = entry.message.replace(": ", " - ");
+    let message = match kind {
+        SectionKind::Dependencies if entry.to.is_empty() => {
+            format!("chore(deps): {}", entry.message)
+        }
+        SectionKind::Dependencies if entry.from.is_empty() => {
+            format!("chore(deps): update {} to {}", entry.message, entry.to)
+        }
+        SectionKind::Dependencies => format!(
+            "chore(deps): bump {} from {} to {}",
+            entry.message, entry.from, entry.to
+        ),
+        SectionKind::Security => format!("security: {}", subject),
+        SectionKind::BugFixes => format!("fix: {}", subject),
+        SectionKind::Features => format!("feat: {}", subject),
+    };
+
+    let mut body = vec![];
+    if let Some(note) = entry.breaking_change {
+        body.push(format!("BREAKING CHANGE: {}", note));
+    }
+    if subject != entry.message && kind != SectionKind::Dependencies {
+        body.push(format!("Changelog: {}", entry.message));
+    }
+
+    Commit {
+        hash: entry.hash,
+        message,
+        author_name: Arc::from(""),
+        author_email: Arc::from(""),
+        date: entry.date,
+        committer_date: String::new(),
+        body: body.join("\n\n"),
+        pull_request: None,
+        files: vec![],
+    }
+}
+
+fn is_yaml(path: &str) -> bool {
+    path.ends_with(".yaml") || path.ends_with(".yml")
+}
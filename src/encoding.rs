@@ -0,0 +1,24 @@
+/// Windows-1252 characters for bytes 0x80 to 0x9F, where Latin-1 only has
+/// control characters; unassigned bytes keep their Latin-1 meaning.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// Text as UTF-8 when it is valid UTF-8, and otherwise as Windows-1252, a
+/// superset of Latin-1. Commits made by tools that wrote Latin-1 without
+/// recording an encoding pass through git unconverted, and every byte
+/// sequence is valid Windows-1252, so names like "Muñoz" come out right
+/// instead of as replacement characters.
+pub fn decode(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9f => WINDOWS_1252[(byte - 0x80) as usize],
+                _ => byte as char,
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,303 @@
+use crate::cache::DiskCache;
+use crate::config::HttpConfig;
+use crate::http;
+use crate::json;
+use crate::model::{Project, PullRequest};
+use crate::remote::{parse_remote, AzureRepo, BitbucketRepo, Hosts, Remote};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// How many lookups are in flight at once.
+const CONCURRENCY: usize = 8;
+
+/// Responses by URL, shared by the projects of a run so that a repository
+/// collected twice, such as a mirror, is looked up once, and kept on disk
+/// for the next runs.
+pub struct Cache {
+    responses: Mutex<HashMap<String, String>>,
+    disk: Option<DiskCache>,
+}
+
+impl Cache {
+    pub fn new(disk: Option<DiskCache>) -> Cache {
+        Cache {
+            responses: Mutex::new(HashMap::new()),
+            disk,
+        }
+    }
+
+    fn get(&self, url: &str) -> Option<String> {
+        let response = self.responses.lock().unwrap().get(url).cloned();
+        response.or_else(|| self.disk.as_ref()?.get(url))
+    }
+
+    fn put(&self, url: &str, response: &str, fetched: bool) {
+        if let Some(disk) = self.disk.as_ref().filter(|_| fetched) {
+            disk.put(url, response);
+        }
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), response.to_string());
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubPull {
+    number: u64,
+    title: String,
+    html_url: String,
+    #[serde(default)]
+    labels: Vec<GithubLabel>,
+}
+
+#[derive(Deserialize)]
+struct GithubLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BitbucketPage {
+    values: Vec<BitbucketPull>,
+}
+
+#[derive(Deserialize)]
+struct BitbucketPull {
+    id: u64,
+    title: String,
+}
+
+#[derive(Serialize)]
+struct AzureQueryRequest<'a> {
+    queries: Vec<AzureQuery<'a>>,
+}
+
+#[derive(Serialize)]
+struct AzureQuery<'a> {
+    #[serde(rename = "type")]
+    kind: &'a str,
+    items: &'a [&'a str],
+}
+
+#[derive(Deserialize)]
+struct AzureQueryResponse {
+    /// One map per query, from commit to the pull requests it matched.
+    results: Vec<BTreeMap<String, Vec<AzurePull>>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzurePull {
+    pull_request_id: u64,
+    title: String,
+    #[serde(default)]
+    labels: Vec<AzureLabel>,
+}
+
+#[derive(Deserialize)]
+struct AzureLabel {
+    name: String,
+}
+
+/// Looks up the pull request each commit was merged through on GitHub,
+/// Azure DevOps or Bitbucket; commits that were pushed directly keep no
+/// pull request.
+pub fn enrich(project: &mut Project, hosts: &Hosts, cache: &Cache) -> Result<(), String> {
+    let remote = parse_remote(&project.remote)
+        .ok_or_else(|| format!("unrecognized remote {}", project.remote))?;
+
+    if let Some(azure) = remote.azure_repo() {
+        enrich_azure(project, &azure, &hosts.http)
+    } else if let Some(bitbucket) = remote.bitbucket_repo(hosts) {
+        enrich_bitbucket(project, &bitbucket, &hosts.http, cache)
+    } else if remote.is_github(hosts) {
+        enrich_github(project, &remote, hosts, cache)
+    } else {
+        Err(format!(
+            "pull request lookup on {} is not supported",
+            remote.host
+        ))
+    }
+}
+
+fn enrich_github(
+    project: &mut Project,
+    remote: &Remote,
+    hosts: &Hosts,
+    cache: &Cache,
+) -> Result<(), String> {
+    let headers = [
+        format!(
+            "Authorization: Bearer {}",
+            http::github_token(&remote.host)?
+        ),
+        "Accept: application/vnd.github+json".to_string(),
+    ];
+    let api = remote.github_api(hosts);
+    let urls: Vec<String> = project
+        .commits
+        .iter()
+        .map(|commit| {
+            format!(
+                "{}/repos/{}/commits/{}/pulls",
+                api, remote.path, commit.hash
+            )
+        })
+        .collect();
+    let responses = get_all(&urls, &headers, &hosts.http, cache)?;
+
+    for (commit, response) in project.commits.iter_mut().zip(responses) {
+        let pulls: Vec<GithubPull> = json::from_str(&response)?;
+
+        commit.pull_request = pulls.into_iter().next().map(|pull| PullRequest {
+            number: pull.number,
+            title: pull.title,
+            url: pull.html_url,
+            labels: pull.labels.into_iter().map(|label| label.name).collect(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Azure DevOps answers for all commits in one request: first by the merge
+/// commit a pull request completed as, then by the commits it contained.
+fn enrich_azure(
+    project: &mut Project,
+    azure: &AzureRepo,
+    http_config: &HttpConfig,
+) -> Result<(), String> {
+    if project.commits.is_empty() {
+        return Ok(());
+    }
+
+    let hashes: Vec<&str> = project.commits.iter().map(|c| c.hash.as_str()).collect();
+    let request = AzureQueryRequest {
+        queries: vec![
+            AzureQuery {
+                kind: "lastMergeCommit",
+                items: &hashes,
+            },
+            AzureQuery {
+                kind: "commit",
+                items: &hashes,
+            },
+        ],
+    };
+
+    let response = http::request(
+        http_config,
+        "POST",
+        &format!(
+            "{}/_apis/git/repositories/{}/pullrequestquery?api-version=7.0",
+            azure.project_url(),
+            azure.repository
+        ),
+        &[http::azure_authorization()?],
+        Some(&json::to_string(&request)?),
+    )?;
+    let mut response: AzureQueryResponse = json::from_str(&response)?;
+
+    for commit in &mut project.commits {
+        let pull = response
+            .results
+            .iter_mut()
+            .find_map(|results| results.remove(&commit.hash)?.into_iter().next());
+
+        commit.pull_request = pull.map(|pull| PullRequest {
+            number: pull.pull_request_id,
+            title: pull.title,
+            url: azure.pull_request_url(pull.pull_request_id),
+            labels: pull.labels.into_iter().map(|label| label.name).collect(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Bitbucket has no labels, so its pull requests only contribute their
+/// number, title and link.
+fn enrich_bitbucket(
+    project: &mut Project,
+    bitbucket: &BitbucketRepo,
+    http_config: &HttpConfig,
+    cache: &Cache,
+) -> Result<(), String> {
+    let headers = [http::bitbucket_authorization()?];
+    let urls: Vec<String> = project
+        .commits
+        .iter()
+        .map(|commit| bitbucket.commit_pull_requests_api(&commit.hash))
+        .collect();
+    let responses = get_all(&urls, &headers, http_config, cache)?;
+
+    for (commit, response) in project.commits.iter_mut().zip(responses) {
+        let page: BitbucketPage = json::from_str(&response)?;
+
+        commit.pull_request = page.values.into_iter().next().map(|pull| PullRequest {
+            number: pull.id,
+            title: pull.title,
+            url: format!("{}/pull-requests/{}", bitbucket.repository_url(), pull.id),
+            labels: vec![],
+        });
+    }
+
+    Ok(())
+}
+
+/// GETs every URL, up to `CONCURRENCY` at a time, returning the responses
+/// in order. The first failure stops the lookups not yet started.
+fn get_all(
+    urls: &[String],
+    headers: &[String],
+    http_config: &HttpConfig,
+    cache: &Cache,
+) -> Result<Vec<String>, String> {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let responses = Mutex::new(vec![None; urls.len()]);
+    let error = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for _ in 0..CONCURRENCY.min(urls.len()) {
+            scope.spawn(|| {
+                while !failed.load(Ordering::Relaxed) {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(url) = urls.get(i) else {
+                        break;
+                    };
+
+                    let cached = cache.get(url);
+                    let fetched = cached.is_none();
+                    let response = match cached {
+                        Some(response) => Ok(response),
+                        None => http::request(http_config, "GET", url, headers, None),
+                    };
+                    match response {
+                        Ok(response) => {
+                            cache.put(url, &response, fetched);
+                            responses.lock().unwrap()[i] = Some(response);
+                        }
+                        Err(e) => {
+                            failed.store(true, Ordering::Relaxed);
+                            error.lock().unwrap().get_or_insert(e);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    if let Some(e) = error.into_inner().unwrap() {
+        return Err(e);
+    }
+    Ok(responses
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|response| response.expect("every URL is fetched without a failure"))
+        .collect())
+}
@@ -0,0 +1,22 @@
+//! Process exit codes, kept distinct so CI jobs can tell failures apart.
+
+use std::fmt::Display;
+
+/// A publisher or other integration failed.
+pub const FAILURE: i32 = 1;
+/// The command line could not be parsed.
+pub const USAGE: i32 = 2;
+/// The config file is missing, unreadable or incomplete.
+pub const CONFIG: i32 = 3;
+/// A git command could not be run or failed.
+pub const GIT: i32 = 4;
+/// `--fail-if-empty` was given and there was nothing to report.
+pub const EMPTY: i32 = 5;
+/// `--lint` was given and entries broke the `[lint]` rules, or commits
+/// checked by `lint-commits` broke the `[convention]`.
+pub const LINT: i32 = 6;
+
+pub fn fail(code: i32, message: impl Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(code);
+}
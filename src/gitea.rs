@@ -0,0 +1,36 @@
+use crate::http;
+use crate::json::quote;
+use crate::remote::{parse_remote, Hosts};
+
+/// Creates a release for `tag` on a Gitea or Forgejo repository with the
+/// changelog as its notes, authenticating with `GITEA_TOKEN` or a token
+/// stored by `auth login`. Gitea creates the tag from the default branch if
+/// it does not exist yet.
+pub fn publish_release(hosts: &Hosts, remote: &str, tag: &str, notes: &str) -> Result<(), String> {
+    let remote = parse_remote(remote).ok_or_else(|| format!("unrecognized remote {}", remote))?;
+    if !remote.is_gitea(hosts) {
+        return Err(format!(
+            "{} is not a known Gitea host; add it to [gitea] hosts",
+            remote.host
+        ));
+    }
+
+    let token = http::gitea_token(&remote.host)?;
+
+    let body = format!(
+        "{{\"tag_name\":{},\"name\":{},\"body\":{}}}",
+        quote(tag),
+        quote(tag),
+        quote(notes)
+    );
+
+    http::request(
+        &hosts.http,
+        "POST",
+        &format!("{}/repos/{}/releases", remote.gitea_api(hosts), remote.path),
+        &[format!("Authorization: token {}", token)],
+        Some(&body),
+    )?;
+
+    Ok(())
+}
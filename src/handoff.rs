@@ -0,0 +1,105 @@
+use crate::config::OnCallConfig;
+use crate::convention::Subject;
+use crate::model::{Commit, Project, ProjectList};
+use crate::render::{self, IssueLinks, IssuePattern};
+use std::collections::BTreeMap;
+
+/// What the next person on call should know about: commits mentioning an
+/// incident ticket, by ticket, then the other fixes and reverts, newest
+/// first. `date` is the day the handoff is for.
+pub fn summary(projects: &ProjectList, oncall: &OnCallConfig, date: &str) -> String {
+    let incident_links = [IssueLinks {
+        pattern: IssuePattern::Keyed {
+            teams: oncall.incident_keys.clone(),
+        },
+        url: String::new(),
+    }];
+
+    let mut incidents: BTreeMap<String, Vec<(&Project, &Commit)>> = BTreeMap::new();
+    let mut fixes = vec![];
+    let mut reverts = vec![];
+
+    for project in &projects.projects {
+        for commit in project.commits.iter().filter(|c| !c.is_skipped()) {
+            let mut mentioned: Vec<String> = render::mentions(&incident_links, &commit.message)
+                .into_iter()
+                .chain(render::mentions(&incident_links, &commit.body))
+                .map(|mention| mention.name)
+                .collect();
+            mentioned.sort();
+            mentioned.dedup();
+
+            if !mentioned.is_empty() {
+                for incident in mentioned {
+                    incidents
+                        .entry(incident)
+                        .or_default()
+                        .push((project, commit));
+                }
+            } else if is_revert(commit) {
+                reverts.push((project, commit));
+            } else if is_fix(commit) {
+                fixes.push((project, commit));
+            }
+        }
+    }
+
+    // Across projects, so each list is in one order rather than by project.
+    for commits in incidents.values_mut().chain([&mut fixes, &mut reverts]) {
+        commits.sort_by(|(_, a), (_, b)| Commit::newest_first(a, b));
+    }
+
+    let mut summary = format!("On-call handoff for {}\n", date);
+    if incidents.is_empty() && fixes.is_empty() && reverts.is_empty() {
+        summary.push_str("\nNo incidents, fixes or reverts\n");
+        return summary;
+    }
+
+    if !incidents.is_empty() {
+        summary.push_str("\nIncidents\n");
+        for (incident, commits) in &incidents {
+            summary.push_str(&format!("- {}\n", incident));
+            for (project, commit) in commits {
+                summary.push_str(&format!("  - {}\n", line(project, commit)));
+            }
+        }
+    }
+
+    for (title, commits) in [("Fixes", &fixes), ("Reverts", &reverts)] {
+        if commits.is_empty() {
+            continue;
+        }
+        summary.push_str(&format!("\n{}\n", title));
+        for (project, commit) in commits {
+            summary.push_str(&format!("- {}\n", line(project, commit)));
+        }
+    }
+
+    summary
+}
+
+/// `fix:` and `hotfix:` commits, breaking or scoped ones included.
+fn is_fix(commit: &Commit) -> bool {
+    Subject::parse(&commit.message).is_some_and(|subject| matches!(subject.kind, "fix" | "hotfix"))
+}
+
+/// `git revert`'s own subjects, and conventional `revert:` ones.
+fn is_revert(commit: &Commit) -> bool {
+    commit.message.starts_with("Revert \"")
+        || Subject::parse(&commit.message).is_some_and(|subject| subject.kind == "revert")
+}
+
+fn line(project: &Project, commit: &Commit) -> String {
+    format!(
+        "{}: {} ({}, {}, {})",
+        project.name,
+        commit.message,
+        commit.hash.get(..8).unwrap_or(&commit.hash),
+        commit.author_name,
+        commit
+            .date
+            .get(..16)
+            .unwrap_or(&commit.date)
+            .replace('T', " ")
+    )
+}
@@ -0,0 +1,159 @@
+use crate::model::ProjectList;
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::BTreeMap;
+
+/// GitHub's contribution palette, from no commits to the busiest days.
+const SVG_COLORS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+/// 256-color terminal greens matching the same levels.
+const TERMINAL_COLORS: [u8; 5] = [237, 22, 28, 34, 40];
+/// Shades used when the terminal gets no colors.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+const CELL: usize = 13;
+
+/// Commits per local day over a window of weeks ending with today's.
+pub struct Heatmap {
+    counts: BTreeMap<NaiveDate, usize>,
+    /// The Monday the first column starts on.
+    start: NaiveDate,
+    today: NaiveDate,
+    max: usize,
+}
+
+impl Heatmap {
+    /// Covers the last `days` days, or the span of the commits without a
+    /// window.
+    pub fn new(projects: &ProjectList, days: i64, today: NaiveDate) -> Heatmap {
+        let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+
+        for commit in projects.projects.iter().flat_map(|p| &p.commits) {
+            if let Ok(date) = DateTime::parse_from_rfc3339(&commit.date) {
+                *counts
+                    .entry(date.with_timezone(&Local).date_naive())
+                    .or_default() += 1;
+            }
+        }
+
+        let first = if days > 0 {
+            today - Duration::days(days - 1)
+        } else {
+            counts.keys().next().copied().unwrap_or(today)
+        };
+        let start = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+        let max = counts.values().copied().max().unwrap_or(0);
+
+        Heatmap {
+            counts,
+            start,
+            today,
+            max,
+        }
+    }
+
+    fn weeks(&self) -> i64 {
+        (self.today - self.start).num_days() / 7 + 1
+    }
+
+    /// Days of the grid by column then row, skipping days after today.
+    fn cells(&self) -> impl Iterator<Item = (i64, i64, NaiveDate, usize)> + '_ {
+        (0..self.weeks()).flat_map(move |week| {
+            (0..7).filter_map(move |weekday| {
+                let day = self.start + Duration::days(week * 7 + weekday);
+                (day <= self.today).then(|| (week, weekday, day, self.count(day)))
+            })
+        })
+    }
+
+    fn count(&self, day: NaiveDate) -> usize {
+        self.counts.get(&day).copied().unwrap_or(0)
+    }
+
+    /// Quartiles of the busiest day, like GitHub's graph.
+    fn level(&self, count: usize) -> usize {
+        if count == 0 {
+            0
+        } else {
+            (count * 4).div_ceil(self.max).clamp(1, 4)
+        }
+    }
+
+    /// Rows for Monday to Sunday, one character per week.
+    pub fn terminal(&self, color: bool) -> String {
+        let mut rows = vec![String::new(); 7];
+
+        for (_, weekday, _, count) in self.cells() {
+            let level = self.level(count);
+            let row = &mut rows[weekday as usize];
+            if color {
+                row.push_str(&format!("\x1b[38;5;{}m■\x1b[0m", TERMINAL_COLORS[level]));
+            } else {
+                row.push(SHADES[level]);
+            }
+        }
+
+        let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+        let mut heatmap = String::new();
+        for (name, row) in names.iter().zip(rows) {
+            heatmap.push_str(&format!("{} {}\n", name, row));
+        }
+        heatmap.push_str(&format!(
+            "{} commits since {}, at most {} a day\n",
+            self.counts
+                .range(self.start..)
+                .map(|(_, count)| count)
+                .sum::<usize>(),
+            self.start,
+            self.max
+        ));
+        heatmap
+    }
+
+    pub fn svg(&self) -> String {
+        let left = 30;
+        let top = 20;
+        let width = left + self.weeks() as usize * CELL;
+        let height = top + 7 * CELL;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"9\">\n",
+            width, height
+        );
+
+        for (row, name) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+            svg.push_str(&format!(
+                "<text x=\"0\" y=\"{}\">{}</text>\n",
+                top + row * CELL + 9,
+                name
+            ));
+        }
+
+        // A month is labelled above the first week that starts in it.
+        let mut month = None;
+        for week in 0..self.weeks() {
+            let monday = self.start + Duration::days(week * 7);
+            if month != Some(monday.month()) {
+                month = Some(monday.month());
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"10\">{}</text>\n",
+                    left + week as usize * CELL,
+                    monday.format("%b")
+                ));
+            }
+        }
+
+        for (week, weekday, day, count) in self.cells() {
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{size}\" height=\"{size}\" rx=\"2\" fill=\"{}\"><title>{} commits on {}</title></rect>\n",
+                left + week as usize * CELL,
+                top + weekday as usize * CELL,
+                SVG_COLORS[self.level(count)],
+                count,
+                day,
+                size = CELL - 2
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,47 @@
+use crate::config::{FailurePolicy, Hook};
+use crate::exit::{self, fail};
+use crate::shell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Runs hooks in order. Per-project hooks run once in each configured
+/// project's directory, with `PROJECT_NAME` set.
+pub fn run(stage: &str, hooks: &[Hook], paths: &BTreeMap<String, PathBuf>) {
+    for hook in hooks {
+        if hook.per_project {
+            for (name, path) in paths {
+                let mut command = shell::command(&hook.command);
+                command.current_dir(path).env("PROJECT_NAME", name);
+                check(stage, hook, Some(name), command.status());
+            }
+        } else {
+            check(stage, hook, None, shell::command(&hook.command).status());
+        }
+    }
+}
+
+fn check(
+    stage: &str,
+    hook: &Hook,
+    project: Option<&str>,
+    status: std::io::Result<std::process::ExitStatus>,
+) {
+    let error = match status {
+        Ok(status) if status.success() => return,
+        Ok(status) => status.to_string(),
+        Err(e) => e.to_string(),
+    };
+    let message = match project {
+        Some(project) => format!(
+            "{} hook `{}` failed in {}: {}",
+            stage, hook.command, project, error
+        ),
+        None => format!("{} hook `{}` failed: {}", stage, hook.command, error),
+    };
+
+    match hook.on_failure {
+        FailurePolicy::Abort => fail(exit::FAILURE, message),
+        FailurePolicy::Warn => eprintln!("{}", message),
+        FailurePolicy::Ignore => {}
+    }
+}
@@ -0,0 +1,121 @@
+use crate::config::Config;
+use crate::convention;
+use crate::json;
+use crate::model::ProjectList;
+use crate::render::Format;
+use crate::yaml;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How many scopes and files are listed per project.
+const TOP: usize = 10;
+
+#[derive(Serialize)]
+pub struct Hotspots {
+    projects: Vec<ProjectHotspots>,
+}
+
+#[derive(Serialize)]
+struct ProjectHotspots {
+    project: String,
+    scopes: Vec<Count>,
+    files: Vec<Count>,
+}
+
+#[derive(Serialize)]
+struct Count {
+    name: String,
+    commits: usize,
+}
+
+/// The conventional commit scopes and files changed by the most commits
+/// in each project. Scopes the project's `[convention]` does not know are
+/// counted as `unscoped` when it groups them.
+pub fn hotspots(projects: &ProjectList, config: &Config) -> Hotspots {
+    let projects = projects
+        .projects
+        .iter()
+        .map(|project| {
+            let mut scopes: HashMap<&str, usize> = HashMap::new();
+            let mut files: HashMap<&str, usize> = HashMap::new();
+            let convention = config.convention(&project.name);
+
+            for commit in &project.commits {
+                if let Some(mut scope) = commit.scope() {
+                    if convention.group_unknown_scopes
+                        && convention::unknown_scope(&commit.message, convention).is_some()
+                    {
+                        scope = "unscoped";
+                    }
+                    *scopes.entry(scope).or_default() += 1;
+                }
+                for file in &commit.files {
+                    *files.entry(file).or_default() += 1;
+                }
+            }
+
+            ProjectHotspots {
+                project: project.name.clone(),
+                scopes: top(scopes),
+                files: top(files),
+            }
+        })
+        .collect();
+
+    Hotspots { projects }
+}
+
+/// Most commits first, ties by name so runs compare cleanly.
+fn top(counts: HashMap<&str, usize>) -> Vec<Count> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    counts
+        .into_iter()
+        .take(TOP)
+        .map(|(name, commits)| Count {
+            name: name.to_string(),
+            commits,
+        })
+        .collect()
+}
+
+impl Hotspots {
+    /// The data formats serialize the report as it is; anything else gets
+    /// markdown tables.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Json => json::to_string_pretty(self).expect("Failed to serialize JSON"),
+            Format::Yaml => yaml::to_string(self).expect("Failed to serialize YAML"),
+            Format::Toml => toml::to_string_pretty(self).expect("Failed to serialize TOML"),
+            _ => self.markdown(),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let mut markdown = String::new();
+
+        for project in &self.projects {
+            markdown.push_str(&format!("## {}\n\n", project.project));
+            push_table(&mut markdown, "Scope", &project.scopes);
+            push_table(&mut markdown, "File", &project.files);
+        }
+
+        markdown
+    }
+}
+
+fn push_table(markdown: &mut String, heading: &str, counts: &[Count]) {
+    if counts.is_empty() {
+        return;
+    }
+
+    markdown.push_str(&format!("| {} | Commits |\n| --- | --- |\n", heading));
+    for count in counts {
+        markdown.push_str(&format!(
+            "| {} | {} |\n",
+            count.name.replace('|', "\\|"),
+            count.commits
+        ));
+    }
+    markdown.push('\n');
+}
@@ -0,0 +1,423 @@
+use crate::config::HttpConfig;
+use crate::keychain;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How many times a request is sent before its failure is returned.
+const ATTEMPTS: u32 = 4;
+
+/// The longest a rate limit is waited out; beyond it, the request fails.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Sends a request through `curl`, returning the response body on a 2xx
+/// status and a description of the failure otherwise.
+///
+/// Rate limits and transient failures are retried with exponential
+/// backoff, waiting as long as `Retry-After` or the GitHub and GitLab
+/// rate-limit headers ask. Server errors and timeouts are only retried for
+/// methods that are safe to repeat, so a release is never published twice.
+pub fn request(
+    http_config: &HttpConfig,
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&str>,
+) -> Result<String, String> {
+    let idempotent = !matches!(method, "POST" | "PATCH");
+    let mut backoff = Duration::from_secs(1);
+
+    for attempt in 1.. {
+        let failure = match send(http_config, method, url, headers, body) {
+            Ok(response) => return Ok(response),
+            Err(failure) => failure,
+        };
+
+        let wait = match failure.retry {
+            Retry::Never => None,
+            Retry::Idempotent if !idempotent => None,
+            Retry::Idempotent => Some(backoff),
+            Retry::After(wait) => Some(wait.unwrap_or(backoff)),
+        };
+        let Some(wait) = wait.filter(|_| attempt < ATTEMPTS) else {
+            return Err(failure.message);
+        };
+        if wait > MAX_WAIT {
+            return Err(format!(
+                "{} (rate limited for another {} minutes)",
+                failure.message,
+                wait.as_secs().div_ceil(60)
+            ));
+        }
+
+        eprintln!(
+            "{} {} failed, retrying in {}s: {}",
+            method,
+            url,
+            wait.as_secs(),
+            failure.message
+        );
+        thread::sleep(wait);
+        backoff *= 2;
+    }
+
+    unreachable!("the last attempt returns")
+}
+
+/// Whether a failed request is worth sending again.
+enum Retry {
+    Never,
+    /// A server error or a dropped connection, which a non-idempotent
+    /// request may already have taken effect through.
+    Idempotent,
+    /// Rate limited or never connected, so nothing happened; after the given
+    /// wait, or the backoff without one.
+    After(Option<Duration>),
+}
+
+struct Failure {
+    message: String,
+    retry: Retry,
+}
+
+fn send(
+    http_config: &HttpConfig,
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&str>,
+) -> Result<String, Failure> {
+    let fatal = |message: String| Failure {
+        message,
+        retry: Retry::Never,
+    };
+
+    let mut command = Command::new("curl");
+    command
+        .arg("-sS")
+        // The headers carry the rate limit.
+        .arg("-i")
+        .arg("-X")
+        .arg(method)
+        .arg("-w")
+        .arg("\n%{http_code}");
+
+    network(&mut command, http_config, url);
+
+    // Headers carry tokens, which other users can read from a command line,
+    // so they go through a config file on stdin. The body is read from a
+    // file of its own, as curl limits the length of config lines.
+    let mut config = String::new();
+    for header in headers {
+        config.push_str(&format!("header = {}\n", quote_config(header)));
+    }
+    let body_file = body
+        .map(BodyFile::new)
+        .transpose()
+        .map_err(|e| fatal(format!("failed to write request body: {}", e)))?;
+    if let Some(body_file) = &body_file {
+        config.push_str("header = \"Content-Type: application/json\"\n");
+        config.push_str(&format!(
+            "data-binary = {}\n",
+            quote_config(&format!("@{}", body_file.0.display()))
+        ));
+    }
+
+    let mut child = command
+        .arg("--config")
+        .arg("-")
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| fatal(format!("failed to execute curl: {}", e)))?;
+
+    // Dropped once written, so curl sees the end of its config.
+    child
+        .stdin
+        .take()
+        .expect("curl stdin is piped")
+        .write_all(config.as_bytes())
+        .map_err(|e| fatal(format!("failed to send request: {}", e)))?;
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| fatal(format!("failed to execute curl: {}", e)))?;
+
+    if !output.status.success() {
+        // curl's exit codes: 6 and 7 are failures to resolve or connect,
+        // before anything was sent; 28, 52, 55 and 56 are timeouts and
+        // connections lost during the request.
+        let retry = match output.status.code() {
+            Some(6 | 7) => Retry::After(None),
+            Some(28 | 52 | 55 | 56) => Retry::Idempotent,
+            _ => Retry::Never,
+        };
+        return Err(Failure {
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            retry,
+        });
+    }
+
+    let output = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = output.rsplit_once('\n').unwrap_or(("", &output));
+    let status: u16 = status.trim().parse().unwrap_or(0);
+    let (head, response) = split_head(response);
+
+    if (200..300).contains(&status) {
+        return Ok(response.to_string());
+    }
+
+    let header = |name: &str| {
+        head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    };
+    // GitHub answers an exhausted limit with 403 or 429, GitLab with 429;
+    // both say when it resets, in epoch seconds. GitHub's secondary limit,
+    // on too many requests at once, is a 403 with Retry-After.
+    let exhausted = ["x-ratelimit-remaining", "ratelimit-remaining"]
+        .iter()
+        .any(|name| header(name).as_deref() == Some("0"));
+    let retry = match status {
+        429 => Retry::After(wait(&header)),
+        403 if exhausted || header("retry-after").is_some() => Retry::After(wait(&header)),
+        500 | 502 | 503 | 504 => Retry::Idempotent,
+        _ => Retry::Never,
+    };
+
+    Err(Failure {
+        message: format!("{} {} returned HTTP {}: {}", method, url, status, response),
+        retry,
+    })
+}
+
+/// The proxy and TLS options for `url`'s host. curl itself honors
+/// `HTTPS_PROXY` and `NO_PROXY` when no proxy is configured.
+fn network(command: &mut Command, http_config: &HttpConfig, url: &str) {
+    if let Some(proxy) = &http_config.proxy {
+        command.arg("--proxy").arg(proxy);
+    }
+    if !http_config.no_proxy.is_empty() {
+        command.arg("--noproxy").arg(http_config.no_proxy.join(","));
+    }
+
+    let tls = http_config.hosts.get(host(url));
+    let ca_bundle = tls
+        .and_then(|tls| tls.ca_bundle.as_ref())
+        .or(http_config.ca_bundle.as_ref());
+    if let Some(ca_bundle) = ca_bundle {
+        command.arg("--cacert").arg(ca_bundle);
+    }
+
+    let Some(tls) = tls else {
+        return;
+    };
+    if tls.insecure {
+        command.arg("--insecure");
+    }
+    if let Some(cert) = &tls.client_cert {
+        command.arg("--cert").arg(cert);
+    }
+    if let Some(key) = &tls.client_key {
+        command.arg("--key").arg(key);
+    }
+}
+
+/// A request body in a temporary file, removed when dropped.
+struct BodyFile(PathBuf);
+
+impl BodyFile {
+    fn new(body: &str) -> io::Result<BodyFile> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "chenjirogu-{}-{}.json",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&path, body)?;
+        Ok(BodyFile(path))
+    }
+}
+
+impl Drop for BodyFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// `value` as a double-quoted curl config parameter, in which a backslash
+/// escapes the next character and `\n`, `\r` and `\t` are control
+/// characters.
+fn quote_config(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The host name of a URL, without credentials or port.
+pub fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or(host)
+}
+
+/// The response headers and body of `curl -i` output. Interim responses,
+/// such as `100 Continue`, come first with their own headers.
+fn split_head(output: &str) -> (&str, &str) {
+    let mut head = "";
+    let mut rest = output;
+
+    while rest.starts_with("HTTP/") {
+        let (block, body) = rest
+            .split_once("\r\n\r\n")
+            .or_else(|| rest.split_once("\n\n"))
+            .unwrap_or((rest, ""));
+        head = block;
+        rest = body;
+    }
+
+    (head, rest)
+}
+
+/// How long the server asked to wait: `Retry-After` in seconds, or until
+/// the rate limit resets.
+fn wait(header: &dyn Fn(&str) -> Option<String>) -> Option<Duration> {
+    if let Some(seconds) = header("retry-after").and_then(|value| value.parse().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let reset: u64 = ["x-ratelimit-reset", "ratelimit-reset"]
+        .iter()
+        .find_map(|name| header(name)?.parse().ok())?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now) + 1))
+}
+
+/// The GitHub token from `GITHUB_TOKEN`, or `GH_TOKEN` as used by the `gh`
+/// CLI, or else the one `auth login` stored for the host.
+pub fn github_token(host: &str) -> Result<String, String> {
+    std::env::var("GITHUB_TOKEN")
+        .or_else(|_| std::env::var("GH_TOKEN"))
+        .or_else(|_| stored_token(host, "GITHUB_TOKEN"))
+}
+
+pub fn gitlab_token(host: &str) -> Result<String, String> {
+    std::env::var("GITLAB_TOKEN").or_else(|_| stored_token(host, "GITLAB_TOKEN"))
+}
+
+pub fn gitea_token(host: &str) -> Result<String, String> {
+    std::env::var("GITEA_TOKEN").or_else(|_| stored_token(host, "GITEA_TOKEN"))
+}
+
+fn stored_token(host: &str, variable: &str) -> Result<String, String> {
+    keychain::get(host).ok_or_else(|| {
+        format!(
+            "{} is not set and no token is stored for {}; see auth login",
+            variable, host
+        )
+    })
+}
+
+/// The `Authorization` header for Azure DevOps: a personal access token from
+/// `AZURE_DEVOPS_EXT_PAT`, as used by the `az devops` CLI, or the pipeline's
+/// `SYSTEM_ACCESSTOKEN`.
+pub fn azure_authorization() -> Result<String, String> {
+    if let Ok(token) = std::env::var("AZURE_DEVOPS_EXT_PAT") {
+        return Ok(format!("Authorization: Basic {}", basic_auth("", &token)));
+    }
+
+    std::env::var("SYSTEM_ACCESSTOKEN")
+        .map(|token| format!("Authorization: Bearer {}", token))
+        .map_err(|_| "AZURE_DEVOPS_EXT_PAT is not set".to_string())
+}
+
+/// The `Authorization` header for Bitbucket: `BITBUCKET_TOKEN` as a bearer
+/// token, or with `BITBUCKET_USER` as basic auth for Cloud app passwords.
+pub fn bitbucket_authorization() -> Result<String, String> {
+    let token =
+        std::env::var("BITBUCKET_TOKEN").map_err(|_| "BITBUCKET_TOKEN is not set".to_string())?;
+
+    Ok(match std::env::var("BITBUCKET_USER") {
+        Ok(user) => format!("Authorization: Basic {}", basic_auth(&user, &token)),
+        Err(_) => format!("Authorization: Bearer {}", token),
+    })
+}
+
+/// Encodes `user:password` for an HTTP basic `Authorization` header.
+pub fn basic_auth(user: &str, password: &str) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let input = format!("{}:{}", user, password);
+    let mut encoded = String::new();
+
+    for chunk in input.as_bytes().chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Percent-encodes a value for use as a single URL path segment or query value.
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_parameters_escape_quotes_and_control_characters() {
+        assert_eq!(
+            quote_config("Authorization: Bearer a\"b\\c"),
+            r#""Authorization: Bearer a\"b\\c""#
+        );
+        assert_eq!(quote_config("one\ntwo\tthree"), r#""one\ntwo\tthree""#);
+    }
+}
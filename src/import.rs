@@ -0,0 +1,540 @@
+//! Reads markdown changelogs back into a `ProjectList`, so that history kept
+//! in a CHANGELOG.md can be rendered, merged and converted like collected
+//! data. Two layouts are understood: the markdown this tool writes, and
+//! keepachangelog.com files.
+//!
+//! Each entry becomes a commit whose subject files it under the same section
+//! again, e.g. `fix: ...` for a bugfix. Hashes, links and dates come from the
+//! document where it has them. A keepachangelog.com item without a commit
+//! link gets a hash made from its release and text, and its release is kept
+//! in a `Release:` trailer.
+
+use crate::diagnostics::Diagnostics;
+use crate::model::{Commit, Project, ProjectList};
+use crate::render::{self, SectionKind};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// `name` is the project of a keepachangelog.com file, which the file itself
+/// does not say.
+pub fn markdown(text: &str, name: &str) -> Result<ProjectList, String> {
+    let projects = if render::is_keep_a_changelog(text) {
+        vec![keep_a_changelog(text, name)]
+    } else {
+        generated(text)?
+    };
+    Ok(ProjectList {
+        projects,
+        diagnostics: Diagnostics::default(),
+    })
+}
+
+fn generated(text: &str) -> Result<Vec<Project>, String> {
+    let mut projects: Vec<Project> = vec![];
+    let mut date = None;
+    let mut team = None;
+    let mut kind = None;
+    // The security section at the top, whose entries name their project
+    // when there are several.
+    let mut security = new_project("", None);
+    let mut in_security = false;
+
+    for line in text.lines() {
+        if let Some(title) = line.strip_prefix("# ") {
+            match (&date, title.strip_prefix("Changelog for ")) {
+                (None, Some(rest)) => date = Some(timestamp(rest.get(..10).unwrap_or(rest))),
+                (None, None) => return Err("not a chenjirogu markdown changelog".to_string()),
+                (Some(_), _) => team = Some(title.to_string()),
+            }
+        } else if line.starts_with("<details>") {
+            return Err("projects folded with --collapsible cannot be imported".to_string());
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            in_security = heading.starts_with("<a id=\"security\">");
+            kind = in_security.then_some(SectionKind::Security);
+            if !in_security {
+                projects.push(project(heading, team.clone()));
+            }
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            kind = section_kind(heading);
+        } else if let (Some(kind), Some(date)) = (kind, &date) {
+            let project = match in_security {
+                true => &mut security,
+                false => match projects.last_mut() {
+                    Some(project) => project,
+                    None => continue,
+                },
+            };
+            push_line(project, kind, line, date);
+        } else if let Some(project) = projects.last_mut() {
+            about(project, line);
+        }
+    }
+
+    if date.is_none() {
+        return Err("not a markdown changelog, it has no # title".to_string());
+    }
+
+    let several = projects.len() > 1;
+    for mut commit in security.commits {
+        let project = projects.iter_mut().find(|project| {
+            !several
+                || commit
+                    .message
+                    .starts_with(&format!("security: {}: ", project.name))
+        });
+        let Some(project) = project else {
+            continue;
+        };
+
+        if several {
+            commit.message = commit
+                .message
+                .replacen(&format!("{}: ", project.name), "", 1);
+        }
+        if project.remote.is_empty() {
+            project.remote = security.remote.clone();
+        }
+        project.commits.push(commit);
+    }
+
+    Ok(projects)
+}
+
+fn keep_a_changelog(text: &str, name: &str) -> Project {
+    let mut project = new_project(name, None);
+    let mut release = String::new();
+    let mut date = String::new();
+    let mut kind = None;
+
+    for line in text.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            // `[1.2.0] - 2024-05-01`, perhaps with a `[YANKED]` marker.
+            let (version, rest) = heading.split_once(']').unwrap_or((heading, ""));
+            release = version.trim_start_matches('[').to_string();
+            date = rest
+                .trim_start_matches([' ', '-'])
+                .get(..10)
+                .map(timestamp)
+                .unwrap_or_default();
+            kind = None;
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            kind = keep_a_changelog_kind(heading);
+        } else if let Some((_, url)) = line.strip_prefix('[').and_then(|l| l.split_once("]: ")) {
+            // Link definitions like `[1.2.0]: https://host/o/r/compare/...`.
+            if let Some((remote, _)) = url.split_once("/compare/") {
+                project.remote = remote.to_string();
+            }
+        } else if let Some(kind) = kind {
+            let before = project.commits.len();
+            push_line(&mut project, kind, line, &date);
+            if project.commits.len() == before {
+                continue_item(&mut project, line);
+                continue;
+            }
+
+            let commit = project.commits.last_mut().expect("pushed above");
+            if commit.hash.is_empty() {
+                commit.hash = synthetic_hash(&release, &commit.message);
+            }
+            if release != "Unreleased" {
+                commit.body = format!("Release: {}", release);
+            }
+        }
+    }
+
+    // Unreleased items have no date of their own, and are dated with the
+    // newest release.
+    let newest = project.commits.iter().map(|c| c.date.clone()).max();
+    for commit in &mut project.commits {
+        if commit.date.is_empty() {
+            commit.date = newest.clone().unwrap_or_default();
+        }
+    }
+
+    project
+}
+
+fn new_project(name: &str, team: Option<String>) -> Project {
+    Project {
+        name: name.to_string(),
+        path: PathBuf::new(),
+        commits: vec![],
+        remote: String::new(),
+        team,
+        description: None,
+        emoji: None,
+        homepage: None,
+        version: None,
+        anchor_group: None,
+    }
+}
+
+/// A project from its heading, `<a id="..."></a>` and the title, which may
+/// start with the project's emoji.
+fn project(heading: &str, team: Option<String>) -> Project {
+    let title = heading
+        .rsplit_once("</a>")
+        .map_or(heading, |(_, title)| title);
+    let mut project = new_project(title, team);
+
+    if let Some((first, name)) = title.split_once(' ') {
+        let shortcode = first.len() > 2 && first.starts_with(':') && first.ends_with(':');
+        if shortcode || !first.chars().any(char::is_alphanumeric) {
+            project.emoji = Some(first.to_string());
+            project.name = name.to_string();
+        }
+    }
+
+    project
+}
+
+/// The paragraph under a project's heading: its description and a link to
+/// its homepage.
+fn about(project: &mut Project, line: &str) {
+    for part in line.split(" · ").filter(|part| !part.is_empty()) {
+        match part
+            .strip_prefix("[Homepage](")
+            .and_then(|url| url.strip_suffix(')'))
+        {
+            Some(url) => project.homepage = Some(url.to_string()),
+            None => project.description = Some(part.to_string()),
+        }
+    }
+}
+
+fn section_kind(heading: &str) -> Option<SectionKind> {
+    [
+        SectionKind::Security,
+        SectionKind::BugFixes,
+        SectionKind::Features,
+        SectionKind::Dependencies,
+    ]
+    .into_iter()
+    .find(|kind| heading.ends_with(kind.title()))
+}
+
+fn keep_a_changelog_kind(heading: &str) -> Option<SectionKind> {
+    match heading.trim() {
+        "Added" | "Changed" | "Deprecated" | "Removed" => Some(SectionKind::Features),
+        "Fixed" => Some(SectionKind::BugFixes),
+        "Security" => Some(SectionKind::Security),
+        _ => None,
+    }
+}
+
+/// Adds the commit behind a list item or table row, or the breaking change
+/// note quoted under the last one.
+fn push_line(project: &mut Project, kind: SectionKind, line: &str, date: &str) {
+    let line = line.trim_start();
+
+    if let Some(note) = line.strip_prefix('>') {
+        let note = note.trim_start();
+        if let Some(commit) = project.commits.last_mut() {
+            commit.body = match note.strip_prefix("**Breaking change:** ") {
+                Some(note) => format!("BREAKING CHANGE: {}", note),
+                None => format!("{}\n{}", commit.body, note),
+            };
+        }
+        return;
+    }
+
+    // The table header, and the link to entries left out.
+    if line.contains("[…and ") || line.starts_with("| Package ") || line.starts_with("| --- ") {
+        return;
+    }
+
+    let (message, link, bare_hash) = if let Some(row) = line.strip_prefix("| ") {
+        // `| [package](link) | from | to |`
+        let cells: Vec<&str> = row
+            .trim_end_matches('|')
+            .split('|')
+            .map(str::trim)
+            .collect();
+        let [package, from, to] = cells[..] else {
+            return;
+        };
+        let (package, link) = split_link(package);
+        let message = match (from, to) {
+            ("", "") => format!("chore(deps): {}", package),
+            ("", to) => format!("chore(deps): update {} to {}", package, to),
+            (from, to) => format!("chore(deps): bump {} from {} to {}", package, from, to),
+        };
+        (message, link, "")
+    } else if let Some(item) = line.strip_prefix("- ") {
+        // `message [#1a2b3c4](link)`, or `message #1a2b3c4` for a project
+        // without a remote; only this tool adds either.
+        let (text, link, bare_hash) = match item.rsplit_once(" [#") {
+            Some((text, hash)) if hash.ends_with(')') => {
+                (text, split_link(&format!("[{}", hash)).1, "")
+            }
+            _ => match item.rsplit_once(" #") {
+                Some((text, hash))
+                    if hash.len() >= 7 && hash.chars().all(|c| c.is_ascii_hexdigit()) =>
+                {
+                    (text, String::new(), hash)
+                }
+                _ => (item, String::new(), ""),
+            },
+        };
+        let prefix = match kind {
+            SectionKind::Security => "security",
+            SectionKind::BugFixes => "fix",
+            SectionKind::Features => "feat",
+            SectionKind::Dependencies => "chore(deps)",
+        };
+        (format!("{}: {}", prefix, plain(text)), link, bare_hash)
+    } else {
+        return;
+    };
+
+    // Links are the remote and `/commits/<hash>`, or `/commit/` for Gitea.
+    let (remote, hash) = link
+        .rsplit_once("/commits/")
+        .or_else(|| link.rsplit_once("/commit/"))
+        .unwrap_or(("", bare_hash));
+    if project.remote.is_empty() {
+        project.remote = remote.to_string();
+    }
+
+    project.commits.push(Commit {
+        hash: hash.to_string(),
+        message,
+        author_name: Arc::from(""),
+        author_email: Arc::from(""),
+        date: date.to_string(),
+        committer_date: String::new(),
+        body: String::new(),
+        pull_request: None,
+        files: vec![],
+    });
+}
+
+/// Joins a keepachangelog.com item wrapped onto indented lines.
+fn continue_item(project: &mut Project, line: &str) {
+    let text = line.trim();
+    if text.starts_with('>') {
+        return;
+    }
+    if let (false, Some(commit)) = (text.is_empty(), project.commits.last_mut()) {
+        commit.message = format!("{} {}", commit.message, plain(text));
+    }
+}
+
+/// `[text](url)` split into its text and url; other cells are all text.
+fn split_link(cell: &str) -> (String, String) {
+    cell.strip_prefix('[')
+        .and_then(|rest| rest.split_once("]("))
+        .and_then(|(text, rest)| Some((text, rest.strip_suffix(')')?)))
+        .map_or((cell.to_string(), String::new()), |(text, url)| {
+            (text.to_string(), url.to_string())
+        })
+}
+
+/// The message without the links added to issue mentions.
+fn plain(text: &str) -> String {
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        let link = rest[start + 1..]
+            .split_once("](")
+            .and_then(|(text, after)| {
+                let (_, after) = after.split_once(')')?;
+                (!text.contains('[')).then_some((text, after))
+            });
+        match link {
+            Some((text, after)) => {
+                plain.push_str(&rest[..start]);
+                plain.push_str(text);
+                rest = after;
+            }
+            None => {
+                plain.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+
+    plain.push_str(rest);
+    plain
+}
+
+/// Midnight UTC on a `YYYY-MM-DD` date, in the RFC 3339 form commits use.
+fn timestamp(date: &str) -> String {
+    format!("{}T00:00:00+00:00", date)
+}
+
+/// A stable stand-in hash, FNV-1a over the release and message, so that
+/// importing the same file twice gives the same commits.
+fn synthetic_hash(release: &str, message: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in release.bytes().chain([0]).chain(message.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(project: &Project) -> Vec<(&str, &str)> {
+        project
+            .commits
+            .iter()
+            .map(|commit| (commit.hash.as_str(), commit.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn imports_generated_changelogs() {
+        let projects = markdown(
+            "# Changelog for 2024-05-01\n\
+             \n\
+             ## <a id=\"security\"></a>:lock: Security\n \
+             - beta: escape <b> in titles [#0a0a0a0a](https://github.com/acme/beta/commits/0a0a0a0a0a)\n\
+             \n\
+             ## <a id=\"alpha\"></a>:rocket: alpha\n\
+             A tool: for things · [Homepage](https://alpha.dev)\n\
+             ### <a id=\"alpha-bugfixes\"></a>:bug: Bugfixes\n \
+             - handle ü in names: and # signs [#1b1b1b1b](https://github.com/acme/alpha/commits/1b1b1b1b1b)\n \
+             - - leading dash, see [#12](https://github.com/acme/alpha/issues/12) [#2c2c2c2c](https://github.com/acme/alpha/commits/2c2c2c2c2c)\n   \
+             > **Breaking change:** config moved\n\
+             \n\
+             ## <a id=\"beta\"></a>beta\n\
+             ### <a id=\"beta-features\"></a>:rocket: Features\n \
+             - offline mode #3d3d3d3d\n\
+             ### <a id=\"beta-dependencies\"></a>:arrow_up: Dependencies\n\
+             | Package | From | To |\n\
+             | --- | --- | --- |\n\
+             | [serde](https://github.com/acme/beta/commits/4e4e4e4e4e) | 1.0.1 | 1.0.2 |\n",
+            "ignored",
+        )
+        .expect("a generated changelog")
+        .projects;
+
+        let [alpha, beta] = &projects[..] else {
+            panic!("two projects, not {}", projects.len());
+        };
+        assert_eq!(alpha.name, "alpha");
+        assert_eq!(alpha.emoji.as_deref(), Some(":rocket:"));
+        assert_eq!(alpha.description.as_deref(), Some("A tool: for things"));
+        assert_eq!(alpha.homepage.as_deref(), Some("https://alpha.dev"));
+        assert_eq!(alpha.remote, "https://github.com/acme/alpha");
+        assert_eq!(
+            messages(alpha),
+            [
+                ("1b1b1b1b1b", "fix: handle ü in names: and # signs"),
+                ("2c2c2c2c2c", "fix: - leading dash, see #12"),
+            ]
+        );
+        assert_eq!(alpha.commits[1].body, "BREAKING CHANGE: config moved");
+        assert_eq!(alpha.commits[0].date, "2024-05-01T00:00:00+00:00");
+
+        assert_eq!(
+            messages(beta),
+            [
+                ("3d3d3d3d", "feat: offline mode"),
+                ("4e4e4e4e4e", "chore(deps): bump serde from 1.0.1 to 1.0.2"),
+                ("0a0a0a0a0a", "security: escape <b> in titles"),
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_keep_a_changelog_files() {
+        let project = &markdown(
+            "# Changelog\n\
+             All notable changes are documented here, see keepachangelog.com.\n\
+             \n\
+             ## [Unreleased]\n\
+             ### Added\n\
+             - Export to `YAML`: with # comments\n\
+             \n\
+             ## [1.2.0] - 2024-05-01\n\
+             ### Fixed\n\
+             - A crash when a name has ü,\n  wrapped onto a second line\n\
+             ### Unknown heading\n\
+             - ignored\n\
+             \n\
+             [1.2.0]: https://github.com/acme/alpha/compare/v1.1.0...v1.2.0\n",
+            "alpha",
+        )
+        .expect("a keepachangelog.com file")
+        .projects[0];
+
+        assert_eq!(project.name, "alpha");
+        assert_eq!(project.remote, "https://github.com/acme/alpha");
+        let [unreleased, fixed] = &project.commits[..] else {
+            panic!("two commits, not {}", project.commits.len());
+        };
+        assert_eq!(
+            unreleased.message,
+            "feat: Export to `YAML`: with # comments"
+        );
+        assert_eq!(unreleased.body, "");
+        assert_eq!(unreleased.date, "2024-05-01T00:00:00+00:00");
+        assert_eq!(
+            fixed.message,
+            "fix: A crash when a name has ü, wrapped onto a second line"
+        );
+        assert_eq!(fixed.body, "Release: 1.2.0");
+        assert_eq!(fixed.hash.len(), 16);
+        assert_ne!(unreleased.hash, fixed.hash);
+    }
+
+    #[test]
+    fn round_trips_rendered_changelogs() {
+        let text = "# Changelog for 2024-05-01\n\n\
+                    ## <a id=\"alpha\"></a>alpha\n\
+                    ### <a id=\"alpha-bugfixes\"></a>:bug: Bugfixes\n \
+                    - \"quoted\" [brackets] ü # - #5f5f5f5f\n";
+        let projects = markdown(text, "alpha").expect("a generated changelog");
+        let options = render::RenderOptions {
+            generated_at: chrono::DateTime::parse_from_rfc3339("2024-05-01T12:00:00+00:00")
+                .expect("a valid date"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render::document(render::Format::Markdown, &options, &projects),
+            text.to_string() + "\n"
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for text in [
+            "",
+            "just some notes\n- fix: a [#1](x)\n",
+            "# Release notes\n## alpha\n",
+            "# Changelog for 2024-05-01\n<details>\n<summary>alpha</summary>\n",
+        ] {
+            assert!(markdown(text, "alpha").is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn ignores_lines_it_does_not_understand() {
+        let projects = markdown(
+            "# Changelog for 2024-05\n\
+             ### <a id=\"x-bugfixes\"></a>:bug: Bugfixes\n\
+             - before any project\n\
+             ## <a id=\"alpha\"></a>alpha\n\
+             ### <a id=\"alpha-bugfixes\"></a>:bug: Bugfixes\n\
+             - no link\n\
+             | only | two |\n\
+             - [#zz](not a hash) #12\n\
+             random text\n",
+            "alpha",
+        )
+        .expect("a generated changelog")
+        .projects;
+
+        assert_eq!(
+            messages(&projects[0]),
+            [("", "fix: no link"), ("", "fix: #zz #12")]
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Minimal JSON support built on `toml::Value`, which already models every
+//! JSON type except `null`. Serde types go through `toml::Value::try_from`
+//! on the way out and `Value::try_into` on the way in, so the crate needs no
+//! JSON dependency of its own. Object keys come out sorted and `null`s are
+//! dropped while parsing, which suits optional fields.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use toml::Value;
+
+pub fn to_string<T: Serialize>(value: &T) -> Result<String, String> {
+    let value = Value::try_from(value).map_err(|e| e.to_string())?;
+    let mut json = String::new();
+    write_value(&mut json, &value, None);
+    Ok(json)
+}
+
+pub fn to_string_pretty<T: Serialize>(value: &T) -> Result<String, String> {
+    let value = Value::try_from(value).map_err(|e| e.to_string())?;
+    let mut json = String::new();
+    write_value(&mut json, &value, Some(0));
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    parse(text)?.try_into().map_err(|e| e.to_string())
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+
+    let value = parser.value()?.unwrap_or(Value::Table(Default::default()));
+    parser.whitespace();
+
+    if parser.pos != parser.chars.len() {
+        return Err(format!("unexpected trailing data at offset {}", parser.pos));
+    }
+
+    Ok(value)
+}
+
+/// Quotes a string as a JSON string literal.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+fn write_value(json: &mut String, value: &Value, indent: Option<usize>) {
+    let newline = |json: &mut String, depth: usize| {
+        if indent.is_some() {
+            json.push('\n');
+            json.push_str(&"  ".repeat(depth));
+        }
+    };
+    let depth = indent.unwrap_or(0);
+
+    match value {
+        Value::String(s) => json.push_str(&quote(s)),
+        Value::Integer(i) => json.push_str(&i.to_string()),
+        Value::Float(f) if f.is_finite() => json.push_str(&f.to_string()),
+        Value::Float(_) => json.push_str("null"),
+        Value::Boolean(b) => json.push_str(&b.to_string()),
+        Value::Datetime(d) => json.push_str(&quote(&d.to_string())),
+        Value::Array(items) => {
+            if items.is_empty() {
+                json.push_str("[]");
+                return;
+            }
+
+            json.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                newline(json, depth + 1);
+                write_value(json, item, indent.map(|d| d + 1));
+            }
+            newline(json, depth);
+            json.push(']');
+        }
+        Value::Table(table) => {
+            if table.is_empty() {
+                json.push_str("{}");
+                return;
+            }
+
+            json.push('{');
+            for (i, (key, item)) in table.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                newline(json, depth + 1);
+                json.push_str(&quote(key));
+                json.push(':');
+                if indent.is_some() {
+                    json.push(' ');
+                }
+                write_value(json, item, indent.map(|d| d + 1));
+            }
+            newline(json, depth);
+            json.push('}');
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.whitespace();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at offset {}", c, self.pos))
+        }
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        let end = self.pos + word.len();
+        if end <= self.chars.len() && self.chars[self.pos..end].iter().copied().eq(word.chars()) {
+            self.pos = end;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses one value; `None` stands for `null`.
+    fn value(&mut self) -> Result<Option<Value>, String> {
+        self.whitespace();
+
+        match self.chars.get(self.pos).copied() {
+            Some('{') => self.object().map(Some),
+            Some('[') => self.array().map(Some),
+            Some('"') => self.string().map(|s| Some(Value::String(s))),
+            Some('t') if self.keyword("true") => Ok(Some(Value::Boolean(true))),
+            Some('f') if self.keyword("false") => Ok(Some(Value::Boolean(false))),
+            Some('n') if self.keyword("null") => Ok(None),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number().map(Some),
+            _ => Err(format!("unexpected character at offset {}", self.pos)),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        let mut table = toml::Table::new();
+        self.expect('{')?;
+        self.whitespace();
+
+        if self.chars.get(self.pos) == Some(&'}') {
+            self.pos += 1;
+            return Ok(Value::Table(table));
+        }
+
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            if let Some(value) = self.value()? {
+                table.insert(key, value);
+            }
+
+            self.whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Table(table));
+                }
+                _ => return Err(format!("expected ',' or '}}' at offset {}", self.pos)),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        let mut items = vec![];
+        self.expect('[')?;
+        self.whitespace();
+
+        if self.chars.get(self.pos) == Some(&']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            if let Some(value) = self.value()? {
+                items.push(value);
+            }
+
+            self.whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(format!("expected ',' or ']' at offset {}", self.pos)),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+
+        loop {
+            let c = *self
+                .chars
+                .get(self.pos)
+                .ok_or_else(|| "unterminated string".to_string())?;
+            self.pos += 1;
+
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = *self
+                        .chars
+                        .get(self.pos)
+                        .ok_or_else(|| "unterminated string".to_string())?;
+                    self.pos += 1;
+
+                    match escape {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => s.push(self.unicode_escape()?),
+                        c => s.push(c),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.iter().skip(self.pos).take(4).collect();
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("invalid \\u escape '{}'", digits))
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+
+        // Characters outside the BMP arrive as a UTF-16 surrogate pair.
+        let code = if (0xd800..0xdc00).contains(&high) && self.keyword("\\u") {
+            let low = self.hex4()?;
+            0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+        } else {
+            high
+        };
+
+        Ok(char::from_u32(code).unwrap_or('\u{fffd}'))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self.pos < self.chars.len()
+            && matches!(
+                self.chars[self.pos],
+                '-' | '+' | '.' | 'e' | 'E' | '0'..='9'
+            )
+        {
+            self.pos += 1;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        if let Ok(i) = text.parse::<i64>() {
+            return Ok(Value::Integer(i));
+        }
+
+        text.parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| format!("invalid number '{}'", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(quote("fix: \"quoted\""), r#""fix: \"quoted\"""#);
+        assert_eq!(quote("C:\\path"), r#""C:\\path""#);
+        assert_eq!(quote("one\ntwo\r\tthree"), r#""one\ntwo\r\tthree""#);
+        assert_eq!(quote("bell\u{7}"), r#""bell\u0007""#);
+        assert_eq!(quote("#12 - ü 🚀"), "\"#12 - ü 🚀\"");
+    }
+
+    #[test]
+    fn strings_read_back_unchanged() {
+        for s in [
+            "fix: crash",
+            "a # b",
+            "- leading dash",
+            "line one\nline two",
+            "quote \" and backslash \\",
+            "control \u{1}\u{1f}",
+            "handle ü and 🚀 in names",
+            "",
+        ] {
+            let json = to_string(&vec![s]).expect("serializable");
+            assert_eq!(
+                parse(&json).expect("valid JSON"),
+                Value::Array(vec![Value::String(s.to_string())]),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn parses_escapes() {
+        assert_eq!(
+            parse(r#"["\u00fc\ud83d\ude80", "\/\b\f"]"#).expect("valid JSON"),
+            Value::Array(vec![
+                Value::String("ü🚀".to_string()),
+                Value::String("/\u{8}\u{c}".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn pretty_output_round_trips() {
+        let value = parse(r#"{"b": [1, 2.5, true], "a": {"c": "d"}, "e": [], "n": null}"#)
+            .expect("valid JSON");
+
+        assert_eq!(
+            to_string_pretty(&value).expect("serializable"),
+            "{\n  \"a\": {\n    \"c\": \"d\"\n  },\n  \"b\": [\n    1,\n    2.5,\n    true\n  ],\n  \"e\": []\n}\n"
+        );
+        assert_eq!(
+            parse(&to_string_pretty(&value).expect("serializable")).expect("valid JSON"),
+            value
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for json in [
+            "",
+            "{",
+            "[1,",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            r#"{a: 1}"#,
+            r#""unterminated"#,
+            r#""\u12""#,
+            "tru",
+            "1 2",
+            "-",
+        ] {
+            assert!(parse(json).is_err(), "{:?}", json);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! API tokens kept in the system keychain by `auth login`, through the
+//! platform's own tool: `security` on macOS and `secret-tool` (libsecret)
+//! elsewhere. Tokens are stored per host under one service name.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const SERVICE: &str = "chenjirogu";
+
+/// The token stored for `host`, if there is one and the keychain can be
+/// read.
+pub fn get(host: &str) -> Option<String> {
+    let output = if cfg!(target_os = "macos") {
+        Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", host, "-w"])
+            .stderr(Stdio::null())
+            .output()
+    } else {
+        Command::new("secret-tool")
+            .args(["lookup", "service", SERVICE, "host", host])
+            .stderr(Stdio::null())
+            .output()
+    }
+    .ok()?;
+
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !token.is_empty()).then_some(token)
+}
+
+/// Stores `token` for `host`, replacing any stored before.
+pub fn set(host: &str, token: &str) -> Result<(), String> {
+    if cfg!(windows) {
+        return Err("no keychain is supported on Windows; set the token's variable".to_string());
+    }
+
+    // Tokens go through stdin, as other users can read a command line.
+    if cfg!(target_os = "macos") {
+        // A trailing `-w` without a value makes `security` prompt for the
+        // secret, then again to confirm it.
+        return run_with_input(
+            Command::new("security").args([
+                "add-generic-password",
+                "-U",
+                "-s",
+                SERVICE,
+                "-a",
+                host,
+                "-w",
+            ]),
+            &format!("{}\n{}\n", token, token),
+        );
+    }
+
+    run_with_input(
+        Command::new("secret-tool")
+            .args(["store", "--label"])
+            .arg(format!("{} token for {}", SERVICE, host))
+            .args(["service", SERVICE, "host", host]),
+        token,
+    )
+}
+
+/// Removes the token stored for `host`.
+pub fn delete(host: &str) -> Result<(), String> {
+    if cfg!(windows) {
+        return Err("no keychain is supported on Windows".to_string());
+    }
+
+    if cfg!(target_os = "macos") {
+        run(Command::new("security").args(["delete-generic-password", "-s", SERVICE, "-a", host]))
+    } else {
+        run(Command::new("secret-tool").args(["clear", "service", SERVICE, "host", host]))
+    }
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    check(command.stdout(Stdio::null()).output(), &program)
+}
+
+fn run_with_input(command: &mut Command, input: &str) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to execute {}: {}", program, e))?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())
+        .map_err(|e| format!("failed to send the token to {}: {}", program, e))?;
+    check(child.wait_with_output(), &program)
+}
+
+fn check(output: std::io::Result<std::process::Output>, program: &str) -> Result<(), String> {
+    let output = output.map_err(|e| format!("failed to execute {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
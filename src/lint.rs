@@ -0,0 +1,196 @@
+use crate::config::{Config, LintConfig};
+use crate::convention;
+use crate::model::ProjectList;
+use crate::regex::{self, Regex};
+use crate::render::{self, RenderOptions};
+
+/// Entries, as they would be rendered, that break the `[lint]` rules; one
+/// line per problem, e.g. `alpha 1a2b3c4d "Fix teh crash": misspelled
+/// "teh", use "the"`. Scopes outside the project's `[convention]` are
+/// flagged too. Dependency bumps are generated and never flagged.
+pub fn lint(projects: &ProjectList, options: &RenderOptions, config: &Config) -> Vec<String> {
+    let defaults = LintConfig::default();
+    let lint = config.lint.as_ref().unwrap_or(&defaults);
+    let banned: Vec<(&String, Regex)> = lint
+        .banned_words
+        .iter()
+        .map(|word| (word, whole_word(word, true)))
+        .collect();
+    let misspelled: Vec<(&String, &String, Regex)> = lint
+        .corrections
+        .iter()
+        .map(|(wrong, right)| (wrong, right, whole_word(wrong, false)))
+        .collect();
+    let mut problems = vec![];
+
+    for project in &projects.projects {
+        let convention = config.convention(&project.name);
+        let sections = render::sections(project, options);
+        let entries = sections
+            .sections
+            .iter()
+            .flat_map(|section| &section.entries)
+            .filter(|entry| entry.bump.is_none());
+
+        for entry in entries {
+            let message = &entry.message;
+            let mut found = vec![];
+
+            if message.starts_with(char::is_lowercase) {
+                found.push("starts lowercase".to_string());
+            }
+            if let Some(max) = lint.max_length {
+                let length = message.chars().count();
+                if length > max {
+                    found.push(format!("{} characters, over {}", length, max));
+                }
+            }
+            if let Some(scope) = convention::unknown_scope(&entry.commit.message, convention) {
+                found.push(format!("unknown scope {}", scope));
+            }
+            for (word, regex) in &banned {
+                if regex.is_match(message) {
+                    found.push(format!("contains banned {:?}", word));
+                }
+            }
+            for (wrong, right, regex) in &misspelled {
+                if regex.is_match(message) {
+                    found.push(format!("misspelled {:?}, use {:?}", wrong, right));
+                }
+            }
+
+            for problem in found {
+                problems.push(format!(
+                    "{} {} {:?}: {}",
+                    project.name,
+                    entry.short_hash(),
+                    message,
+                    problem
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+fn whole_word(word: &str, ignore_case: bool) -> Regex {
+    let pattern = format!(r"\b{}\b", regex::escape(word));
+    let pattern = if ignore_case {
+        format!("(?i){}", pattern)
+    } else {
+        pattern
+    };
+    Regex::new(&pattern).expect("an escaped word is a valid pattern")
+}
+
+/// Commits whose messages break their project's `[convention]`, one line
+/// per problem like the entry lint.
+pub fn lint_commits(projects: &ProjectList, config: &Config) -> Vec<String> {
+    let mut problems = vec![];
+
+    for project in &projects.projects {
+        let convention = config.convention(&project.name);
+        for commit in &project.commits {
+            for problem in convention::check(&commit.message, convention) {
+                problems.push(format!(
+                    "{} {} {:?}: {}",
+                    project.name,
+                    commit.hash.get(..8).unwrap_or(&commit.hash),
+                    commit.message,
+                    problem
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Commit, Project};
+    use std::path::PathBuf;
+
+    fn commit(hash: &str, message: &str, date: &str) -> Commit {
+        Commit {
+            hash: hash.to_string(),
+            message: message.to_string(),
+            author_name: "Ana".into(),
+            author_email: "ana@example.com".into(),
+            date: date.to_string(),
+            committer_date: String::new(),
+            body: String::new(),
+            pull_request: None,
+            files: vec![],
+        }
+    }
+
+    #[test]
+    fn unknown_scopes_are_flagged_and_grouped() {
+        let config: Config = toml::from_str(
+            "[paths]\n\
+             [convention]\n\
+             scopes = [\"api\"]\n\
+             group_unknown_scopes = true\n",
+        )
+        .expect("a valid config");
+        let projects = ProjectList {
+            projects: vec![Project {
+                name: "alpha".to_string(),
+                path: PathBuf::new(),
+                commits: vec![
+                    commit("1111111111", "feat(web): Dark mode", "2024-05-04T10:00:00Z"),
+                    commit(
+                        "2222222222",
+                        "feat(api): List users",
+                        "2024-05-03T10:00:00Z",
+                    ),
+                    commit(
+                        "3333333333",
+                        "feat(cli): Shell completions",
+                        "2024-05-02T10:00:00Z",
+                    ),
+                    commit("4444444444", "feat: Faster startup", "2024-05-01T10:00:00Z"),
+                ],
+                remote: String::new(),
+                team: None,
+                description: None,
+                emoji: None,
+                homepage: None,
+                version: None,
+                anchor_group: None,
+            }],
+            diagnostics: Default::default(),
+        };
+        let options = RenderOptions {
+            conventions: [("alpha".to_string(), config.convention.clone())].into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            lint(&projects, &options, &config),
+            [
+                "alpha 11111111 \"Dark mode\": unknown scope web",
+                "alpha 33333333 \"Shell completions\": unknown scope cli",
+            ]
+        );
+
+        let sections = render::sections(&projects.projects[0], &options);
+        let entries: Vec<_> = sections.sections[0]
+            .entries
+            .iter()
+            .map(|entry| (entry.scope, entry.message.as_ref()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (None, "Faster startup"),
+                (Some("api"), "List users"),
+                (Some("unscoped"), "Dark mode"),
+                (Some("unscoped"), "Shell completions"),
+            ]
+        );
+    }
+}
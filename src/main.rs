@@ -1,176 +1,2066 @@
-use chrono::prelude::*;
-use serde::{Deserialize, Serialize};
+mod badges;
+mod bump;
+mod cache;
+mod commit_hook;
+mod config;
+mod confluence;
+mod convention;
+mod diagnostics;
+mod diff;
+mod draft;
+mod encoding;
+mod enrich;
+mod exit;
+mod gitea;
+mod glob;
+mod handoff;
+mod heatmap;
+mod hooks;
+mod hotspots;
+mod http;
+mod import;
+mod json;
+mod keychain;
+mod lint;
+mod manifest;
+mod merge;
+mod milestone;
+mod mirrors;
+mod model;
+mod notion;
+mod plugin;
+mod pull_request;
+mod range;
+mod regex;
+mod remote;
+mod render;
+mod report;
+mod search;
+mod shell;
+mod site;
+mod standup;
+mod stats;
+mod vcs;
+mod version;
+mod who;
+mod workspace;
+mod yaml;
+
+use chrono::{DateTime, FixedOffset, Local};
+use config::{read_config, Config, LintConfig};
+use diagnostics::{DiagnosticKind, Diagnostics};
+use exit::fail;
+use model::{Interner, Project, ProjectList, Summary};
+use render::{Format, RenderOptions};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use toml;
+use vcs::Selection;
+
+#[derive(PartialEq)]
+enum Mode {
+    Generate,
+    Render,
+    Diff,
+    Merge,
+    Convert,
+    PublishConfluence,
+    PublishNotion,
+    PublishGitea,
+    Site,
+    List,
+    Report,
+    Standup,
+    Handoff,
+    Stats,
+    Heatmap,
+    Hotspots,
+    Search,
+    Who,
+    LintCommits,
+    InstallHooks,
+    Bump,
+    Finalize,
+    AuthLogin,
+    AuthLogout,
+}
 
-#[derive(Debug, Deserialize)]
-struct PathsConfig {
-    paths: std::collections::BTreeMap<String, String>,
+/// Whether terminal output gets ANSI colors.
+#[derive(PartialEq)]
+enum ColorChoice {
+    Never,
+    /// When stdout is a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Commit {
-    hash: String,
-    message: String,
+struct Options {
+    mode: Mode,
     author_name: String,
-    author_email: String,
-    date: String,
+    days: i64,
+    split_per_project: bool,
+    commit_back: bool,
+    commit_branch: Option<String>,
+    open_pr: bool,
+    /// The first `--format`, for views that render one way only.
+    format: Format,
+    /// Every `--format` given, in order; empty when there were none.
+    formats: Vec<Format>,
+    stdout: bool,
+    inputs: Vec<String>,
+    /// How `--input` files are read, from `--from`; by their extension
+    /// when `None`.
+    from: Option<Format>,
+    fail_if_empty: bool,
+    verbose: bool,
+    include_bots: bool,
+    expand_squash: bool,
+    enrich_prs: bool,
+    /// Leave out everything that needs a host's API.
+    offline: bool,
+    milestone: Option<String>,
+    /// Collect what was committed since each project's previous release.
+    since_release: bool,
+    tag: Option<String>,
+    overrides: Vec<String>,
+    projects: Vec<String>,
+    excluded_projects: Vec<String>,
+    /// Only commits changing a file under one of these paths or matching
+    /// one of these globs, e.g. `*.rs`.
+    only_touching: Vec<String>,
+    report_by: report::Period,
+    post_slack: bool,
+    range: Option<range::Preset>,
+    business_days: Option<i64>,
+    svg: bool,
+    time_of_day: bool,
+    /// `stats` for these authors side by side.
+    compare: Option<Vec<String>>,
+    /// Collect the commits of everyone on this `[teams]` roster.
+    team: Option<String>,
+    grep: Option<String>,
+    path: Option<String>,
+    toc: bool,
+    /// End the changelog with an index of the tickets commits mention.
+    ticket_index: bool,
+    group_by: render::GroupBy,
+    collapsible: bool,
+    badges: bool,
+    /// Write the versions `bump` suggests and stage them.
+    apply: bool,
+    /// Where `--manifest` writes what each project would release.
+    manifest: Option<PathBuf>,
+    /// Where `--draft` writes the entries to edit, in place of the
+    /// changelog.
+    draft: Option<String>,
+    /// Open what was written in `$EDITOR` before it is committed or
+    /// announced.
+    edit: bool,
+    /// Publish without showing a preview and asking first.
+    yes: bool,
+    front_matter: Option<render::FrontMatter>,
+    deterministic: bool,
+    color: ColorChoice,
+    max_entry_length: Option<usize>,
+    max_per_section: Option<usize>,
+    /// Fail when entries break the `[lint]` rules.
+    lint: bool,
+    /// Correct what the lint can: lowercase starts and misspellings.
+    fix: bool,
+    /// `--yanked` or `--hotfix`.
+    release_marker: Option<render::ReleaseMarker>,
+    /// A message to check with `lint-commits`, as from a commit-msg hook.
+    message_file: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Project {
-    name: String,
-    commits: Vec<Commit>,
-    remote: String,
+fn main() {
+    let options = parse_args();
+
+    if options.mode == Mode::Render || options.mode == Mode::Convert {
+        let mut projects = load_project_list(&options.inputs[0], options.from);
+        if options.deterministic {
+            projects.sort_commits();
+        }
+        let render_options = render_options(&options, None, &projects);
+        for output in outputs(&options, None) {
+            generate_changelog(&projects, &options, &output, &render_options);
+        }
+        return;
+    }
+
+    // A draft already holds the entries, worded as they should read.
+    if options.mode == Mode::Finalize {
+        let path = &options.inputs[0];
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to read {}: {}", path, e)));
+        let draft = draft::Draft::parse(&text, path)
+            .unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to parse {}: {}", path, e)));
+        let generated_at = DateTime::parse_from_rfc3339(&draft.generated_at)
+            .unwrap_or_else(|e| fail(exit::FAILURE, format!("Invalid date in {}: {}", path, e)));
+        let projects = draft.into_projects();
+        let render_options = RenderOptions {
+            generated_at,
+            ..render_options(&options, None, &projects)
+        };
+        for output in outputs(&options, None) {
+            generate_changelog(&projects, &options, &output, &render_options);
+        }
+        return;
+    }
+
+    if options.mode == Mode::AuthLogin {
+        let host = &options.inputs[0];
+        let token = read_token(host);
+        keychain::set(host, &token).unwrap_or_else(|e| fail(exit::FAILURE, e));
+        println!("Stored the token for {}", host);
+        return;
+    }
+
+    if options.mode == Mode::AuthLogout {
+        let host = &options.inputs[0];
+        keychain::delete(host).unwrap_or_else(|e| fail(exit::FAILURE, e));
+        println!("Removed the token for {}", host);
+        return;
+    }
+
+    if options.mode == Mode::Diff {
+        let old = load_project_list(&options.inputs[0], options.from);
+        let new = load_project_list(&options.inputs[1], options.from);
+        let (report, changed) = diff::diff(&old, &new);
+
+        print!("{}", report);
+        std::process::exit(if changed { 1 } else { 0 });
+    }
+
+    if options.mode == Mode::Merge {
+        let [a, b] = [&options.inputs[0], &options.inputs[1]].map(|path| {
+            fs::read_to_string(path)
+                .unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to read {}: {}", path, e)))
+        });
+        print!(
+            "{}",
+            merge::merge(&a, &b).unwrap_or_else(|e| fail(exit::FAILURE, e))
+        );
+        return;
+    }
+
+    let mut config = read_config(&options.overrides);
+
+    // From the commit-msg hook: one message, in the project's convention.
+    if let Some(file) = &options.message_file {
+        let message = fs::read_to_string(file)
+            .unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to read {}: {}", file, e)));
+        let message = convention::strip_comments(&message);
+        let convention = match options.projects.first() {
+            Some(project) => config.convention(project),
+            None => &config.convention,
+        };
+
+        let problems = convention::check(&message, convention);
+        for problem in &problems {
+            eprintln!(
+                "{:?}: {}",
+                message.lines().next().unwrap_or_default(),
+                problem
+            );
+        }
+        if !problems.is_empty() {
+            fail(exit::LINT, "Commit message does not follow the convention");
+        }
+        return;
+    }
+    select_projects(&mut config, &options);
+
+    if options.mode == Mode::List {
+        list_projects(&config);
+        return;
+    }
+
+    if options.mode == Mode::InstallHooks {
+        let report = commit_hook::install(&config).unwrap_or_else(|e| fail(exit::FAILURE, e));
+        for line in report {
+            println!("{}", line);
+        }
+        return;
+    }
+
+    hooks::run("pre", &config.hooks.pre, &config.paths);
+
+    let mut project_list = plugin::run(&config.plugins, process_projects(&config, &options))
+        .unwrap_or_else(|e| fail(exit::FAILURE, e));
+    if options.deterministic {
+        project_list.sort_commits();
+    }
+    let render_options = render_options(&options, Some(&config), &project_list);
+
+    if options.verbose {
+        for project in &project_list.projects {
+            let skipped = project.commits.iter().filter(|c| c.is_skipped()).count();
+            eprintln!(
+                "{}: {} commits, {} skipped by marker",
+                project.name,
+                project.commits.len(),
+                skipped
+            );
+
+            let convention = config.convention(&project.name);
+            for commit in &project.commits {
+                if let Some(scope) = convention::unknown_scope(&commit.message, convention) {
+                    eprintln!(
+                        "{}: {} has unknown scope {}",
+                        project.name,
+                        commit.hash.get(..8).unwrap_or(&commit.hash),
+                        scope
+                    );
+                }
+            }
+        }
+    }
+
+    if options.fail_if_empty
+        && project_list
+            .projects
+            .iter()
+            .all(|project| render::sections(project, &render_options).is_empty())
+    {
+        fail(exit::EMPTY, "No feat or fix commits found");
+    }
+
+    // Entries are linted wherever a changelog is produced, whenever
+    // `[lint]` is set up, and with its defaults on `--lint` or `--fix`.
+    let renders_entries = matches!(
+        options.mode,
+        Mode::Generate
+            | Mode::PublishConfluence
+            | Mode::PublishNotion
+            | Mode::PublishGitea
+            | Mode::Site
+    );
+    if renders_entries {
+        diagnostics::check_subjects(&mut project_list, !render_options.labels.is_empty());
+    }
+    if renders_entries && (config.lint.is_some() || options.lint || options.fix) {
+        let problems = lint::lint(&project_list, &render_options, &config);
+        for problem in &problems {
+            eprintln!("{}", problem);
+        }
+        if options.lint && !problems.is_empty() {
+            fail(
+                exit::LINT,
+                format!(
+                    "{} lint {} in changelog entries",
+                    problems.len(),
+                    if problems.len() == 1 {
+                        "problem"
+                    } else {
+                        "problems"
+                    }
+                ),
+            );
+        }
+    }
+
+    // Files the changelog went to, for the summary.
+    let mut written = vec![];
+    match options.mode {
+        Mode::Render
+        | Mode::Convert
+        | Mode::Diff
+        | Mode::Merge
+        | Mode::List
+        | Mode::InstallHooks
+        | Mode::Finalize
+        | Mode::AuthLogin
+        | Mode::AuthLogout => {
+            unreachable!("handled without collecting from git")
+        }
+        Mode::Generate if options.draft.is_some() => {
+            let path = options.draft.as_deref().unwrap_or_default();
+            let draft = draft::Draft::new(&project_list, &render_options)
+                .to_string(path)
+                .unwrap_or_else(|e| fail(exit::FAILURE, e));
+            fs::write(path, draft).unwrap_or_else(|e| {
+                fail(exit::FAILURE, format!("Failed to write {}: {}", path, e))
+            });
+            eprintln!("Wrote {}; edit it, then run finalize {}", path, path);
+            written.push(PathBuf::from(path));
+        }
+        Mode::Generate => {
+            let outputs = outputs(&options, Some(&config));
+            for output in &outputs {
+                written.extend(generate_changelog(
+                    &project_list,
+                    &options,
+                    output,
+                    &render_options,
+                ));
+            }
+
+            // With --commit-back, the committed files are edited instead.
+            if options.edit && !options.commit_back {
+                for path in &written {
+                    edit(path);
+                }
+            }
+
+            if options.badges {
+                badges::write(&project_list, &render_options);
+            }
+
+            if let Some(path) = &options.manifest {
+                let manifest =
+                    manifest::manifest(&project_list, &render_options, &config, |project| {
+                        notes_path(&options, &outputs, project)
+                            .map(|path| path.display().to_string())
+                    })
+                    .unwrap_or_else(|e| fail(exit::CONFIG, e));
+                fs::write(path, manifest)
+                    .unwrap_or_else(|_| panic!("Failed to write {}", path.display()));
+            }
+
+            if options.commit_back {
+                // The file is committed, so it never carries terminal colors.
+                let render_options = RenderOptions {
+                    color: false,
+                    ..render_options
+                };
+                commit_back(&project_list, &options, &render_options);
+            }
+        }
+        Mode::PublishConfluence => {
+            let confluence = config
+                .confluence
+                .as_ref()
+                .unwrap_or_else(|| fail(exit::CONFIG, "No [confluence] section in config"));
+            let mut storage = render::storage(&render_options, &project_list);
+            // Once edited, the preview is the page as it will be stored.
+            let preview = if options.edit {
+                storage = edit_text(&storage, "html");
+                storage.clone()
+            } else {
+                render::document(Format::Text, &render_options, &project_list)
+            };
+            approve(&options, &preview, "Publish this changelog to Confluence?");
+
+            match confluence::publish(confluence, &config.http, &storage) {
+                Ok(()) => println!("Published changelog to Confluence"),
+                Err(e) => fail(
+                    exit::FAILURE,
+                    format!("Failed to publish to Confluence: {}", e),
+                ),
+            }
+        }
+        Mode::Standup => {
+            let today = Local::now().date_naive();
+            let since = today - chrono::Duration::days(standup::days_back(today));
+            let summary = standup::summary(&project_list, since);
+
+            if options.post_slack {
+                approve(&options, &summary, "Post this standup to Slack?");
+                match standup::post_to_slack(&config.http, &summary) {
+                    Ok(()) => println!("Posted standup to Slack"),
+                    Err(e) => fail(exit::FAILURE, format!("Failed to post to Slack: {}", e)),
+                }
+            } else {
+                print!("{}", summary);
+            }
+        }
+        Mode::Handoff => print!(
+            "{}",
+            handoff::summary(
+                &project_list,
+                &config.oncall,
+                &render_options.generated_at.format("%Y-%m-%d").to_string()
+            )
+        ),
+        Mode::Who => {
+            let path = options.path.as_deref().expect("checked in parse_args");
+            let authors = who::who(&project_list, path);
+            if authors.is_empty() {
+                fail(exit::EMPTY, format!("No commits touch {}", path));
+            }
+            print!("{}", authors);
+        }
+        Mode::Search => {
+            let results = search::results(&project_list, &render_options);
+            if results.is_empty() {
+                fail(exit::EMPTY, "No commits match");
+            }
+            print!("{}", results);
+        }
+        Mode::Hotspots => print!(
+            "{}",
+            hotspots::hotspots(&project_list, &config).render(options.format)
+        ),
+        Mode::Heatmap => {
+            let heatmap =
+                heatmap::Heatmap::new(&project_list, options.days, Local::now().date_naive());
+            if options.svg {
+                print!("{}", heatmap.svg());
+            } else {
+                print!("{}", heatmap.terminal(use_color(&options)));
+            }
+        }
+        Mode::Stats if options.time_of_day => print!(
+            "{}",
+            stats::time_of_day(&project_list, &config.calendar).render(options.format)
+        ),
+        Mode::Stats if options.compare.is_some() => print!(
+            "{}",
+            stats::compare(
+                &project_list,
+                options.compare.as_deref().unwrap_or_default()
+            )
+            .render(options.format)
+        ),
+        Mode::Stats => print!(
+            "{}",
+            stats::stats(&project_list, options.days, Local::now().date_naive())
+                .render(options.format)
+        ),
+        Mode::Bump => {
+            let (changes, skipped) = bump::plan(&project_list, &render_options, &config)
+                .unwrap_or_else(|e| fail(exit::CONFIG, e));
+            for line in skipped {
+                eprintln!("{}", line);
+            }
+
+            for change in &changes {
+                println!(
+                    "{}: {} {} -> {} ({})",
+                    change.project.name,
+                    change.file,
+                    change.from,
+                    change.to,
+                    change.bump.name()
+                );
+                if options.apply {
+                    bump::apply(change).unwrap_or_else(|e| fail(exit::FAILURE, e));
+                    // Mercurial has no staging area; the file is committed
+                    // as modified.
+                    if !change.project.path.join(".hg").is_dir() {
+                        run_git(&change.project.path, &["add", change.file]);
+                    }
+                }
+            }
+        }
+        Mode::Report => print!("{}", report::report(&project_list, options.report_by)),
+        Mode::LintCommits => {
+            let problems = lint::lint_commits(&project_list, &config);
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            let count: usize = project_list.projects.iter().map(|p| p.commits.len()).sum();
+            if !problems.is_empty() {
+                fail(
+                    exit::LINT,
+                    format!(
+                        "{} {} in {} {}",
+                        problems.len(),
+                        if problems.len() == 1 {
+                            "problem"
+                        } else {
+                            "problems"
+                        },
+                        count,
+                        if count == 1 { "commit" } else { "commits" }
+                    ),
+                );
+            }
+            println!("All {} commits follow the convention", count);
+        }
+        Mode::Site => {
+            site::save_run(&project_list);
+            site::build(&render_options);
+        }
+        Mode::PublishGitea => {
+            let tag = options.tag.as_deref().expect("checked in parse_args");
+            // Release notes are markdown, whatever --format says.
+            let notes: Vec<String> = project_list
+                .projects
+                .iter()
+                .map(|project| render::project_document(Format::Markdown, &render_options, project))
+                .map(|notes| {
+                    if options.edit {
+                        edit_text(&notes, "md")
+                    } else {
+                        notes
+                    }
+                })
+                .collect();
+            approve(
+                &options,
+                &notes.concat(),
+                &format!("Publish these notes as release {} on Gitea?", tag),
+            );
+
+            for (project, notes) in project_list.projects.iter().zip(&notes) {
+                match gitea::publish_release(&config.hosts(), &project.remote, tag, notes) {
+                    Ok(()) => println!("Published {} release {}", project.name, tag),
+                    Err(e) => fail(
+                        exit::FAILURE,
+                        format!("Failed to publish {} release: {}", project.name, e),
+                    ),
+                }
+            }
+        }
+        Mode::PublishNotion => {
+            let notion = config
+                .notion
+                .as_ref()
+                .unwrap_or_else(|| fail(exit::CONFIG, "No [notion] section in config"));
+            approve(
+                &options,
+                &render::document(Format::Text, &render_options, &project_list),
+                "Publish this changelog to Notion?",
+            );
+
+            match notion::publish(notion, &render_options, &project_list) {
+                Ok(()) => println!("Published changelog to Notion"),
+                Err(e) => fail(exit::FAILURE, format!("Failed to publish to Notion: {}", e)),
+            }
+        }
+    }
+
+    hooks::run("post", &config.hooks.post, &config.paths);
+
+    if options.mode == Mode::Generate {
+        let mut summary = Summary::new(&project_list.projects).to_string();
+        if !written.is_empty() {
+            let paths: Vec<_> = written
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            summary.push_str(&format!(", wrote {}", paths.join(", ")));
+        }
+        eprintln!("{}", summary);
+    }
+    eprint!("{}", project_list.diagnostics.report());
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ProjectList {
-    projects: Vec<Project>,
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {0} <author_name|*> <days> [options]
+       {0} <author_name|*> --milestone <name> [options]
+       {0} <author_name|*> --since-release [options]
+       {0} <author_name|*> --range <preset> [options]
+       {0} publish <confluence|notion> <author_name> <days>
+       {0} publish gitea <author_name> <days> --tag <tag>
+       {0} site <author_name> <days>
+       {0} report <author_name|*> <days> [--by <day|week>]
+       {0} standup <author_name> [--post-slack]
+       {0} handoff <author_name|*> <days>   fixes, reverts and commits mentioning
+                            [oncall] incident_keys tickets, for the next on call
+       {0} stats <author_name|*> <days> [--time-of-day] [--format <json|yaml|toml>]
+       {0} stats --compare <author>,<author>[,...] <days>   commits per project and
+                            type side by side
+       {0} heatmap <author_name|*> <days> [--svg]
+       {0} search <regex> <days> [options]
+       {0} who --path <path|glob> [days]
+       {0} hotspots <author_name|*> <days> [--format <json|yaml|toml>]
+       {0} bump <author_name|*> <days> [--apply]   suggest versions; --apply writes
+                            them to Cargo.toml or package.json and stages them
+       {0} lint-commits <author_name|*> <days>   check messages against [convention]
+       {0} lint-commits --message-file <file> [--project <name>]
+       {0} hooks install [--project <name|glob>]   add a commit-msg hook running lint-commits
+       {0} auth login <host>   keep a token for e.g. github.com in the system
+                            keychain, used when its variable is not set
+       {0} auth logout <host>
+       {0} render --input <data.json|data.toml|CHANGELOG.md> [options]
+       {0} diff <old.json> <new.json>
+       {0} merge <a.md> <b.md>   combine two markdown changelogs on stdout
+       {0} convert [--from <md|json|yaml|toml>] --to <format> <file>
+                            e.g. an old CHANGELOG.md to json on stdout
+       {0} finalize <draft.toml|draft.yaml> [options]   render an edited --draft
+       {0} list
+
+Options:
+  --format <md|asciidoc|rst|atom|pdf|html|mdbook|slack|text|json|yaml|toml|gha>
+                            repeatable, to write several formats in one run
+  --stdout
+  --from <md|json|yaml|toml>  how render, diff and convert read their files,
+                            by default from the extension
+  --split-per-project
+  --commit-back [--commit-branch <branch>] [--open-pr]
+  --fail-if-empty
+  --verbose
+  --include-bots
+  --expand-squash
+  --enrich-prs
+  --offline                 skip --enrich-prs and anything else that needs
+                            a host's API
+  --milestone <name>
+  --since-release           everything since the previous release tag; the
+                            last stable one unless [release] prereleases
+  --tag <tag>
+  --yanked | --hotfix       mark the release, e.g. [YANKED] after its date;
+                            kept when --commit-back adds it to a
+                            keepachangelog.com CHANGELOG.md under --tag
+  --toc                     link to every project at the top (md, html, asciidoc, rst)
+  --ticket-index            end with each ticket mentioned, like ENG-421, and
+                            the commits mentioning it in any project (md,
+                            html, asciidoc, rst)
+  --group-by <team|ticket>  arrange the changelog under each mentioned
+                            ticket instead of teams, with the commits of
+                            every project mentioning it beneath
+  --collapsible             fold each project into <details> (md)
+  --front-matter <hugo|jekyll|zola>   for markdown in a static site
+  --color <never|auto|always>  colors on a terminal, see [theme]; auto
+                            honors NO_COLOR
+  --deterministic           date documents by the newest commit, or
+                            SOURCE_DATE_EPOCH, and order commits stably
+  --max-entry-length <n>    cut longer entries short with …
+  --max-per-section <n>     show the newest n entries and link the rest
+  --lint                    fail when entries break the [lint] rules
+  --fix                     capitalize entries and correct [lint] misspellings
+  --badges                  also write badges/<project>.json and .svg
+  --manifest <file>         also write each project's suggested version,
+                            breaking flag, commit count and notes as json
+  --edit                    open the changelog in $EDITOR before it is
+                            committed or published and post hooks run; a
+                            non-zero exit aborts. For changelog files,
+                            publish confluence and publish gitea
+  --yes, -y                 publish without a preview and confirmation,
+                            which need a terminal otherwise
+  --draft <file.toml|file.yaml>   write the entries to edit instead of the
+                            changelog; finalize renders the result
+  --business-days <n>       the last n working days, see [calendar]
+  --range <this-week|last-week|this-month|last-month|this-sprint|last-sprint>
+  --project <name|glob>     only these projects; repeatable
+  --exclude-project <name|glob>
+  --team <name>             everyone on a [teams] roster, in place of the
+                            author; for changelogs, stats and reports alike
+  --only-touching <path|glob>   only commits changing such files, e.g.
+                            \"*.rs\" or services/; repeatable
+  --set <key.path=value>    override a config key; also CHENJIROGU_KEY__PATH
+
+Exit codes: 1 publishing failed, 2 usage, 3 config error, 4 git/hg error,
+5 no feat/fix commits with --fail-if-empty, or no search matches, 6 lint
+problems with --lint or lint-commits",
+        program
+    );
+    std::process::exit(exit::USAGE);
 }
 
-fn main() {
+fn parse_args() -> Options {
     let args: Vec<String> = std::env::args().collect();
+    let mut positional = vec![];
+    let mut mode = Mode::Generate;
+    let mut split_per_project = false;
+    let mut commit_back = false;
+    let mut commit_branch = None;
+    let mut open_pr = false;
+    let mut formats = vec![];
+    let mut stdout = false;
+    let mut inputs = vec![];
+    let mut from = None;
+    let mut fail_if_empty = false;
+    let mut verbose = false;
+    let mut include_bots = false;
+    let mut expand_squash = false;
+    let mut enrich_prs = false;
+    let mut offline = false;
+    let mut milestone = None;
+    let mut since_release = false;
+    let mut tag = None;
+    let mut overrides = vec![];
+    let mut projects = vec![];
+    let mut excluded_projects = vec![];
+    let mut only_touching = vec![];
+    let mut report_by = report::Period::Day;
+    let mut post_slack = false;
+    let mut range = None;
+    let mut business_days = None;
+    let mut svg = false;
+    let mut time_of_day = false;
+    let mut compare = None;
+    let mut team = None;
+    let mut path = None;
+    let mut toc = false;
+    let mut ticket_index = false;
+    let mut group_by = render::GroupBy::Team;
+    let mut collapsible = false;
+    let mut badges = false;
+    let mut manifest = None;
+    let mut draft = None;
+    let mut edit = false;
+    let mut yes = false;
+    let mut apply = false;
+    let mut front_matter = None;
+    let mut deterministic = false;
+    let mut color = ColorChoice::Auto;
+    let mut max_entry_length = None;
+    let mut max_per_section = None;
+    let mut lint = false;
+    let mut message_file = None;
+    let mut release_marker = None;
+    let mut fix = false;
+
+    let mut iter = args[1..].iter().peekable();
 
-    if args.len() != 3 {
-        eprintln!("Usage: {} <author_name> <days>", args[0]);
-        std::process::exit(1);
+    match iter.peek().map(|arg| arg.as_str()) {
+        Some("publish") => {
+            iter.next();
+            mode = match iter.next().map(|arg| arg.as_str()) {
+                Some("confluence") => Mode::PublishConfluence,
+                Some("notion") => Mode::PublishNotion,
+                Some("gitea") => Mode::PublishGitea,
+                _ => usage(&args[0]),
+            };
+        }
+        Some("site") => {
+            iter.next();
+            mode = Mode::Site;
+        }
+        Some("render") => {
+            iter.next();
+            mode = Mode::Render;
+        }
+        Some("diff") => {
+            iter.next();
+            mode = Mode::Diff;
+        }
+        Some("merge") => {
+            iter.next();
+            mode = Mode::Merge;
+        }
+        Some("convert") => {
+            iter.next();
+            mode = Mode::Convert;
+        }
+        Some("finalize") => {
+            iter.next();
+            mode = Mode::Finalize;
+        }
+        Some("who") => {
+            iter.next();
+            mode = Mode::Who;
+        }
+        Some("search") => {
+            iter.next();
+            mode = Mode::Search;
+        }
+        Some("hotspots") => {
+            iter.next();
+            mode = Mode::Hotspots;
+        }
+        Some("heatmap") => {
+            iter.next();
+            mode = Mode::Heatmap;
+        }
+        Some("bump") => {
+            iter.next();
+            mode = Mode::Bump;
+        }
+        Some("stats") => {
+            iter.next();
+            mode = Mode::Stats;
+        }
+        Some("standup") => {
+            iter.next();
+            mode = Mode::Standup;
+        }
+        Some("handoff") => {
+            iter.next();
+            mode = Mode::Handoff;
+        }
+        Some("report") => {
+            iter.next();
+            mode = Mode::Report;
+        }
+        Some("list") => {
+            iter.next();
+            mode = Mode::List;
+        }
+        Some("lint-commits") => {
+            iter.next();
+            mode = Mode::LintCommits;
+        }
+        Some("hooks") => {
+            iter.next();
+            mode = match iter.next().map(|arg| arg.as_str()) {
+                Some("install") => Mode::InstallHooks,
+                _ => usage(&args[0]),
+            };
+        }
+        Some("auth") => {
+            iter.next();
+            mode = match iter.next().map(|arg| arg.as_str()) {
+                Some("login") => Mode::AuthLogin,
+                Some("logout") => Mode::AuthLogout,
+                _ => usage(&args[0]),
+            };
+        }
+        _ => {}
     }
 
-    let author_name = &args[1];
-    let days: i64 = args[2].parse().expect("Failed to parse the number of days");
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--split-per-project" => split_per_project = true,
+            "--commit-back" => commit_back = true,
+            "--open-pr" => open_pr = true,
+            "--stdout" => stdout = true,
+            "--fail-if-empty" => fail_if_empty = true,
+            "--verbose" | "-v" => verbose = true,
+            "--include-bots" => include_bots = true,
+            "--expand-squash" => expand_squash = true,
+            "--enrich-prs" => enrich_prs = true,
+            "--offline" => offline = true,
+            "--post-slack" => post_slack = true,
+            "--svg" => svg = true,
+            "--toc" => toc = true,
+            "--ticket-index" => ticket_index = true,
+            "--group-by" => {
+                group_by = iter
+                    .next()
+                    .and_then(|name| render::GroupBy::parse(name))
+                    .unwrap_or_else(|| usage(&args[0]))
+            }
+            "--collapsible" => collapsible = true,
+            "--badges" => badges = true,
+            "--apply" => apply = true,
+            "--edit" => edit = true,
+            "--yes" | "-y" => yes = true,
+            "--manifest" => {
+                manifest = Some(PathBuf::from(
+                    iter.next().unwrap_or_else(|| usage(&args[0])),
+                ))
+            }
+            "--draft" => draft = Some(iter.next().unwrap_or_else(|| usage(&args[0])).clone()),
+            "--time-of-day" => time_of_day = true,
+            "--team" => team = Some(iter.next().unwrap_or_else(|| usage(&args[0])).clone()),
+            "--compare" => {
+                let authors: Vec<String> = iter
+                    .next()
+                    .unwrap_or_else(|| usage(&args[0]))
+                    .split(',')
+                    .map(|author| author.trim().to_string())
+                    .filter(|author| !author.is_empty())
+                    .collect();
+                if authors.len() < 2 {
+                    usage(&args[0]);
+                }
+                compare = Some(authors);
+            }
+            "--commit-branch" => {
+                commit_branch = Some(iter.next().unwrap_or_else(|| usage(&args[0])).clone())
+            }
+            "--since-release" => since_release = true,
+            "--milestone" => {
+                milestone = Some(iter.next().unwrap_or_else(|| usage(&args[0])).clone())
+            }
+            // git reports paths with forward slashes, whatever the platform.
+            "--path" => {
+                path = Some(
+                    iter.next()
+                        .unwrap_or_else(|| usage(&args[0]))
+                        .replace('\\', "/"),
+                )
+            }
+            "--tag" => tag = Some(iter.next().unwrap_or_else(|| usage(&args[0])).clone()),
+            "--set" => overrides.push(iter.next().unwrap_or_else(|| usage(&args[0])).clone()),
+            "--project" => projects.push(iter.next().unwrap_or_else(|| usage(&args[0])).clone()),
+            "--exclude-project" => {
+                excluded_projects.push(iter.next().unwrap_or_else(|| usage(&args[0])).clone())
+            }
+            "--only-touching" => only_touching.push(
+                iter.next()
+                    .unwrap_or_else(|| usage(&args[0]))
+                    .replace('\\', "/"),
+            ),
+            "--by" => {
+                report_by = iter
+                    .next()
+                    .and_then(|name| report::Period::parse(name))
+                    .unwrap_or_else(|| usage(&args[0]))
+            }
+            "--range" => {
+                range = Some(
+                    iter.next()
+                        .and_then(|name| range::Preset::parse(name))
+                        .unwrap_or_else(|| usage(&args[0])),
+                )
+            }
+            "--max-entry-length" => {
+                max_entry_length = Some(
+                    iter.next()
+                        .and_then(|length| length.parse().ok())
+                        .filter(|length| *length > 0)
+                        .unwrap_or_else(|| usage(&args[0])),
+                )
+            }
+            "--max-per-section" => {
+                max_per_section = Some(
+                    iter.next()
+                        .and_then(|count| count.parse().ok())
+                        .filter(|count| *count > 0)
+                        .unwrap_or_else(|| usage(&args[0])),
+                )
+            }
+            "--business-days" => {
+                business_days = Some(
+                    iter.next()
+                        .and_then(|days| days.parse().ok())
+                        .filter(|days| *days > 0)
+                        .unwrap_or_else(|| usage(&args[0])),
+                )
+            }
+            "--front-matter" => {
+                front_matter = Some(
+                    iter.next()
+                        .and_then(|name| render::FrontMatter::parse(name))
+                        .unwrap_or_else(|| usage(&args[0])),
+                )
+            }
+            "--deterministic" => deterministic = true,
+            "--lint" => lint = true,
+            "--yanked" | "--hotfix" => {
+                // A release is one or the other.
+                if release_marker.is_some() {
+                    usage(&args[0]);
+                }
+                release_marker = Some(if arg == "--yanked" {
+                    render::ReleaseMarker::Yanked
+                } else {
+                    render::ReleaseMarker::Hotfix
+                });
+            }
+            "--fix" => fix = true,
+            "--color" => {
+                color = parse_color(iter.next().unwrap_or_else(|| usage(&args[0])))
+                    .unwrap_or_else(|| usage(&args[0]))
+            }
+            _ if arg.starts_with("--color=") => {
+                color = parse_color(&arg["--color=".len()..]).unwrap_or_else(|| usage(&args[0]))
+            }
+            "--message-file" => {
+                message_file = Some(iter.next().unwrap_or_else(|| usage(&args[0])).clone())
+            }
+            "--input" => inputs.push(iter.next().unwrap_or_else(|| usage(&args[0])).clone()),
+            "--format" | "--to" => formats.push(
+                iter.next()
+                    .and_then(|name| Format::parse(name))
+                    .unwrap_or_else(|| usage(&args[0])),
+            ),
+            "--from" => {
+                from = Some(
+                    iter.next()
+                        .and_then(|name| Format::parse(name))
+                        .filter(|format| {
+                            matches!(
+                                format,
+                                Format::Markdown | Format::Json | Format::Yaml | Format::Toml
+                            )
+                        })
+                        .unwrap_or_else(|| usage(&args[0])),
+                )
+            }
+            _ if arg.starts_with("--") => usage(&args[0]),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    // A single message is only checked, and hooks only written.
+    if message_file.is_some() && mode != Mode::LintCommits {
+        usage(&args[0]);
+    }
+    if mode == Mode::InstallHooks || message_file.is_some() {
+        if !positional.is_empty() {
+            usage(&args[0]);
+        }
+        positional = vec![String::new(), "0".to_string()];
+    }
+
+    // Working on saved data, or only describing the projects, needs
+    // neither an author nor a window.
+    if mode == Mode::List {
+        if !positional.is_empty() {
+            usage(&args[0]);
+        }
+        positional = vec![String::new(), "0".to_string()];
+    }
+
+    if mode == Mode::Render {
+        if !positional.is_empty() || inputs.len() != 1 {
+            usage(&args[0]);
+        }
+        positional = vec![String::new(), "0".to_string()];
+    }
+
+    // A conversion takes its file as an argument and prints one format.
+    if mode == Mode::Convert {
+        if positional.len() != 1 || !inputs.is_empty() || formats.len() != 1 {
+            usage(&args[0]);
+        }
+        inputs = std::mem::replace(&mut positional, vec![String::new(), "0".to_string()]);
+        stdout = true;
+    }
+    if matches!(mode, Mode::Finalize | Mode::AuthLogin | Mode::AuthLogout) {
+        if positional.len() != 1 || !inputs.is_empty() {
+            usage(&args[0]);
+        }
+        inputs = std::mem::replace(&mut positional, vec![String::new(), "0".to_string()]);
+    }
+    if from.is_some() && !matches!(mode, Mode::Render | Mode::Convert | Mode::Diff) {
+        usage(&args[0]);
+    }
+
+    if mode == Mode::Diff || mode == Mode::Merge {
+        if positional.len() != 2 {
+            usage(&args[0]);
+        }
+        inputs = std::mem::replace(&mut positional, vec![String::new(), "0".to_string()]);
+    }
+
+    // A search is across all authors, so its first argument is the pattern.
+    let mut grep = None;
+    if mode == Mode::Search {
+        if positional.is_empty() {
+            usage(&args[0]);
+        }
+        grep = Some(std::mem::replace(&mut positional[0], "*".to_string()));
+    }
 
-    let config = read_config();
-    let project_list = process_projects(&config, author_name, days);
-    generate_changelog(&project_list);
+    // `who` looks at every author, over the whole history unless given a
+    // number of days; 0 stands for that.
+    if mode == Mode::Who {
+        if path.is_none() || positional.len() > 1 {
+            usage(&args[0]);
+        }
+        positional.insert(0, "*".to_string());
+        if positional.len() == 1 {
+            positional.push("0".to_string());
+        }
+    }
+
+    // Compared authors stand in for the author argument.
+    if compare.is_some() {
+        if mode != Mode::Stats || time_of_day {
+            usage(&args[0]);
+        }
+        positional.insert(0, "*".to_string());
+    }
+    // So does a team, for any mode that takes an author.
+    if team.is_some() {
+        positional.insert(0, "*".to_string());
+    }
+
+    // A standup covers everything since the start of the last business
+    // day, which git's rolling window only reaches with a day to spare.
+    if mode == Mode::Standup {
+        if positional.len() != 1 {
+            usage(&args[0]);
+        }
+        positional.push((standup::days_back(Local::now().date_naive()) + 1).to_string());
+    }
+
+    // A milestone, the previous release, a named range or working days
+    // replace the window of days, and only one of them can.
+    let windows = [
+        milestone.is_some(),
+        since_release,
+        range.is_some(),
+        business_days.is_some(),
+    ];
+    match windows.iter().filter(|set| **set).count() {
+        0 => {}
+        1 if positional.len() == 1 => positional.push("0".to_string()),
+        1 => {}
+        _ => usage(&args[0]),
+    }
+
+    if positional.len() != 2 {
+        usage(&args[0]);
+    }
+
+    if manifest.is_some() && mode != Mode::Generate {
+        usage(&args[0]);
+    }
+    // Notion pages are built from the commits rather than from text.
+    if edit && mode == Mode::PublishNotion {
+        fail(
+            exit::USAGE,
+            "--edit works with changelog files, publish confluence and publish gitea, not publish notion",
+        );
+    }
+    // Only files and published text can be edited.
+    let editable = (mode == Mode::Generate && !stdout)
+        || matches!(mode, Mode::PublishConfluence | Mode::PublishGitea);
+    if edit && !editable {
+        usage(&args[0]);
+    }
+    // Only publishing asks before it goes ahead.
+    let publishes = matches!(
+        mode,
+        Mode::PublishConfluence | Mode::PublishNotion | Mode::PublishGitea
+    ) || (mode == Mode::Standup && post_slack)
+        || open_pr;
+    if yes && !publishes {
+        usage(&args[0]);
+    }
+    // Pull request lookups are skipped offline; these cannot do without.
+    if offline && (publishes || milestone.is_some()) {
+        usage(&args[0]);
+    }
+    // A draft stands in for every output, so there is nothing to commit.
+    if draft.is_some() && (mode != Mode::Generate || commit_back || manifest.is_some()) {
+        usage(&args[0]);
+    }
+    if apply && mode != Mode::Bump {
+        usage(&args[0]);
+    }
+
+    if mode == Mode::PublishGitea && tag.is_none() {
+        usage(&args[0]);
+    }
+
+    // A pull request needs both the changelog commit and a branch to hold it.
+    if open_pr && (!commit_back || commit_branch.is_none()) {
+        usage(&args[0]);
+    }
+
+    // Several documents on stdout could not be told apart.
+    if stdout && formats.len() > 1 {
+        usage(&args[0]);
+    }
+
+    Options {
+        mode,
+        author_name: positional[0].clone(),
+        days: positional[1].parse().unwrap_or_else(|_| usage(&args[0])),
+        split_per_project,
+        commit_back,
+        commit_branch,
+        open_pr,
+        format: formats.first().copied().unwrap_or(Format::Markdown),
+        formats,
+        stdout,
+        inputs,
+        from,
+        fail_if_empty,
+        verbose,
+        include_bots,
+        expand_squash,
+        enrich_prs,
+        offline,
+        milestone,
+        since_release,
+        tag,
+        overrides,
+        projects,
+        excluded_projects,
+        only_touching,
+        report_by,
+        post_slack,
+        range,
+        business_days,
+        svg,
+        time_of_day,
+        compare,
+        team,
+        grep,
+        path,
+        toc,
+        ticket_index,
+        group_by,
+        collapsible,
+        badges,
+        apply,
+        manifest,
+        draft,
+        edit,
+        yes,
+        front_matter,
+        deterministic,
+        color,
+        max_entry_length,
+        max_per_section,
+        lint,
+        fix,
+        message_file,
+        release_marker,
+    }
 }
 
-fn get_remote(path: &str) -> String {
-    let remote_command = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("remote")
-        .arg("get-url")
-        .arg("origin")
-        .output()
-        .expect("failed to execute git remote get-url origin");
+fn parse_color(name: &str) -> Option<ColorChoice> {
+    match name {
+        "never" => Some(ColorChoice::Never),
+        "auto" => Some(ColorChoice::Auto),
+        "always" => Some(ColorChoice::Always),
+        _ => None,
+    }
+}
 
-    let remote_command = String::from_utf8_lossy(&remote_command.stdout);
-    let trimmed_remote_command = remote_command.trim();
-    trimmed_remote_command.to_string()
+/// `--color` wins over `NO_COLOR`, which only turns off the automatic
+/// colors; see https://no-color.org.
+fn use_color(options: &Options) -> bool {
+    match options.color {
+        ColorChoice::Never => false,
+        ColorChoice::Always => true,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && std::io::stdout().is_terminal()
+        }
+    }
 }
 
-fn get_log(path: &str, days: i64) -> String {
-    let log_command = Command::new("git")
-        .arg("-C")
-        .arg(path)
-        .arg("log")
-        .arg("--since")
-        .arg(format!("{} days ago", days))
-        .arg("--pretty=format:%H,%s,%an,%ae,%ad")
-        .output()
-        .expect("failed to execute git log");
+/// Narrows the configured projects to the `--project` patterns, minus the
+/// `--exclude-project` ones.
+fn select_projects(config: &mut Config, options: &Options) {
+    // A name that selects nothing is most likely a typo.
+    for pattern in &options.projects {
+        if !config.paths.keys().any(|name| glob::matches(pattern, name)) {
+            fail(
+                exit::CONFIG,
+                format!("--project {} matches no configured project", pattern),
+            );
+        }
+    }
+
+    config.paths.retain(|name, _| {
+        (options.projects.is_empty()
+            || options
+                .projects
+                .iter()
+                .any(|pattern| glob::matches(pattern, name)))
+            && !options
+                .excluded_projects
+                .iter()
+                .any(|pattern| glob::matches(pattern, name))
+    });
+}
 
-    let log_command = String::from_utf8_lossy(&log_command.stdout);
+/// Prints one line per configured project, to check what a run would scan.
+fn list_projects(config: &Config) {
+    let mut rows = vec![[
+        "NAME".to_string(),
+        "PATH".to_string(),
+        "REMOTE".to_string(),
+        "BRANCH".to_string(),
+        "LAST COMMIT".to_string(),
+    ]];
 
-    log_command.to_string()
+    for (name, path) in &config.paths {
+        let backend = vcs::backend(path);
+        let remote = backend.remote(path);
+
+        rows.push([
+            name.to_string(),
+            path.display().to_string(),
+            if remote.is_empty() {
+                "-".to_string()
+            } else {
+                remote
+            },
+            backend
+                .default_branch(path)
+                .unwrap_or_else(|| "-".to_string()),
+            backend
+                .last_commit_date(path)
+                .unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in &rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
 }
 
-fn read_config() -> PathsConfig {
-    let config_str = fs::read_to_string("config.toml").expect("Failed to open config file");
-    toml::from_str(&config_str).expect("Failed to parse config")
+/// Loads data previously exported with `--format json` or `--format toml`.
+/// Reads a `--input` file as `format`, or as its extension says.
+fn load_project_list(path: &str, format: Option<Format>) -> ProjectList {
+    let data = fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to open {}", path));
+    let format = format.unwrap_or_else(|| {
+        let extension = Path::new(path).extension().unwrap_or_default();
+        Format::parse(&extension.to_string_lossy()).unwrap_or(Format::Json)
+    });
+
+    match format {
+        Format::Toml => toml::from_str(&data).map_err(|e| e.to_string()),
+        Format::Yaml => yaml::from_str(&data),
+        Format::Markdown => {
+            // A keepachangelog.com file is named after the directory it
+            // sits in.
+            let path = Path::new(path);
+            let name = fs::canonicalize(path)
+                .ok()
+                .and_then(|path| Some(path.parent()?.file_name()?.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| {
+                    path.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                });
+            import::markdown(&data, &name)
+        }
+        _ => json::from_str(&data),
+    }
+    .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path, e))
 }
 
-fn process_projects(config: &PathsConfig, author_name: &str, days: i64) -> ProjectList {
-    let mut project_list = ProjectList { projects: vec![] };
+fn process_projects(config: &Config, options: &Options) -> ProjectList {
+    let exclude_bots = config.bots.exclude && !options.include_bots;
+    let team = options.team.as_ref().map(|team| {
+        config
+            .teams
+            .get(team)
+            .unwrap_or_else(|| fail(exit::CONFIG, format!("No team {} in [teams]", team)))
+    });
+
+    let mut project_list = ProjectList {
+        projects: vec![],
+        diagnostics: Diagnostics::default(),
+    };
+
+    let today = Local::now().date_naive();
+    let dates = match (options.range, options.business_days) {
+        (Some(range), _) => Some(range.resolve(&config.sprint, today)),
+        (None, Some(days)) => Some(range::business_days(days, &config.calendar, today)),
+        (None, None) => None,
+    }
+    .map(|dates| dates.unwrap_or_else(|e| fail(exit::CONFIG, e)));
+    let enrich_cache = enrich::Cache::new(cache::DiskCache::new(&config.cache));
+    let mut names = Interner::default();
 
     for (name, path) in &config.paths {
-        let remote = get_remote(path);
+        let backend = vcs::backend(path);
 
+        let metadata = config.projects.get(name).cloned().unwrap_or_default();
         let mut project = Project {
             name: name.to_string(),
+            path: path.clone(),
             commits: vec![],
-            remote,
+            remote: backend.remote(path),
+            team: metadata.team,
+            description: metadata.description,
+            emoji: metadata.emoji,
+            homepage: metadata.homepage,
+            version: None,
+            anchor_group: None,
         };
 
-        let log_command = get_log(path, days);
-
-        for line in log_command.lines() {
-            let commit: Vec<&str> = line.split(",").collect();
-            if commit[2].to_string() == *author_name {
-                let commit = Commit {
-                    hash: commit[0].to_string(),
-                    message: commit[1].to_string(),
-                    author_name: commit[2].to_string(),
-                    author_email: commit[3].to_string(),
-                    date: commit[4].to_string(),
-                };
+        // In milestone mode the hosting service decides which commits belong
+        // to the release, and each is described by its pull request.
+        let mut pull_requests = HashMap::new();
+        let hashes: Vec<String>;
+        let previous_release = options
+            .since_release
+            .then(|| version::previous(&project, &config.release(name)))
+            .flatten();
+        let selection = match &options.milestone {
+            Some(milestone) => {
+                pull_requests =
+                    milestone::pull_requests(&config.hosts(), &project.remote, milestone)
+                        .unwrap_or_else(|e| {
+                            fail(
+                                exit::FAILURE,
+                                format!("Failed to load milestone for {}: {}", name, e),
+                            )
+                        })
+                        .into_iter()
+                        .collect();
+                hashes = pull_requests.keys().cloned().collect();
+                Selection::Commits(&hashes)
+            }
+            // A project without releases yet has all its history to list.
+            None if options.since_release => match &previous_release {
+                Some(release) => Selection::SinceTag(&release.tag),
+                None => Selection::All,
+            },
+            None => match dates {
+                Some((since, until)) => Selection::Dates { since, until },
+                None if options.mode == Mode::Who && options.days == 0 => Selection::All,
+                None => Selection::Days(options.days),
+            },
+        };
+
+        // Filtered as they are read, so only the commits kept are held. A
+        // repository that cannot be read is left out, not the whole run.
+        let log = backend.log(
+            path,
+            &selection,
+            options.grep.as_deref(),
+            &mut names,
+            &mut |mut commit| {
+                // `*` collects every author, for team-wide changelogs.
+                if options.author_name != "*" && *commit.author_name != *options.author_name {
+                    return;
+                }
+                if team.is_some_and(|team| !team.iter().any(|a| **a == *commit.author_name)) {
+                    return;
+                }
+
+                if !options.only_touching.is_empty()
+                    && !options
+                        .only_touching
+                        .iter()
+                        .any(|path| who::touches(&commit, path))
+                {
+                    return;
+                }
+
+                if let Some(pull_request) = pull_requests.remove(&commit.hash) {
+                    commit.message = pull_request.title.clone();
+                    commit.pull_request = Some(pull_request);
+                }
+
+                // Excluded bots still contribute their dependency bumps when
+                // those are rolled up into their own section.
+                if exclude_bots
+                    && config.bots.is_bot(&commit.author_name)
+                    && !(config.bots.rollup && commit.dependency_bump().is_some())
+                {
+                    return;
+                }
+
                 project.commits.push(commit);
+            },
+        );
+        if let Err(e) = log {
+            project_list.diagnostics.push(
+                DiagnosticKind::SkippedRepository,
+                name,
+                format!("left out, {}", e),
+            );
+            continue;
+        }
+
+        // Enrichment is best-effort: the changelog can still be built from
+        // commit prefixes alone.
+        if options.enrich_prs && options.offline {
+            if options.verbose {
+                eprintln!("{}: offline, not looking up pull requests", name);
+            }
+        } else if options.enrich_prs {
+            if let Err(e) = enrich::enrich(&mut project, &config.hosts(), &enrich_cache) {
+                project_list.diagnostics.push(
+                    DiagnosticKind::Enrichment,
+                    name,
+                    format!("failed to look up pull requests: {}", e),
+                );
             }
         }
 
+        if project.remote.is_empty() {
+            project_list.diagnostics.push(
+                DiagnosticKind::MissingRemote,
+                name,
+                "no remote, so its entries are not linked".to_string(),
+            );
+        }
+
         project_list.projects.push(project);
     }
 
+    mirrors::dedupe(&mut project_list);
+
+    // After deduplication, which would fold packages of one repository
+    // back together.
+    project_list.projects = project_list
+        .projects
+        .into_iter()
+        .flat_map(|project| match config.projects.get(&project.name) {
+            Some(metadata) if metadata.workspace => {
+                let packages = workspace::packages(&project.path);
+                workspace::split(project, &packages)
+            }
+            _ => vec![project],
+        })
+        .collect();
+
     project_list
 }
 
-fn generate_changelog(projects: &ProjectList) {
-    let mut changelog = String::new();
+/// `config` is `None` when rendering saved data, which does not read it.
+fn render_options(
+    options: &Options,
+    config: Option<&Config>,
+    projects: &ProjectList,
+) -> RenderOptions {
+    RenderOptions {
+        color: options.stdout && use_color(options),
+        theme: config
+            .map(|c| render::Theme::new(&c.theme).unwrap_or_else(|e| fail(exit::CONFIG, e)))
+            .unwrap_or_default(),
+        expand_squash: options.expand_squash,
+        labels: config.map(|c| c.labels.clone()).unwrap_or_default(),
+        hosts: config.map(Config::hosts).unwrap_or_default(),
+        linear: config.and_then(|c| c.linear.clone()),
+        toc: options.toc,
+        ticket_index: options.ticket_index,
+        group_by: options.group_by,
+        collapsible: options.collapsible,
+        front_matter: options.front_matter,
+        generated_at: generated_at(options, projects),
+        max_entry_length: options.max_entry_length,
+        max_per_section: options.max_per_section,
+        normalize: normalize(options, config),
+        release_marker: options.release_marker,
+        release_tags: config
+            .filter(|config| config.release.is_some())
+            .map(|config| release_tags(config, projects))
+            .unwrap_or_default(),
+        conventions: config
+            .map(|config| {
+                projects
+                    .projects
+                    .iter()
+                    .map(|project| {
+                        let convention = config.convention(&project.name).clone();
+                        (project.name.clone(), convention)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
 
-    changelog.push_str(&format!(
-        "# Changelog for {}\n\n",
-        Local::now().format("%Y-%m-%d")
-    ));
+fn release_tags(config: &Config, projects: &ProjectList) -> BTreeMap<String, String> {
+    projects
+        .projects
+        .iter()
+        .filter_map(|project| {
+            let previous = version::previous(project, &config.release(&project.name))?;
+            Some((project.name.clone(), previous.tag))
+        })
+        .collect()
+}
 
-    for project in &projects.projects {
-        changelog.push_str(&format!("## {}\n", project.name));
+/// The `[normalize]` rewrites, plus the lint's corrections with `--fix`.
+fn normalize(options: &Options, config: Option<&Config>) -> render::Normalize {
+    let normalize = config
+        .map(|c| render::Normalize::new(&c.normalize).unwrap_or_else(|e| fail(exit::CONFIG, e)))
+        .unwrap_or_default();
+
+    if options.fix {
+        let defaults = LintConfig::default();
+        normalize.with_fixes(config.and_then(|c| c.lint.as_ref()).unwrap_or(&defaults))
+    } else {
+        normalize
+    }
+}
+
+/// `SOURCE_DATE_EPOCH` when set, as for reproducible builds. Otherwise now,
+/// or with `--deterministic` the newest commit's date, so regenerating from
+/// the same history gives the same document.
+fn generated_at(options: &Options, projects: &ProjectList) -> DateTime<FixedOffset> {
+    if let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") {
+        return epoch
+            .trim()
+            .parse()
+            .ok()
+            .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+            .map(|date| date.fixed_offset())
+            .unwrap_or_else(|| fail(exit::USAGE, format!("Invalid SOURCE_DATE_EPOCH {}", epoch)));
+    }
+
+    if !options.deterministic {
+        return Local::now().fixed_offset();
+    }
+
+    projects
+        .projects
+        .iter()
+        .flat_map(|project| &project.commits)
+        .filter_map(|commit| DateTime::parse_from_rfc3339(&commit.date).ok())
+        .max()
+        .unwrap_or_default()
+}
+
+/// One rendering of the collected projects.
+struct Output {
+    format: Format,
+    /// Where to write it instead of `changelog.<ext>`; the directory for
+    /// mdbook and `--split-per-project`.
+    path: Option<PathBuf>,
+}
+
+/// Every `--format` given, or else the config's `[[outputs]]`, or else
+/// markdown alone.
+fn outputs(options: &Options, config: Option<&Config>) -> Vec<Output> {
+    if !options.formats.is_empty() {
+        return options
+            .formats
+            .iter()
+            .map(|format| Output {
+                format: *format,
+                path: None,
+            })
+            .collect();
+    }
+
+    let configured = config
+        .map(|config| config.outputs.as_slice())
+        .unwrap_or(&[]);
+    if configured.is_empty() {
+        return vec![Output {
+            format: Format::Markdown,
+            path: None,
+        }];
+    }
+
+    configured
+        .iter()
+        .map(|output| Output {
+            format: Format::parse(&output.format).unwrap_or_else(|| {
+                fail(
+                    exit::CONFIG,
+                    format!("Unknown format {} in [[outputs]]", output.format),
+                )
+            }),
+            path: output.path.clone(),
+        })
+        .collect()
+}
 
-        let (project_features, project_bug_fixes) = separate_features_and_bug_fixes(&project);
+fn generate_changelog(
+    projects: &ProjectList,
+    options: &Options,
+    output: &Output,
+    render_options: &RenderOptions,
+) -> Vec<PathBuf> {
+    let format = output.format;
+    let extension = format.extension();
 
-        if !project_bug_fixes.is_empty() {
-            changelog.push_str("### :bug: Bugfixes\n");
-            changelog.push_str(&project_bug_fixes);
+    if format == Format::MdBook {
+        let directory = output_directory(output);
+
+        return render::mdbook(render_options, projects)
+            .into_iter()
+            .map(|(file_name, contents)| {
+                let file_name = directory.join(file_name);
+                fs::write(&file_name, contents)
+                    .unwrap_or_else(|_| panic!("Failed to write {}", file_name.display()));
+                file_name
+            })
+            .collect();
+    }
+
+    if options.split_per_project {
+        let directory = output_directory(output);
+        let mut written = vec![];
+
+        for project in &projects.projects {
+            let changelog = render::project_document(format, render_options, project);
+
+            let file_name = directory.join(format!(
+                "{}.{}",
+                project_file_name(&project.name),
+                extension
+            ));
+            fs::write(&file_name, changelog)
+                .unwrap_or_else(|_| panic!("Failed to write {}", file_name.display()));
+            written.push(file_name);
         }
 
-        if !project_features.is_empty() {
-            changelog.push_str("### :rocket: Features\n");
-            changelog.push_str(&project_features);
+        return written;
+    }
+
+    let changelog = render::document(format, render_options, projects);
+
+    // Workflow commands only work on stdout; the readable changelog goes to
+    // the job summary instead.
+    if format == Format::Gha {
+        print!("{}", changelog);
+
+        if let Ok(summary) = std::env::var("GITHUB_STEP_SUMMARY") {
+            let markdown = render::document(Format::Markdown, render_options, projects);
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&summary)
+                .and_then(|mut file| file.write_all(markdown.as_bytes()))
+                .unwrap_or_else(|_| panic!("Failed to write {}", summary));
         }
 
-        changelog.push('\n');
+        return vec![];
     }
 
-    fs::write("changelog.md", changelog).expect("Failed to write changelog.md");
+    if options.stdout {
+        print!("{}", changelog);
+        return vec![];
+    }
+
+    let file_name = match &output.path {
+        Some(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .unwrap_or_else(|_| panic!("Failed to create {}", parent.display()));
+            }
+            path.clone()
+        }
+        None => PathBuf::from(format!("changelog.{}", extension)),
+    };
+    fs::write(&file_name, changelog)
+        .unwrap_or_else(|_| panic!("Failed to write {}", file_name.display()));
+    vec![file_name]
 }
 
-fn separate_features_and_bug_fixes(project: &Project) -> (String, String) {
-    let mut project_features = String::new();
-    let mut project_bug_fixes = String::new();
+/// The file a project's changelog went to: the committed CHANGELOG with
+/// `--commit-back`, or else the first output written to disk.
+fn notes_path(options: &Options, outputs: &[Output], project: &Project) -> Option<PathBuf> {
+    if options.commit_back {
+        let file_name = format!("CHANGELOG.{}", options.format.extension());
+        return Some(project.path.join(file_name));
+    }
 
-    for commit in &project.commits {
-        let message_parts: Vec<&str> = commit.message.split(": ").collect();
-        if message_parts.len() == 2 {
-            let message: String = commit.message.split(": ").nth(1).unwrap().to_string();
-            let commit_link = format!("{}/commits/{}", project.remote, commit.hash);
-            if commit.message.starts_with("feat:") {
-                project_features.push_str(&format!(
-                    " - {} [#{}]({})\n",
-                    message,
-                    &commit.hash[0..8],
-                    commit_link
-                ));
-            } else if commit.message.starts_with("fix:") {
-                project_bug_fixes.push_str(&format!(
-                    " - {} [#{}]({})\n",
-                    message,
-                    &commit.hash[0..8],
-                    commit_link
-                ));
+    let output = outputs.first().filter(|_| !options.stdout)?;
+    let directory = || output.path.clone().unwrap_or(PathBuf::from("changelog"));
+    Some(match output.format {
+        Format::Gha => return None,
+        Format::MdBook => directory(),
+        format if options.split_per_project => directory().join(format!(
+            "{}.{}",
+            project_file_name(&project.name),
+            format.extension()
+        )),
+        format => output
+            .path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("changelog.{}", format.extension()))),
+    })
+}
+
+/// The directory mdbook and `--split-per-project` write to, created.
+fn output_directory(output: &Output) -> &Path {
+    let directory = output.path.as_deref().unwrap_or(Path::new("changelog"));
+    fs::create_dir_all(directory)
+        .unwrap_or_else(|_| panic!("Failed to create {} directory", directory.display()));
+    directory
+}
+
+fn commit_back(projects: &ProjectList, options: &Options, render_options: &RenderOptions) {
+    let file_name = format!("CHANGELOG.{}", options.format.extension());
+
+    for project in &projects.projects {
+        if render::sections(project, render_options).is_empty() {
+            continue;
+        }
+
+        let changelog_path = Path::new(&project.path).join(&file_name);
+        let existing = if options.format.is_standalone() {
+            String::new()
+        } else {
+            fs::read_to_string(&changelog_path).unwrap_or_default()
+        };
+
+        // A keepachangelog.com file gets a release in its own style, below
+        // its title, named after --tag or else the date.
+        let keep_a_changelog =
+            options.format == Format::Markdown && render::is_keep_a_changelog(&existing);
+        let (notes, changelog) = if keep_a_changelog {
+            let version = options
+                .tag
+                .clone()
+                .unwrap_or_else(|| render_options.generated_at.format("%Y-%m-%d").to_string());
+            let release = render::keep_a_changelog_release(render_options, project, &version);
+            let changelog = render::insert_release(&existing, &release);
+            (release, changelog)
+        } else {
+            let notes = render::project_document(options.format, render_options, project);
+            let changelog = format!("{}{}", notes, existing);
+            (notes, changelog)
+        };
+
+        fs::write(&changelog_path, changelog)
+            .unwrap_or_else(|_| panic!("Failed to write {}", changelog_path.display()));
+
+        // The pull request describes the notes as edited, which is whatever
+        // now comes before the old changelog. A keepachangelog.com release
+        // sits inside it, so keeps its generated text.
+        let notes = if options.edit {
+            edit(&changelog_path);
+            let edited = fs::read_to_string(&changelog_path)
+                .unwrap_or_else(|_| panic!("Failed to read {}", changelog_path.display()));
+            match edited.strip_suffix(existing.as_str()) {
+                Some(edited) if !keep_a_changelog => edited.to_string(),
+                _ => notes,
+            }
+        } else {
+            notes
+        };
+
+        let base = git_output(&project.path, &["rev-parse", "--abbrev-ref", "HEAD"]);
+
+        if let Some(branch) = &options.commit_branch {
+            run_git(&project.path, &["checkout", "-b", branch]);
+        }
+
+        run_git(&project.path, &["add", &file_name]);
+        run_git(
+            &project.path,
+            &["commit", "-m", "chore(release): update changelog"],
+        );
+
+        if let (true, Some(branch)) = (options.open_pr, &options.commit_branch) {
+            approve(
+                options,
+                &notes,
+                &format!(
+                    "Push {} and open a pull request for {}?",
+                    branch, project.name
+                ),
+            );
+            run_git(&project.path, &["push", "-u", "origin", branch]);
+
+            match pull_request::open_pull_request(
+                &render_options.hosts,
+                &project.remote,
+                branch,
+                &base,
+                &notes,
+            ) {
+                Ok(()) => println!("Opened pull request for {}", project.name),
+                Err(e) => eprintln!("Failed to open pull request for {}: {}", project.name, e),
             }
         }
     }
+}
+
+/// A token typed at the terminal without echoing it, or piped in.
+fn read_token(host: &str) -> String {
+    let terminal = std::io::stdin().is_terminal();
+    if terminal {
+        eprint!("Token for {}: ", host);
+        let _ = Command::new("stty").arg("-echo").status();
+    }
+
+    let mut token = String::new();
+    let read = std::io::stdin().read_line(&mut token);
+
+    if terminal {
+        let _ = Command::new("stty").arg("echo").status();
+        eprintln!();
+    }
+    read.unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to read the token: {}", e)));
+
+    let token = token.trim();
+    if token.is_empty() {
+        fail(exit::USAGE, "No token given");
+    }
+    token.to_string()
+}
+
+/// Shows what is about to go out and asks to go ahead, unless `--yes`
+/// said so already. Anything but yes stops the run.
+fn approve(options: &Options, preview: &str, question: &str) {
+    if options.yes {
+        return;
+    }
+    if !std::io::stdin().is_terminal() {
+        fail(
+            exit::USAGE,
+            "Publishing needs confirmation on a terminal; pass --yes to skip it",
+        );
+    }
+
+    eprintln!("{}", preview.trim_end());
+    eprint!("\n{} [y/N] ", question);
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to read the answer: {}", e)));
+    if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        fail(exit::FAILURE, "Not publishing");
+    }
+}
+
+/// Opens a file in `$EDITOR`, or `vi` without one, and waits for it. An
+/// editor exiting non-zero stops the run before anything is published.
+fn edit(path: &Path) {
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // The editor may carry arguments, e.g. `code --wait`.
+    let file = if cfg!(windows) {
+        format!("\"{}\"", path.display())
+    } else {
+        shell::quote(&path.to_string_lossy())
+    };
+
+    let status = shell::command(&format!("{} {}", editor, file))
+        .status()
+        .unwrap_or_else(|e| fail(exit::FAILURE, format!("Failed to run {}: {}", editor, e)));
+    if !status.success() {
+        fail(
+            exit::FAILURE,
+            format!("{} exited with {}; not publishing", editor, status),
+        );
+    }
+}
+
+/// `edit` for text that is published rather than written, through a
+/// temporary file with `extension` so the editor highlights it.
+fn edit_text(text: &str, extension: &str) -> String {
+    let path =
+        std::env::temp_dir().join(format!("chenjirogu-{}.{}", std::process::id(), extension));
+    fs::write(&path, text).unwrap_or_else(|e| {
+        fail(
+            exit::FAILURE,
+            format!("Failed to write {}: {}", path.display(), e),
+        )
+    });
+    edit(&path);
+    let edited = fs::read_to_string(&path).unwrap_or_else(|e| {
+        fail(
+            exit::FAILURE,
+            format!("Failed to read {}: {}", path.display(), e),
+        )
+    });
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+fn run_git(path: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .status()
+        .unwrap_or_else(|e| fail(exit::GIT, format!("failed to execute git: {}", e)));
+
+    if !status.success() {
+        fail(
+            exit::GIT,
+            format!("git {} failed in {}", args.join(" "), path.display()),
+        );
+    }
+}
+
+fn git_output(path: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .unwrap_or_else(|e| fail(exit::GIT, format!("failed to execute git: {}", e)));
+
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
 
-    (project_features, project_bug_fixes)
+// Project names come from config keys, so keep them from escaping the
+// changelog directory when used as file names, and clear of the characters
+// Windows does not allow in them.
+fn project_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '<' | '>' | '"' | '|' | '?' | '*' => '-',
+            _ => c,
+        })
+        .collect()
 }
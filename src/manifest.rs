@@ -0,0 +1,85 @@
+//! A summary of what each project would release, for deployment pipelines
+//! deciding which services to ship.
+
+use crate::config::Config;
+use crate::json;
+use crate::model::{Project, ProjectList};
+use crate::render::RenderOptions;
+use crate::version::{self, Scheme};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    projects: Vec<Release<'a>>,
+}
+
+#[derive(Serialize)]
+struct Release<'a> {
+    name: &'a str,
+    /// The version this release follows: the latest tagged one, or a
+    /// workspace package's declared version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_tag: Option<String>,
+    /// `None` when there is nothing to release, or the previous version
+    /// does not follow the scheme.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    /// What `[release] tag_pattern` names the new version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bump: Option<&'static str>,
+    breaking: bool,
+    commits: usize,
+    /// Where the project's release notes were written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+}
+
+/// The manifest as JSON. `notes` gives the file each project's changelog
+/// went to, if any.
+pub fn manifest(
+    projects: &ProjectList,
+    options: &RenderOptions,
+    config: &Config,
+    notes: impl Fn(&Project) -> Option<String>,
+) -> Result<String, String> {
+    let today = options.generated_at.date_naive();
+    let mut releases = vec![];
+
+    for project in &projects.projects {
+        let release = config.release(&project.name);
+        let scheme = Scheme::parse(&release.scheme)?;
+
+        let previous_release = version::previous(project, &release);
+        let previous_tag = previous_release
+            .as_ref()
+            .map(|previous| previous.tag.clone());
+        let previous = project
+            .version
+            .clone()
+            .or(previous_release.map(|previous| previous.version));
+
+        let bump = version::recommend(project, options);
+        let version =
+            bump.and_then(|bump| version::next(&scheme, previous.as_deref(), bump, today));
+
+        releases.push(Release {
+            name: &project.name,
+            previous,
+            previous_tag,
+            tag: version
+                .as_deref()
+                .map(|version| version::tag(&release, project, version)),
+            version,
+            bump: bump.map(|bump| bump.name()),
+            breaking: project.commits.iter().any(|commit| commit.is_breaking()),
+            commits: project.commits.len(),
+            notes: notes(project),
+        });
+    }
+
+    json::to_string_pretty(&Manifest { projects: releases })
+}
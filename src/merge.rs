@@ -0,0 +1,365 @@
+//! Merges markdown changelogs written by separate runs, e.g. by two
+//! teammates covering different projects or overlapping windows. The
+//! documents are read back by their headings: `##` projects, optionally
+//! under `#` team groups, with `###` sections of entries.
+
+/// The changelog of `a` with what only `b` has added: its groups, projects
+/// and sections, and entries whose commit `a` does not list. `a`'s title
+/// and front matter are kept; the table of contents is left out since it
+/// would no longer cover every project.
+pub fn merge(a: &str, b: &str) -> Result<String, String> {
+    let mut merged = parse(a).map_err(|e| format!("First changelog: {}", e))?;
+    let other = parse(b).map_err(|e| format!("Second changelog: {}", e))?;
+
+    for group in other.groups {
+        match merged
+            .groups
+            .iter_mut()
+            .find(|g| g.heading == group.heading)
+        {
+            Some(existing) => existing.merge(group),
+            None => merged.groups.push(group),
+        }
+    }
+
+    Ok(merged.render())
+}
+
+struct Document<'a> {
+    preamble: Vec<&'a str>,
+    groups: Vec<Group<'a>>,
+}
+
+struct Group<'a> {
+    /// `None` for projects listed without teams.
+    heading: Option<&'a str>,
+    projects: Vec<Project<'a>>,
+}
+
+struct Project<'a> {
+    heading: &'a str,
+    about: Vec<&'a str>,
+    sections: Vec<Section<'a>>,
+}
+
+struct Section<'a> {
+    /// `None` for entries straight under the heading, as in the security
+    /// section at the top.
+    heading: Option<&'a str>,
+    /// A table's column names and rule.
+    header: Vec<&'a str>,
+    /// Each entry's lines, the first being the list item or table row.
+    entries: Vec<Vec<&'a str>>,
+    /// The `…and N more` line of a shortened section.
+    more: Option<&'a str>,
+}
+
+fn parse(text: &str) -> Result<Document<'_>, String> {
+    if text.contains("<details>") {
+        return Err("projects folded with --collapsible cannot be merged".to_string());
+    }
+
+    let mut document = Document {
+        preamble: vec![],
+        groups: vec![],
+    };
+    let mut seen_title = false;
+    let mut in_contents = false;
+
+    for line in text.lines() {
+        if line == "**Contents**" {
+            in_contents = true;
+            continue;
+        }
+        if in_contents {
+            if line.is_empty() || line.trim_start().starts_with("- ") {
+                continue;
+            }
+            in_contents = false;
+        }
+
+        if line.starts_with("# ") && !seen_title {
+            seen_title = true;
+            document.preamble.push(line);
+        } else if line.starts_with("# ") {
+            document.groups.push(Group {
+                heading: Some(line),
+                projects: vec![],
+            });
+        } else if line.starts_with("## ") {
+            if document.groups.is_empty() {
+                document.groups.push(Group {
+                    heading: None,
+                    projects: vec![],
+                });
+            }
+            let group = document.groups.last_mut().expect("pushed above");
+            group.projects.push(Project {
+                heading: line,
+                about: vec![],
+                sections: vec![],
+            });
+        } else if let Some(project) = document
+            .groups
+            .last_mut()
+            .and_then(|group| group.projects.last_mut())
+        {
+            project.push_line(line);
+        } else if document.groups.is_empty() {
+            document.preamble.push(line);
+        }
+    }
+
+    if !seen_title {
+        return Err("not a markdown changelog, it has no # title".to_string());
+    }
+    Ok(document)
+}
+
+impl<'a> Document<'a> {
+    fn render(&self) -> String {
+        let mut changelog = String::new();
+        for line in &self.preamble {
+            changelog.push_str(line);
+            changelog.push('\n');
+        }
+        let mut changelog = format!("{}\n\n", changelog.trim_end());
+
+        for group in &self.groups {
+            if let Some(heading) = group.heading {
+                changelog.push_str(&format!("{}\n\n", heading));
+            }
+            for project in &group.projects {
+                project.render(&mut changelog);
+            }
+        }
+
+        changelog
+    }
+}
+
+impl<'a> Group<'a> {
+    fn merge(&mut self, other: Group<'a>) {
+        for project in other.projects {
+            match self
+                .projects
+                .iter_mut()
+                .find(|p| p.heading == project.heading)
+            {
+                Some(existing) => existing.merge(project),
+                None => self.projects.push(project),
+            }
+        }
+    }
+}
+
+impl<'a> Project<'a> {
+    fn push_line(&mut self, line: &'a str) {
+        if line.starts_with("### ") {
+            self.sections.push(Section::new(Some(line)));
+            return;
+        }
+        if line.is_empty() {
+            return;
+        }
+
+        let starts_entry = line.starts_with(" - ") || line.starts_with('|');
+        if self.sections.is_empty() {
+            if !starts_entry {
+                self.about.push(line);
+                return;
+            }
+            self.sections.push(Section::new(None));
+        }
+
+        self.sections
+            .last_mut()
+            .expect("pushed above")
+            .push_line(line);
+    }
+
+    fn merge(&mut self, other: Project<'a>) {
+        if self.about.is_empty() {
+            self.about = other.about;
+        }
+
+        for section in other.sections {
+            match self
+                .sections
+                .iter_mut()
+                .find(|s| s.heading == section.heading)
+            {
+                Some(existing) => existing.merge(section),
+                None => self.sections.push(section),
+            }
+        }
+        self.sections.sort_by_key(|section| section.rank());
+    }
+
+    fn render(&self, changelog: &mut String) {
+        changelog.push_str(&format!("{}\n", self.heading));
+        if !self.about.is_empty() {
+            changelog.push_str(&format!("{}\n\n", self.about.join("\n")));
+        }
+
+        for section in &self.sections {
+            if let Some(heading) = section.heading {
+                changelog.push_str(&format!("{}\n", heading));
+            }
+            let lines = section
+                .header
+                .iter()
+                .chain(section.entries.iter().flatten());
+            for line in lines.chain(&section.more) {
+                changelog.push_str(&format!("{}\n", line));
+            }
+        }
+
+        changelog.push('\n');
+    }
+}
+
+impl<'a> Section<'a> {
+    fn new(heading: Option<&'a str>) -> Section<'a> {
+        Section {
+            heading,
+            header: vec![],
+            entries: vec![],
+            more: None,
+        }
+    }
+
+    fn push_line(&mut self, line: &'a str) {
+        if line.contains("[…and ") {
+            self.more = Some(line);
+        } else if line.starts_with("| Package ") || line.starts_with("| --- ") {
+            self.header.push(line);
+        } else if line.starts_with(" - ") || line.starts_with('|') {
+            self.entries.push(vec![line]);
+        } else if let Some(entry) = self.entries.last_mut() {
+            entry.push(line);
+        }
+    }
+
+    fn merge(&mut self, other: Section<'a>) {
+        for entry in other.entries {
+            let key = key(entry[0]);
+            if !self.entries.iter().any(|e| self::key(e[0]) == key) {
+                self.entries.push(entry);
+            }
+        }
+        if self.header.is_empty() {
+            self.header = other.header;
+        }
+        self.more = self.more.or(other.more);
+    }
+
+    /// Sections in the order they are rendered.
+    fn rank(&self) -> usize {
+        let titles = ["Security", "Bugfixes", "Features", "Dependencies"];
+        self.heading
+            .and_then(|heading| titles.iter().position(|title| heading.ends_with(title)))
+            .unwrap_or(titles.len())
+    }
+}
+
+/// What identifies an entry's commit: the `[#hash]` of a list item, the
+/// bare `#hash` of one from a project without a remote, or the commit link
+/// of a table row.
+fn key(line: &str) -> &str {
+    if let Some((_, rest)) = line.rsplit_once("[#") {
+        if let Some((hash, _)) = rest.split_once(']') {
+            return hash;
+        }
+    }
+    if let Some((_, hash)) = line.rsplit_once(" #") {
+        if hash.len() >= 7 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return hash;
+        }
+    }
+    line.split_once("](")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map_or(line, |(link, _)| link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "# Changelog for 2026-10-15
+
+## <a id=\"alpha\"></a>alpha
+### <a id=\"alpha-bugfixes\"></a>:bug: Bugfixes
+ - crash on start: \"quoted\" path [#4b586d80](https://example.com/c/4b586d80)
+ - handle ü and # in names #99a33ed8
+";
+
+    const B: &str = "# Changelog for 2026-10-16
+
+## <a id=\"alpha\"></a>alpha
+### <a id=\"alpha-features\"></a>:rocket: Features
+ - - leading dash 🚀 [#75dd6b5c](https://example.com/c/75dd6b5c)
+### <a id=\"alpha-bugfixes\"></a>:bug: Bugfixes
+ - crash on start: \"quoted\" path [#4b586d80](https://example.com/c/4b586d80)
+ - handle ü and # in names #99a33ed8
+ - timeout: 5 -> 10 [#d88ee2d6](https://example.com/c/d88ee2d6)
+
+## <a id=\"beta\"></a>beta
+ - beta thing #5812933f
+";
+
+    #[test]
+    fn entries_are_merged_once_by_commit() {
+        assert_eq!(
+            merge(A, B).expect("both are changelogs"),
+            "# Changelog for 2026-10-15
+
+## <a id=\"alpha\"></a>alpha
+### <a id=\"alpha-bugfixes\"></a>:bug: Bugfixes
+ - crash on start: \"quoted\" path [#4b586d80](https://example.com/c/4b586d80)
+ - handle ü and # in names #99a33ed8
+ - timeout: 5 -> 10 [#d88ee2d6](https://example.com/c/d88ee2d6)
+### <a id=\"alpha-features\"></a>:rocket: Features
+ - - leading dash 🚀 [#75dd6b5c](https://example.com/c/75dd6b5c)
+
+## <a id=\"beta\"></a>beta
+ - beta thing #5812933f
+
+"
+        );
+    }
+
+    #[test]
+    fn merging_a_changelog_with_itself_changes_nothing() {
+        let merged = merge(B, B).expect("a changelog");
+        assert_eq!(merge(&merged, B).expect("a changelog"), merged);
+    }
+
+    #[test]
+    fn keys_identify_commits() {
+        assert_eq!(key(" - a [#4b586d80](https://x/4b586d80)"), "4b586d80");
+        assert_eq!(key(" - a: b #99a33ed8"), "99a33ed8");
+        assert_eq!(key(" - issue #12"), " - issue #12");
+        assert_eq!(
+            key("| serde | 1 | 2 | [abc](https://x/abc) |"),
+            "https://x/abc"
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        assert!(merge("", A).is_err());
+        assert!(merge(A, "## alpha\n - no title\n").is_err());
+        assert!(merge(A, "# T\n<details>\n").is_err());
+    }
+
+    #[test]
+    fn stray_lines_are_kept_in_place() {
+        let merged =
+            merge("# T\nsome intro\n", "# U\n## p\nabout p\n - x\n  more\n").expect("changelogs");
+        assert_eq!(
+            merged,
+            "# T\nsome intro\n\n## p\nabout p\n\n - x\n  more\n\n"
+        );
+    }
+}
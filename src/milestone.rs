@@ -0,0 +1,188 @@
+use crate::config::HttpConfig;
+use crate::http::{self, url_encode};
+use crate::json;
+use crate::model::PullRequest;
+use crate::remote::{parse_remote, Hosts};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+const PER_PAGE: usize = 100;
+
+#[derive(Deserialize)]
+struct GithubMilestone {
+    number: u64,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct GithubIssue {
+    number: u64,
+    title: String,
+    html_url: String,
+    #[serde(default)]
+    labels: Vec<GithubLabel>,
+    /// Only present on issues that are pull requests.
+    pull_request: Option<GithubIssuePull>,
+}
+
+#[derive(Deserialize)]
+struct GithubLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GithubIssuePull {
+    merged_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubPull {
+    merge_commit_sha: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitlabMergeRequest {
+    iid: u64,
+    title: String,
+    web_url: String,
+    #[serde(default)]
+    labels: Vec<String>,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+}
+
+/// The merged pull requests (GitHub) or merge requests (GitLab) of a
+/// milestone, with the commit each one landed as. Issues in the milestone
+/// have no commit to link to and are left out.
+pub fn pull_requests(
+    hosts: &Hosts,
+    remote: &str,
+    milestone: &str,
+) -> Result<Vec<(String, PullRequest)>, String> {
+    let remote = parse_remote(remote).ok_or_else(|| format!("unrecognized remote {}", remote))?;
+
+    if remote.is_github(hosts) {
+        let headers = [
+            format!(
+                "Authorization: Bearer {}",
+                http::github_token(&remote.host)?
+            ),
+            "Accept: application/vnd.github+json".to_string(),
+        ];
+        let repo = format!("{}/repos/{}", remote.github_api(hosts), remote.path);
+
+        let milestones: Vec<GithubMilestone> = paginate(
+            &hosts.http,
+            &format!("{}/milestones?state=all", repo),
+            &headers,
+        )?;
+        let number = milestones
+            .iter()
+            .find(|m| m.title == milestone)
+            .map(|m| m.number)
+            .ok_or_else(|| format!("no milestone named {} in {}", milestone, remote.path))?;
+
+        let issues: Vec<GithubIssue> = paginate(
+            &hosts.http,
+            &format!("{}/issues?milestone={}&state=closed", repo, number),
+            &headers,
+        )?;
+
+        let mut pull_requests = vec![];
+        for issue in issues {
+            // Closed but unmerged pull requests and plain issues never landed.
+            let merged = issue
+                .pull_request
+                .is_some_and(|pull| pull.merged_at.is_some());
+            if !merged {
+                continue;
+            }
+
+            let pull = http::request(
+                &hosts.http,
+                "GET",
+                &format!("{}/pulls/{}", repo, issue.number),
+                &headers,
+                None,
+            )?;
+            let pull: GithubPull = json::from_str(&pull)?;
+
+            if let Some(sha) = pull.merge_commit_sha {
+                pull_requests.push((
+                    sha,
+                    PullRequest {
+                        number: issue.number,
+                        title: issue.title,
+                        url: issue.html_url,
+                        labels: issue.labels.into_iter().map(|label| label.name).collect(),
+                    },
+                ));
+            }
+        }
+
+        Ok(pull_requests)
+    } else if remote.is_gitlab(hosts) {
+        let headers = [format!(
+            "PRIVATE-TOKEN: {}",
+            http::gitlab_token(&remote.host)?
+        )];
+
+        let merge_requests: Vec<GitlabMergeRequest> = paginate(
+            &hosts.http,
+            &format!(
+                "{}/projects/{}/merge_requests?state=merged&milestone={}",
+                remote.gitlab_api(hosts),
+                url_encode(&remote.path),
+                url_encode(milestone)
+            ),
+            &headers,
+        )?;
+
+        Ok(merge_requests
+            .into_iter()
+            .filter_map(|mr| {
+                // Squashed merge requests land as the squash commit.
+                let sha = mr.squash_commit_sha.or(mr.merge_commit_sha)?;
+                Some((
+                    sha,
+                    PullRequest {
+                        number: mr.iid,
+                        title: mr.title,
+                        url: mr.web_url,
+                        labels: mr.labels,
+                    },
+                ))
+            })
+            .collect())
+    } else {
+        Err(format!("milestones on {} are not supported", remote.host))
+    }
+}
+
+/// Fetches every page of a list endpoint; `url` must already have a query.
+fn paginate<T: DeserializeOwned>(
+    http_config: &HttpConfig,
+    url: &str,
+    headers: &[String],
+) -> Result<Vec<T>, String> {
+    let mut items = vec![];
+
+    for page in 1.. {
+        let response = http::request(
+            http_config,
+            "GET",
+            &format!("{}&per_page={}&page={}", url, PER_PAGE, page),
+            headers,
+            None,
+        )?;
+        let batch: Vec<T> = json::from_str(&response)?;
+        let done = batch.len() < PER_PAGE;
+        items.extend(batch);
+
+        if done {
+            break;
+        }
+    }
+
+    Ok(items)
+}
@@ -0,0 +1,113 @@
+use crate::diagnostics::DiagnosticKind;
+use crate::model::{Commit, Project, ProjectList};
+use crate::remote::parse_remote;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Folds projects that are clones of the same repository, e.g. a laptop
+/// checkout and a NAS mirror, into the first of them, and drops commits
+/// already listed under an earlier project, noting each in the list's
+/// diagnostics. A project left without commits that way is dropped, so it
+/// does not show up as an empty heading.
+pub fn dedupe(project_list: &mut ProjectList) {
+    let mut warnings = vec![];
+    let mut kept: Vec<Project> = vec![];
+    // Which kept project each commit was first seen in.
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for mut project in project_list.projects.drain(..) {
+        let mirror_of = repository(&project).and_then(|own| {
+            kept.iter()
+                .position(|other| repository(other).as_ref() == Some(&own))
+        });
+
+        if let Some(index) = mirror_of {
+            let original = &mut kept[index];
+            let before = original.commits.len();
+            for commit in project.commits {
+                if let Entry::Vacant(entry) = seen.entry(commit.hash.clone()) {
+                    entry.insert(index);
+                    original.commits.push(commit);
+                }
+            }
+            original.commits.sort_by(Commit::newest_first);
+
+            warnings.push((
+                DiagnosticKind::SkippedProject,
+                project.name,
+                format!(
+                    "a clone of {}; merged {} missing from it",
+                    original.name,
+                    commits(original.commits.len() - before)
+                ),
+            ));
+            continue;
+        }
+
+        let mut duplicates: HashMap<usize, usize> = HashMap::new();
+        project
+            .commits
+            .retain(|commit| match seen.get(&commit.hash) {
+                Some(index) => {
+                    *duplicates.entry(*index).or_default() += 1;
+                    false
+                }
+                None => true,
+            });
+
+        let mut duplicates: Vec<_> = duplicates.into_iter().collect();
+        duplicates.sort();
+        if project.commits.is_empty() && !duplicates.is_empty() {
+            let names: Vec<&str> = duplicates
+                .iter()
+                .map(|(index, _)| kept[*index].name.as_str())
+                .collect();
+            warnings.push((
+                DiagnosticKind::SkippedProject,
+                project.name,
+                format!(
+                    "left out, its {} already listed under {}",
+                    commits(duplicates.iter().map(|(_, count)| count).sum()),
+                    names.join(", ")
+                ),
+            ));
+            continue;
+        }
+        for (index, count) in duplicates {
+            warnings.push((
+                DiagnosticKind::DuplicateCommits,
+                project.name.clone(),
+                format!(
+                    "left out {} already listed under {}",
+                    commits(count),
+                    kept[index].name
+                ),
+            ));
+        }
+
+        for commit in &project.commits {
+            seen.insert(commit.hash.clone(), kept.len());
+        }
+        kept.push(project);
+    }
+
+    project_list.projects = kept;
+    for (kind, project, message) in warnings {
+        project_list.diagnostics.push(kind, &project, message);
+    }
+}
+
+/// Host and path of the project's remote, in lowercase since hosts treat
+/// them case-insensitively.
+fn repository(project: &Project) -> Option<(String, String)> {
+    parse_remote(&project.remote)
+        .map(|remote| (remote.host.to_lowercase(), remote.path.to_lowercase()))
+}
+
+fn commits(count: usize) -> String {
+    format!(
+        "{} {}",
+        count,
+        if count == 1 { "commit" } else { "commits" }
+    )
+}
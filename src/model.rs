@@ -0,0 +1,367 @@
+use crate::convention::Subject;
+use crate::diagnostics::Diagnostics;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Commit {
+    pub hash: String,
+    pub message: String,
+    /// Shared between an author's commits through `Interner`.
+    pub author_name: Arc<str>,
+    pub author_email: Arc<str>,
+    pub date: String,
+    /// When the commit was last rewritten, e.g. by a rebase; RFC 3339 like
+    /// `date`. Older exports do not have it.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub committer_date: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<PullRequest>,
+    /// Paths the commit changed, relative to the repository root.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+/// Keeps one copy of each author name and email for every commit that
+/// repeats it, which adds up over months of history across many projects.
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = Arc::from(string);
+        self.strings.insert(interned.clone());
+        interned
+    }
+}
+
+/// The pull request a commit was merged through, filled in by
+/// `--enrich-prs`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+/// A dependency update parsed from a bot-style commit subject.
+pub struct Bump<'a> {
+    pub package: &'a str,
+    /// Empty when the subject only names the new version.
+    pub from: &'a str,
+    pub to: &'a str,
+}
+
+impl Commit {
+    /// The committer date, or the author date for data without one.
+    pub fn committed_at(&self) -> Option<DateTime<FixedOffset>> {
+        let date = if self.committer_date.is_empty() {
+            &self.date
+        } else {
+            &self.committer_date
+        };
+        DateTime::parse_from_rfc3339(date).ok()
+    }
+
+    /// Newest commit first, by the instant it was committed whatever the
+    /// time zone, and by hash when that is shared. Unlike the order of
+    /// `git log`, this does not depend on how the history was traversed.
+    pub fn newest_first(a: &Commit, b: &Commit) -> Ordering {
+        b.committed_at()
+            .cmp(&a.committed_at())
+            .then_with(|| a.hash.cmp(&b.hash))
+    }
+
+    /// Recognizes Dependabot's `bump X from A to B` and Renovate's
+    /// `update ... X to B` subjects, with or without a `chore(deps):` style
+    /// prefix. Prefixed subjects must look like dependency work so that,
+    /// say, `fix: bump timeout from 5 to 10` stays a fix.
+    pub fn dependency_bump(&self) -> Option<Bump<'_>> {
+        let (prefix, description) = match self.message.split_once(": ") {
+            Some((prefix, description)) if !prefix.contains(' ') => (prefix, description),
+            _ => ("", self.message.as_str()),
+        };
+
+        let dependency_prefix = prefix.is_empty()
+            || prefix.contains("deps")
+            || prefix.starts_with("chore")
+            || prefix.starts_with("build");
+        if !dependency_prefix {
+            return None;
+        }
+
+        if description
+            .get(..5)
+            .is_some_and(|word| word.eq_ignore_ascii_case("bump "))
+        {
+            let (package, versions) = description[5..].split_once(" from ")?;
+            let (from, to) = versions.split_once(" to ")?;
+            return Some(Bump {
+                package,
+                from,
+                to: to.split_whitespace().next()?,
+            });
+        }
+
+        if prefix.contains("deps") && description.starts_with("update ") {
+            let (target, to) = description.rsplit_once(" to ")?;
+            return Some(Bump {
+                package: target.rsplit(' ').next()?,
+                from: "",
+                to: to.split_whitespace().next()?,
+            });
+        }
+
+        None
+    }
+
+    /// The scope of a conventional commit subject, like `api` in
+    /// `feat(api)!: ...`.
+    pub fn scope(&self) -> Option<&str> {
+        Subject::parse(&self.message)?.scope
+    }
+
+    /// Whether the author opted this commit out of the changelog with
+    /// `[skip changelog]` in the subject or a `Changelog: skip` trailer.
+    pub fn is_skipped(&self) -> bool {
+        self.message.to_lowercase().contains("[skip changelog]")
+            || self
+                .trailer("Changelog")
+                .is_some_and(|value| value.eq_ignore_ascii_case("skip"))
+    }
+
+    /// The text of a `BREAKING CHANGE:` (or `BREAKING-CHANGE:`) footer with
+    /// the lines continuing it, up to the next trailer, since migration
+    /// steps tend to run over several lines.
+    pub fn breaking_change(&self) -> Option<String> {
+        let mut lines = self.body.lines().skip_while(|line| {
+            !(line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"))
+        });
+        let (_, first) = lines.next()?.split_once(':')?;
+
+        let mut note = vec![first.trim()];
+        note.extend(
+            lines
+                .take_while(|line| !is_trailer(line))
+                .map(str::trim_end),
+        );
+        let note = note.join("\n").trim().to_string();
+        (!note.is_empty()).then_some(note)
+    }
+
+    /// Whether the commit breaks compatibility, by a `!` after its type
+    /// or a `BREAKING CHANGE:` footer.
+    pub fn is_breaking(&self) -> bool {
+        let bang = Subject::parse(&self.message).is_some()
+            && self
+                .message
+                .split_once(": ")
+                .is_some_and(|(prefix, _)| prefix.ends_with('!'));
+        bang || self.breaking_change().is_some()
+    }
+
+    /// Whether the commit fixes a vulnerability: a `sec:` or `security:`
+    /// type, a `Security:` trailer, or a GitHub security advisory id such as
+    /// `GHSA-xxxx-xxxx-xxxx` in the message.
+    pub fn is_security_fix(&self) -> bool {
+        matches!(
+            Subject::parse(&self.message).map(|subject| subject.kind),
+            Some("sec" | "security")
+        ) || self.trailer("Security").is_some()
+            || has_advisory_id(&self.message)
+            || has_advisory_id(&self.body)
+    }
+
+    /// Looks up a git trailer (`Key: value`) in the last paragraph of the
+    /// commit body. Keys match case-insensitively, as git does.
+    pub fn trailer(&self, key: &str) -> Option<&str> {
+        let trailers = self.body.trim_end().rsplit("\n\n").next()?;
+
+        trailers.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case(key) {
+                Some(value.trim())
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn has_advisory_id(text: &str) -> bool {
+    text.match_indices("GHSA-").any(|(start, _)| {
+        let id = &text.as_bytes()[start + 5..];
+        id.len() >= 14
+            && id[..14].iter().enumerate().all(|(i, byte)| match i {
+                4 | 9 => *byte == b'-',
+                _ => byte.is_ascii_alphanumeric(),
+            })
+    })
+}
+
+/// `Token: value` or `Token #value`, with a token like `Signed-off-by`.
+fn is_trailer(line: &str) -> bool {
+    let token_end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(line.len());
+    token_end > 0 && (line[token_end..].starts_with(": ") || line[token_end..].starts_with(" #"))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Project {
+    pub name: String,
+    pub path: PathBuf,
+    pub commits: Vec<Commit>,
+    pub remote: String,
+    /// Group heading the project is rendered under, from `[projects]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// The version a workspace package's manifest declares, for packages
+    /// the repository's tags do not tell apart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The group qualifying the ids of the project's headings, for a copy
+    /// rendered under several groups, as with `--group-by ticket`.
+    #[serde(skip)]
+    pub anchor_group: Option<String>,
+}
+
+impl Project {
+    /// A copy of the project holding `commits` instead of its own.
+    pub fn with_commits(&self, commits: Vec<Commit>) -> Project {
+        Project {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            commits,
+            remote: self.remote.clone(),
+            team: self.team.clone(),
+            description: self.description.clone(),
+            emoji: self.emoji.clone(),
+            homepage: self.homepage.clone(),
+            version: self.version.clone(),
+            anchor_group: self.anchor_group.clone(),
+        }
+    }
+
+    /// The name as shown in headings, after the project's emoji if any.
+    pub fn title(&self) -> String {
+        match &self.emoji {
+            Some(emoji) => format!("{} {}", emoji, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn has_about(&self) -> bool {
+        self.description.is_some() || self.homepage.is_some()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProjectList {
+    pub projects: Vec<Project>,
+    /// What was left out while collecting, reported once the run is done.
+    #[serde(default, skip_serializing_if = "Diagnostics::is_empty")]
+    pub diagnostics: Diagnostics,
+}
+
+impl ProjectList {
+    /// Orders each project's commits with `Commit::newest_first`, for exports.
+    pub fn sort_commits(&mut self) {
+        for project in &mut self.projects {
+            project.commits.sort_by(Commit::newest_first);
+        }
+    }
+}
+
+/// Counts for the line printed when a run completes, and the `meta` of data
+/// exports.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Summary {
+    pub projects: usize,
+    /// Every commit collected, including those skipped.
+    pub commits: usize,
+    /// Commits opted out with a `[skip changelog]` marker or trailer.
+    pub skipped: usize,
+}
+
+impl Summary {
+    pub fn new<'a>(projects: impl IntoIterator<Item = &'a Project>) -> Summary {
+        let mut summary = Summary {
+            projects: 0,
+            commits: 0,
+            skipped: 0,
+        };
+        for project in projects {
+            summary.projects += 1;
+            summary.commits += project.commits.len();
+            summary.skipped += project.commits.iter().filter(|c| c.is_skipped()).count();
+        }
+        summary
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, {} {} matched, {} skipped",
+            self.projects,
+            if self.projects == 1 {
+                "project"
+            } else {
+                "projects"
+            },
+            self.commits,
+            if self.commits == 1 {
+                "commit"
+            } else {
+                "commits"
+            },
+            self.skipped
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_count_in_the_singular_and_plural() {
+        let summary = |projects, commits, skipped| {
+            Summary {
+                projects,
+                commits,
+                skipped,
+            }
+            .to_string()
+        };
+
+        assert_eq!(summary(1, 1, 0), "1 project, 1 commit matched, 0 skipped");
+        assert_eq!(
+            summary(2, 12, 1),
+            "2 projects, 12 commits matched, 1 skipped"
+        );
+        assert_eq!(summary(0, 0, 0), "0 projects, 0 commits matched, 0 skipped");
+    }
+}
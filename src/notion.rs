@@ -0,0 +1,229 @@
+use crate::config::NotionConfig;
+use crate::http;
+use crate::json;
+use crate::model::ProjectList;
+use crate::render::{self, RenderOptions, Section};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const API: &str = "https://api.notion.com/v1";
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Block {
+    #[serde(rename = "heading_2")]
+    Heading2 { heading_2: RichTextBlock },
+    #[serde(rename = "heading_3")]
+    Heading3 { heading_3: RichTextBlock },
+    #[serde(rename = "paragraph")]
+    Paragraph { paragraph: RichTextBlock },
+    #[serde(rename = "toggle")]
+    Toggle { toggle: RichTextBlock },
+    #[serde(rename = "bulleted_list_item")]
+    BulletedListItem { bulleted_list_item: RichTextBlock },
+    #[serde(rename = "quote")]
+    Quote { quote: RichTextBlock },
+}
+
+#[derive(Serialize)]
+struct RichTextBlock {
+    rich_text: Vec<RichText>,
+    children: Option<Vec<Block>>,
+}
+
+#[derive(Serialize)]
+struct RichText {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    text: Text,
+}
+
+#[derive(Serialize)]
+struct Text {
+    content: String,
+    link: Option<Link>,
+}
+
+#[derive(Serialize)]
+struct Link {
+    url: String,
+}
+
+#[derive(Serialize)]
+struct AppendChildren {
+    children: Vec<Block>,
+}
+
+#[derive(Serialize)]
+struct CreatePage {
+    parent: Parent,
+    properties: BTreeMap<String, TitleProperty>,
+    children: Vec<Block>,
+}
+
+#[derive(Serialize)]
+struct Parent {
+    database_id: String,
+}
+
+#[derive(Serialize)]
+struct TitleProperty {
+    title: Vec<RichText>,
+}
+
+fn text(content: &str, link: Option<&str>) -> RichText {
+    RichText {
+        kind: "text",
+        text: Text {
+            // Notion rejects text objects over 2000 characters.
+            content: render::truncate(content, 2000).into_owned(),
+            link: link.map(|url| Link {
+                url: url.to_string(),
+            }),
+        },
+    }
+}
+
+fn rich_text_block(rich_text: Vec<RichText>, children: Option<Vec<Block>>) -> RichTextBlock {
+    RichTextBlock {
+        rich_text,
+        children,
+    }
+}
+
+fn entry_blocks(section: &Section) -> Vec<Block> {
+    let mut blocks = vec![Block::Heading3 {
+        heading_3: rich_text_block(vec![text(section.kind.title(), None)], None),
+    }];
+
+    for entry in &section.entries {
+        blocks.push(Block::BulletedListItem {
+            bulleted_list_item: rich_text_block(
+                vec![
+                    text(&format!("{} ", entry.message), None),
+                    text(
+                        &format!("#{}", entry.short_hash()),
+                        Some(entry.link.as_str()).filter(|link| !link.is_empty()),
+                    ),
+                ],
+                entry.breaking_change.as_ref().map(|note| {
+                    vec![Block::Quote {
+                        quote: rich_text_block(
+                            vec![text(&format!("Breaking change: {}", note), None)],
+                            None,
+                        ),
+                    }]
+                }),
+            ),
+        });
+    }
+
+    if let Some(more) = &section.more {
+        blocks.push(Block::BulletedListItem {
+            bulleted_list_item: rich_text_block(vec![text(&more.text(), Some(&more.link))], None),
+        });
+    }
+
+    blocks
+}
+
+/// Each project becomes a toggle block holding its sections, so long
+/// changelogs stay collapsed in the page.
+fn project_blocks(options: &RenderOptions, projects: &ProjectList) -> Vec<Block> {
+    projects
+        .projects
+        .iter()
+        .map(|project| {
+            let sections = render::sections(project, options);
+            let mut children = vec![];
+
+            if project.has_about() {
+                let mut about = vec![];
+                if let Some(description) = &project.description {
+                    about.push(text(description, None));
+                }
+                if let Some(homepage) = &project.homepage {
+                    if !about.is_empty() {
+                        about.push(text(" · ", None));
+                    }
+                    about.push(text("Homepage", Some(homepage)));
+                }
+                children.push(Block::Paragraph {
+                    paragraph: rich_text_block(about, None),
+                });
+            }
+
+            children.extend(sections.sections.iter().flat_map(entry_blocks));
+
+            Block::Toggle {
+                toggle: rich_text_block(
+                    vec![text(&project.title(), None)],
+                    Some(children).filter(|children| !children.is_empty()),
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Appends the changelog to `page_id`, or creates a new entry in
+/// `database_id`, authenticating with `NOTION_TOKEN`.
+pub fn publish(
+    config: &NotionConfig,
+    options: &RenderOptions,
+    projects: &ProjectList,
+) -> Result<(), String> {
+    let http_config = &options.hosts.http;
+    let token = std::env::var("NOTION_TOKEN").map_err(|_| "NOTION_TOKEN is not set".to_string())?;
+    let headers = [
+        format!("Authorization: Bearer {}", token),
+        "Notion-Version: 2022-06-28".to_string(),
+    ];
+
+    let title = format!("Changelog for {}", options.generated_at.format("%Y-%m-%d"));
+
+    if let Some(database_id) = &config.database_id {
+        let mut properties = BTreeMap::new();
+        properties.insert(
+            config
+                .title_property
+                .clone()
+                .unwrap_or_else(|| "Name".to_string()),
+            TitleProperty {
+                title: vec![text(&title, None)],
+            },
+        );
+
+        let page = CreatePage {
+            parent: Parent {
+                database_id: database_id.clone(),
+            },
+            properties,
+            children: project_blocks(options, projects),
+        };
+
+        http::request(
+            http_config,
+            "POST",
+            &format!("{}/pages", API),
+            &headers,
+            Some(&json::to_string(&page)?),
+        )?;
+    } else if let Some(page_id) = &config.page_id {
+        let mut children = vec![Block::Heading2 {
+            heading_2: rich_text_block(vec![text(&title, None)], None),
+        }];
+        children.extend(project_blocks(options, projects));
+
+        http::request(
+            http_config,
+            "PATCH",
+            &format!("{}/blocks/{}/children", API, page_id),
+            &headers,
+            Some(&json::to_string(&AppendChildren { children })?),
+        )?;
+    } else {
+        return Err("[notion] needs either page_id or database_id".to_string());
+    }
+
+    Ok(())
+}
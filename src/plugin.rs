@@ -0,0 +1,45 @@
+use crate::config::PluginConfig;
+use crate::json;
+use crate::model::ProjectList;
+use crate::shell;
+use std::io::Write;
+use std::process::Stdio;
+
+/// Pipes the collected projects through each plugin in turn. A plugin reads
+/// the project list as JSON on stdin, the same shape `--format json`
+/// exports, and writes the transformed list back to stdout.
+pub fn run(plugins: &[PluginConfig], mut projects: ProjectList) -> Result<ProjectList, String> {
+    for plugin in plugins {
+        projects = run_one(&plugin.command, &projects)
+            .map_err(|e| format!("plugin `{}` failed: {}", plugin.command, e))?;
+    }
+
+    Ok(projects)
+}
+
+fn run_one(command: &str, projects: &ProjectList) -> Result<ProjectList, String> {
+    let input = json::to_string(projects)?;
+
+    let mut child = shell::command(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // Written from a thread so a plugin that streams its output before
+    // reading all of its input cannot deadlock on full pipes.
+    let mut stdin = child.stdin.take().expect("plugin stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    writer
+        .join()
+        .expect("plugin input writer panicked")
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("exited with {}", output.status));
+    }
+
+    json::from_str(&String::from_utf8_lossy(&output.stdout))
+}
@@ -0,0 +1,71 @@
+use crate::http::{self, url_encode};
+use crate::json::quote;
+use crate::remote::{parse_remote, Hosts};
+
+const TITLE: &str = "chore(release): update changelog";
+
+/// Opens a GitHub pull request or GitLab merge request from `branch` into
+/// `base`, authenticating with `GITHUB_TOKEN`/`GH_TOKEN` or `GITLAB_TOKEN`,
+/// or a token stored by `auth login`.
+pub fn open_pull_request(
+    hosts: &Hosts,
+    remote: &str,
+    branch: &str,
+    base: &str,
+    notes: &str,
+) -> Result<(), String> {
+    let remote = parse_remote(remote).ok_or_else(|| format!("unrecognized remote {}", remote))?;
+
+    if remote.is_github(hosts) {
+        let token = http::github_token(&remote.host)?;
+        let api = remote.github_api(hosts);
+
+        let body = format!(
+            "{{\"title\":{},\"head\":{},\"base\":{},\"body\":{}}}",
+            quote(TITLE),
+            quote(branch),
+            quote(base),
+            quote(notes)
+        );
+
+        http::request(
+            &hosts.http,
+            "POST",
+            &format!("{}/repos/{}/pulls", api, remote.path),
+            &[
+                format!("Authorization: Bearer {}", token),
+                "Accept: application/vnd.github+json".to_string(),
+            ],
+            Some(&body),
+        )?;
+    } else if remote.is_gitlab(hosts) {
+        let token = http::gitlab_token(&remote.host)?;
+
+        let body = format!(
+            "{{\"title\":{},\"source_branch\":{},\"target_branch\":{},\"description\":{}}}",
+            quote(TITLE),
+            quote(branch),
+            quote(base),
+            quote(notes)
+        );
+
+        http::request(
+            &hosts.http,
+            "POST",
+            &format!(
+                "{}/projects/{}/merge_requests",
+                remote.gitlab_api(hosts),
+                url_encode(&remote.path)
+            ),
+            &[format!("PRIVATE-TOKEN: {}", token)],
+            Some(&body),
+        )?;
+    } else {
+        return Err(format!(
+            "opening pull requests on {} is not supported",
+            remote.host
+        ));
+    }
+
+    Ok(())
+}
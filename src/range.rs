@@ -0,0 +1,106 @@
+use crate::config::{CalendarConfig, SprintConfig};
+use chrono::prelude::*;
+use chrono::{Duration, Months};
+
+/// A named window of days, resolved against today's date.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+    ThisSprint,
+    LastSprint,
+}
+
+impl Preset {
+    pub fn parse(name: &str) -> Option<Preset> {
+        match name {
+            "this-week" => Some(Preset::ThisWeek),
+            "last-week" => Some(Preset::LastWeek),
+            "this-month" => Some(Preset::ThisMonth),
+            "last-month" => Some(Preset::LastMonth),
+            "this-sprint" => Some(Preset::ThisSprint),
+            "last-sprint" => Some(Preset::LastSprint),
+            _ => None,
+        }
+    }
+
+    /// The first day of the window and the day after its last. Weeks start
+    /// on Monday; windows that are still running end tomorrow.
+    pub fn resolve(
+        self,
+        sprint: &SprintConfig,
+        today: NaiveDate,
+    ) -> Result<(NaiveDate, NaiveDate), String> {
+        let tomorrow = today + Duration::days(1);
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let first_of_month = today.with_day(1).expect("every month has a first day");
+
+        Ok(match self {
+            Preset::ThisWeek => (monday, tomorrow),
+            Preset::LastWeek => (monday - Duration::days(7), monday),
+            Preset::ThisMonth => (first_of_month, tomorrow),
+            Preset::LastMonth => (first_of_month - Months::new(1), first_of_month),
+            Preset::ThisSprint | Preset::LastSprint => {
+                let start = sprint_start(sprint, today)?;
+                if self == Preset::ThisSprint {
+                    (start, tomorrow)
+                } else {
+                    (start - Duration::days(sprint.length), start)
+                }
+            }
+        })
+    }
+}
+
+/// The window covering the last `days` working days up to today, counting
+/// today if it is one. Weekends and `holidays` are skipped.
+pub fn business_days(
+    days: i64,
+    calendar: &CalendarConfig,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), String> {
+    let holidays = calendar
+        .holidays
+        .iter()
+        .map(|day| {
+            NaiveDate::parse_from_str(day, "%Y-%m-%d")
+                .map_err(|e| format!("invalid holiday {}: {}", day, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut since = today;
+    let mut remaining = days;
+    loop {
+        let working =
+            !matches!(since.weekday(), Weekday::Sat | Weekday::Sun) && !holidays.contains(&since);
+        if working {
+            remaining -= 1;
+        }
+        if remaining <= 0 {
+            break;
+        }
+        since -= Duration::days(1);
+    }
+
+    Ok((since, today + Duration::days(1)))
+}
+
+/// The first day of the sprint `today` falls in, counted in whole sprints
+/// from the configured start of any one sprint.
+fn sprint_start(sprint: &SprintConfig, today: NaiveDate) -> Result<NaiveDate, String> {
+    let anchor = sprint
+        .start
+        .as_deref()
+        .ok_or("sprint ranges need [sprint] start, the first day of any sprint")?;
+    let anchor = NaiveDate::parse_from_str(anchor, "%Y-%m-%d")
+        .map_err(|e| format!("invalid [sprint] start {}: {}", anchor, e))?;
+
+    if sprint.length < 1 {
+        return Err("[sprint] length must be at least one day".to_string());
+    }
+
+    let elapsed = (today - anchor).num_days();
+    Ok(anchor + Duration::days(elapsed.div_euclid(sprint.length) * sprint.length))
+}
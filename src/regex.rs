@@ -0,0 +1,749 @@
+//! A small backtracking regular expression engine for rewrite rules, in the
+//! spirit of the hand-rolled JSON and YAML support. It covers the familiar
+//! syntax: literals and escapes, `.`, classes such as `[^a-z0-9_]` with
+//! `\d`, `\w` and `\s`, anchors `^`, `$` and `\b`, capturing and `(?:…)`
+//! groups, `|`, and the greedy or lazy quantifiers `*`, `+`, `?` and
+//! `{n,m}`. A leading `(?i)` makes the whole pattern case-insensitive.
+//! Patterns are compiled to a program the matcher runs with a stack of its
+//! own rather than by recursion, trying each instruction at each position
+//! at most once, so long messages neither overflow the stack nor take
+//! exponential time.
+
+#[derive(Debug)]
+pub struct Regex {
+    program: Vec<Inst>,
+    groups: usize,
+    ignore_case: bool,
+}
+
+#[derive(Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary(bool),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+/// An instruction of a compiled pattern. Those that test the text move on
+/// to the next character and instruction when they match.
+#[derive(Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary(bool),
+    /// Goes on at the first instruction, and at the second when that fails.
+    Split(usize, usize),
+    Jump(usize),
+    /// Records the position in a capture slot: group `n` starts in `2n` and
+    /// ends in `2n + 1`.
+    Save(usize),
+    Match,
+}
+
+type Captures = Vec<Option<(usize, usize)>>;
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let (pattern, ignore_case) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (rest, true),
+            None => (pattern, false),
+        };
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            groups: 0,
+        };
+
+        let root = parser.alternation()?;
+        if parser.pos != parser.chars.len() {
+            return Err(format!("unmatched ) at offset {}", parser.pos));
+        }
+
+        let mut program = vec![];
+        compile(&root, &mut program);
+        program.push(Inst::Match);
+
+        Ok(Regex {
+            program,
+            groups: parser.groups,
+            ignore_case,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        self.find_at(&text, 0).is_some()
+    }
+
+    /// Replaces every match. `$1` or `${1}` in `replacement` insert a
+    /// group, `$0` the whole match and `$$` a dollar sign. An empty match
+    /// right where the previous match ended is not replaced.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut replaced = String::new();
+        // Where the text not yet copied starts, and where to search next.
+        let mut copied = 0;
+        let mut from = 0;
+        let mut previous_end = None;
+
+        while from <= chars.len() {
+            let Some(captures) = self.find_at(&chars, from) else {
+                break;
+            };
+            let (start, end) = captures[0].expect("a match captures itself");
+
+            if start == end && previous_end == Some(start) {
+                from = start + 1;
+                continue;
+            }
+
+            replaced.extend(&chars[copied..start]);
+            expand(&mut replaced, replacement, &chars, &captures);
+            copied = end;
+            previous_end = Some(end);
+            // An empty match moves the search on by one character so it
+            // cannot repeat.
+            from = if start == end { end + 1 } else { end };
+        }
+
+        replaced.extend(&chars[copied..]);
+        replaced
+    }
+
+    fn find_at(&self, text: &[char], from: usize) -> Option<Captures> {
+        let mut matcher = Matcher {
+            program: &self.program,
+            text,
+            ignore_case: self.ignore_case,
+            from,
+            visited: vec![0; (self.program.len() * (text.len() - from + 1)).div_ceil(64)],
+        };
+
+        (from..=text.len()).find_map(|start| {
+            let slots = matcher.run(start, (self.groups + 1) * 2)?;
+            Some(
+                slots
+                    .chunks(2)
+                    .map(|slot| Some((slot[0]?, slot[1]?)))
+                    .collect(),
+            )
+        })
+    }
+}
+
+/// `text` as a pattern that matches it literally.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn expand(replaced: &mut String, replacement: &str, text: &[char], captures: &Captures) {
+    let mut chars = replacement.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            replaced.push(c);
+            continue;
+        }
+
+        let braced = chars.next_if_eq(&'{').is_some();
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+            digits.push(digit);
+        }
+        if braced {
+            chars.next_if_eq(&'}');
+        }
+
+        match digits.parse::<usize>() {
+            Ok(group) => {
+                if let Some(Some((start, end))) = captures.get(group) {
+                    replaced.extend(&text[*start..*end]);
+                }
+            }
+            Err(_) if chars.next_if_eq(&'$').is_some() => replaced.push('$'),
+            Err(_) => replaced.push('$'),
+        }
+    }
+}
+
+/// Appends the instructions matching `node` to `program`. Alternatives and
+/// greedy repeats put the preferred way first in their splits, so the
+/// matcher tries the ways a pattern can match in the usual order.
+fn compile(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { items, negated } => program.push(Inst::Class {
+            items: items.clone(),
+            negated: *negated,
+        }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::WordBoundary(expected) => program.push(Inst::WordBoundary(*expected)),
+        Node::Group(inner, None) => compile(inner, program),
+        Node::Group(inner, Some(index)) => {
+            program.push(Inst::Save(index * 2));
+            compile(inner, program);
+            program.push(Inst::Save(index * 2 + 1));
+        }
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program);
+            }
+        }
+        Node::Alt(alternatives) => {
+            let mut jumps = vec![];
+            for (i, alternative) in alternatives.iter().enumerate() {
+                if i + 1 == alternatives.len() {
+                    compile(alternative, program);
+                    break;
+                }
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(alternative, program);
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        }
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            for _ in 0..*min {
+                compile(node, program);
+            }
+
+            let split = |program: &mut Vec<Inst>, at: usize, skip: usize| {
+                program[at] = if *greedy {
+                    Inst::Split(at + 1, skip)
+                } else {
+                    Inst::Split(skip, at + 1)
+                };
+            };
+            match max {
+                None => {
+                    let start = program.len();
+                    program.push(Inst::Split(0, 0));
+                    compile(node, program);
+                    program.push(Inst::Jump(start));
+                    split(program, start, program.len());
+                }
+                // Each optional round past the minimum can only be tried
+                // after the one before it matched.
+                Some(max) => {
+                    let mut splits = vec![];
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(node, program);
+                    }
+                    let end = program.len();
+                    for at in splits {
+                        split(program, at, end);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Runs a program over the text from one start after another. Which
+/// instruction was tried at which position is kept across starts: the
+/// program matches the same from there whichever way it got there, so a
+/// second try would fail again.
+struct Matcher<'a> {
+    program: &'a [Inst],
+    text: &'a [char],
+    ignore_case: bool,
+    /// The first start position, below which nothing is tried.
+    from: usize,
+    /// A bit per instruction and position from `from` to the end.
+    visited: Vec<u64>,
+}
+
+/// What is left to do when the current way of matching fails.
+enum Job {
+    Try(usize, usize),
+    Restore(usize, Option<usize>),
+}
+
+impl Matcher<'_> {
+    /// The capture slots of the first way the program matches from
+    /// `start`, in the order splits prefer.
+    fn run(&mut self, start: usize, slots: usize) -> Option<Vec<Option<usize>>> {
+        let mut slots = vec![None; slots];
+        slots[0] = Some(start);
+        let mut jobs = vec![Job::Try(0, start)];
+
+        while let Some(job) = jobs.pop() {
+            let (mut pc, mut pos) = match job {
+                Job::Try(pc, pos) => (pc, pos),
+                Job::Restore(slot, value) => {
+                    slots[slot] = value;
+                    continue;
+                }
+            };
+
+            loop {
+                if !self.visit(pc, pos) {
+                    break;
+                }
+
+                match &self.program[pc] {
+                    Inst::Char(c) => match self.text.get(pos) {
+                        Some(t) if self.same(*c, *t) => pos += 1,
+                        _ => break,
+                    },
+                    Inst::Any => match self.text.get(pos) {
+                        Some(t) if *t != '\n' => pos += 1,
+                        _ => break,
+                    },
+                    Inst::Class { items, negated } => match self.text.get(pos) {
+                        Some(t) if self.in_class(items, *t) != *negated => pos += 1,
+                        _ => break,
+                    },
+                    Inst::Start => {
+                        if pos != 0 {
+                            break;
+                        }
+                    }
+                    Inst::End => {
+                        if pos != self.text.len() {
+                            break;
+                        }
+                    }
+                    Inst::WordBoundary(expected) => {
+                        let word = |i: Option<usize>| {
+                            i.and_then(|i| self.text.get(i))
+                                .is_some_and(|c| is_word(*c))
+                        };
+                        if (word(pos.checked_sub(1)) != word(Some(pos))) != *expected {
+                            break;
+                        }
+                    }
+                    Inst::Split(first, second) => {
+                        jobs.push(Job::Try(*second, pos));
+                        pc = *first;
+                        continue;
+                    }
+                    Inst::Jump(to) => {
+                        pc = *to;
+                        continue;
+                    }
+                    Inst::Save(slot) => {
+                        jobs.push(Job::Restore(*slot, slots[*slot]));
+                        slots[*slot] = Some(pos);
+                    }
+                    Inst::Match => {
+                        slots[1] = Some(pos);
+                        return Some(slots);
+                    }
+                }
+                pc += 1;
+            }
+        }
+
+        None
+    }
+
+    /// Marks the instruction as tried at the position, returning whether it
+    /// was not before.
+    fn visit(&mut self, pc: usize, pos: usize) -> bool {
+        let bit = pc * (self.text.len() - self.from + 1) + (pos - self.from);
+        let (word, mask) = (bit / 64, 1 << (bit % 64));
+        let fresh = self.visited[word] & mask == 0;
+        self.visited[word] |= mask;
+        fresh
+    }
+
+    fn same(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn in_class(&self, items: &[ClassItem], c: char) -> bool {
+        let matches = |c: char| {
+            items.iter().any(|item| match item {
+                ClassItem::Range(from, to) => (*from..=*to).contains(&c),
+                ClassItem::Digit(positive) => c.is_ascii_digit() == *positive,
+                ClassItem::Word(positive) => is_word(c) == *positive,
+                ClassItem::Space(positive) => c.is_whitespace() == *positive,
+            })
+        };
+
+        matches(c)
+            || (self.ignore_case
+                && (c.to_lowercase().any(matches) || c.to_uppercase().any(matches)))
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    groups: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut alternatives = vec![self.concatenation()?];
+        while self.eat('|') {
+            alternatives.push(self.concatenation()?);
+        }
+
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().expect("one alternative")
+        } else {
+            Node::Alt(alternatives)
+        })
+    }
+
+    fn concatenation(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![];
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.bounds() {
+                Some(bounds) => bounds,
+                None => {
+                    self.pos = start;
+                    return Ok(atom);
+                }
+            },
+            _ => return Ok(atom),
+        };
+        if self.pos == start {
+            self.pos += 1;
+        }
+
+        if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return Err(format!("nothing to repeat at offset {}", start));
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(format!("bad repetition bounds at offset {}", start));
+        }
+
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// `{n}`, `{n,}` or `{n,m}`; anything else is a literal brace.
+    fn bounds(&mut self) -> Option<(usize, Option<usize>)> {
+        self.pos += 1;
+        let min = self.number()?;
+        let max = if self.eat(',') {
+            match self.peek() {
+                Some('}') => None,
+                _ => Some(self.number()?),
+            }
+        } else {
+            Some(min)
+        };
+        self.eat('}').then_some((min, max))
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let start = self.pos;
+        let c = self.peek().expect("called with input left");
+        self.pos += 1;
+
+        match c {
+            '(' => {
+                let index = if self.eat('?') {
+                    if !self.eat(':') {
+                        return Err(format!("unsupported group at offset {}", start));
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                let inner = self.alternation()?;
+                if !self.eat(')') {
+                    return Err(format!("unclosed ( at offset {}", start));
+                }
+                Ok(Node::Group(Box::new(inner), index))
+            }
+            '[' => self.class(start),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '*' | '+' | '?' => Err(format!("nothing to repeat at offset {}", start)),
+            '\\' => match self.escape(start)? {
+                Escape::Char(c) => Ok(Node::Char(c)),
+                Escape::Item(item) => Ok(Node::Class {
+                    items: vec![item],
+                    negated: false,
+                }),
+                Escape::WordBoundary(expected) => Ok(Node::WordBoundary(expected)),
+            },
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn class(&mut self, start: usize) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut items = vec![];
+        let mut first = true;
+
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(format!("unclosed [ at offset {}", start));
+            };
+            self.pos += 1;
+
+            // A `]` right after the opening bracket is a literal.
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            let from = match c {
+                '\\' => match self.escape(self.pos - 1)? {
+                    Escape::Char(c) => c,
+                    Escape::Item(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                    Escape::WordBoundary(_) => '\u{8}',
+                },
+                c => c,
+            };
+
+            let range =
+                self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|c| *c != ']');
+            if !range {
+                items.push(ClassItem::Range(from, from));
+                continue;
+            }
+
+            self.pos += 1;
+            let to = match self.peek() {
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.escape(self.pos - 1)? {
+                        Escape::Char(c) => c,
+                        _ => return Err(format!("bad class range at offset {}", self.pos)),
+                    }
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    c
+                }
+                None => return Err(format!("unclosed [ at offset {}", start)),
+            };
+            if to < from {
+                return Err(format!("bad class range at offset {}", self.pos));
+            }
+            items.push(ClassItem::Range(from, to));
+        }
+
+        Ok(Node::Class { items, negated })
+    }
+
+    fn escape(&mut self, start: usize) -> Result<Escape, String> {
+        let Some(c) = self.peek() else {
+            return Err(format!("trailing \\ at offset {}", start));
+        };
+        self.pos += 1;
+
+        Ok(match c {
+            'd' => Escape::Item(ClassItem::Digit(true)),
+            'D' => Escape::Item(ClassItem::Digit(false)),
+            'w' => Escape::Item(ClassItem::Word(true)),
+            'W' => Escape::Item(ClassItem::Word(false)),
+            's' => Escape::Item(ClassItem::Space(true)),
+            'S' => Escape::Item(ClassItem::Space(false)),
+            'b' => Escape::WordBoundary(true),
+            'B' => Escape::WordBoundary(false),
+            'n' => Escape::Char('\n'),
+            't' => Escape::Char('\t'),
+            c if c.is_alphanumeric() => {
+                return Err(format!("unknown escape \\{} at offset {}", c, start))
+            }
+            c => Escape::Char(c),
+        })
+    }
+}
+
+enum Escape {
+    Char(char),
+    Item(ClassItem),
+    WordBoundary(bool),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replace(pattern: &str, text: &str, replacement: &str) -> String {
+        Regex::new(pattern)
+            .expect("a valid pattern")
+            .replace_all(text, replacement)
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(replace(r"[a-c]", "abcd", "x"), "xxxd");
+        assert_eq!(replace(r"[^a-z0-9_]", "a-b_c d", "."), "a.b_c.d");
+        assert_eq!(replace(r"\d+", "v1.23", "N"), "vN.N");
+        assert_eq!(replace(r"[\w-]+", "fix: x-y z", "w"), "w: w w");
+        assert_eq!(replace(r"\s", "a\tb c", "_"), "a_b_c");
+        assert_eq!(replace(r"[]a]", "]a", "x"), "xx");
+        assert_eq!(replace(r"(?i)[a-z]", "AbÉ", "x"), "xxÉ");
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(replace(r"^a", "aaa", "b"), "baa");
+        assert_eq!(replace(r"a$", "aaa", "b"), "aab");
+        assert_eq!(
+            replace(r"\bcat\b", "cat concat cat.", "dog"),
+            "dog concat dog."
+        );
+        assert_eq!(replace(r"\Bcat", "cat concat", "dog"), "cat condog");
+        assert!(!Regex::new(r"^b").unwrap().is_match("ab"));
+    }
+
+    #[test]
+    fn alternation() {
+        assert_eq!(replace(r"feat|fix", "feat and fix", "x"), "x and x");
+        // The first alternative that matches wins, as in other engines.
+        assert_eq!(replace(r"(a|ab)(c|bcd)", "abcd", "[$1,$2]"), "[a,bcd]");
+        assert_eq!(replace(r"(?:x|y)z", "xz yz zz", "_"), "_ _ zz");
+    }
+
+    #[test]
+    fn repetition() {
+        assert_eq!(replace(r"a{2}", "aaaaa", "x"), "xxa");
+        assert_eq!(replace(r"a{2,}", "a aa aaa", "x"), "a x x");
+        assert_eq!(replace(r"a{1,2}", "aaa", "x"), "xx");
+        assert_eq!(replace(r"<.+>", "<a><b>", "x"), "x");
+        assert_eq!(replace(r"<.+?>", "<a><b>", "x"), "xx");
+        assert_eq!(replace(r"(ab)+", "ababa", "[$1]"), "[ab]a");
+        assert_eq!(replace(r"a{,2}", "a{,2}", "x"), "x");
+        assert_eq!(replace(r"(a*)*b", "aab", "[$1]"), "[aa]");
+    }
+
+    #[test]
+    fn empty_matches() {
+        assert_eq!(replace(r"x*", "ax", "-"), "-a-");
+        assert_eq!(replace(r"x*", "abc", "-"), "-a-b-c-");
+        assert_eq!(replace(r"", "ab", "-"), "-a-b-");
+        assert_eq!(replace(r"$", "ab", "!"), "ab!");
+        assert_eq!(replace(r"\b", "a b", "|"), "|a| |b|");
+    }
+
+    #[test]
+    fn long_input() {
+        let subject = "fix: ".to_string() + &"a".repeat(20_000);
+
+        assert!(Regex::new(r"^fix: (.*)$").unwrap().is_match(&subject));
+        assert_eq!(replace(r"a+$", &subject, "b"), "fix: b");
+        // Exponential for a naive backtracker.
+        assert!(!Regex::new(r"^(a+)+$")
+            .unwrap()
+            .is_match(&("a".repeat(5_000) + "!")));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        for pattern in ["(a", "a)", "[a", "*a", "a{2,1}", r"\q", "(?=a)", "^*"] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn replacements() {
+        assert_eq!(replace(r"(\w+)@(\w+)", "ana@acme", "$2/${1}"), "acme/ana");
+        assert_eq!(replace(r"\d", "a1", "$$$0"), "a$1");
+        assert_eq!(replace(r"(x)?y", "y", "[$1]"), "[]");
+    }
+}
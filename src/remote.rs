@@ -0,0 +1,300 @@
+use crate::config::{HostConfig, HostKind, HttpConfig};
+use std::collections::BTreeMap;
+
+/// Self-hosted instances that cannot be recognized by their host name, and
+/// how APIs are reached, gathered from the config.
+#[derive(Debug, Default, Clone)]
+pub struct Hosts {
+    /// By domain.
+    pub configured: BTreeMap<String, HostConfig>,
+    /// How their APIs are reached.
+    pub http: HttpConfig,
+}
+
+impl Hosts {
+    fn kind(&self, host: &str) -> Option<HostKind> {
+        self.configured.get(host).map(|host| host.kind)
+    }
+
+    /// The API root configured for a host.
+    fn api(&self, host: &str) -> Option<String> {
+        let api = self.configured.get(host)?.api.as_deref()?;
+        Some(api.trim_end_matches('/').to_string())
+    }
+}
+
+/// A git remote split into the pieces needed to talk to its hosting service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Remote {
+    pub host: String,
+    /// Repository path on the host, e.g. `owner/repo`, without `.git`.
+    pub path: String,
+}
+
+impl Remote {
+    /// The repository's web page.
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}", self.host, self.path)
+    }
+
+    /// Codeberg and hosts named after Gitea or Forgejo are recognized on
+    /// their own; other self-hosted instances must be listed in `hosts`.
+    pub fn is_gitea(&self, hosts: &Hosts) -> bool {
+        match hosts.kind(&self.host) {
+            Some(kind) => kind == HostKind::Gitea,
+            None => {
+                self.host == "codeberg.org"
+                    || self.host.contains("gitea")
+                    || self.host.contains("forgejo")
+            }
+        }
+    }
+
+    /// GitHub, and GitHub Enterprise under a name with `github` in it or
+    /// listed in `hosts`.
+    pub fn is_github(&self, hosts: &Hosts) -> bool {
+        match hosts.kind(&self.host) {
+            Some(kind) => kind == HostKind::GithubEnterprise,
+            None => self.host.contains("github"),
+        }
+    }
+
+    /// GitLab, and self-managed instances under a name with `gitlab` in it
+    /// or listed in `hosts`.
+    pub fn is_gitlab(&self, hosts: &Hosts) -> bool {
+        match hosts.kind(&self.host) {
+            Some(kind) => kind == HostKind::Gitlab,
+            None => self.host.contains("gitlab"),
+        }
+    }
+
+    /// Recognizes `bitbucket.org` remotes, and Bitbucket Server remotes by
+    /// their `scm/` clone path or a host listed in `hosts`.
+    pub fn bitbucket_repo(&self, hosts: &Hosts) -> Option<BitbucketRepo> {
+        let listed = hosts.kind(&self.host) == Some(HostKind::BitbucketServer);
+        let server = self.path.starts_with("scm/") || listed;
+        if self.host != "bitbucket.org" && !server {
+            return None;
+        }
+
+        let path = self.path.strip_prefix("scm/").unwrap_or(&self.path);
+        let (owner, repository) = path.rsplit_once('/')?;
+
+        Some(BitbucketRepo {
+            server: self.host != "bitbucket.org",
+            host: self.host.clone(),
+            api: hosts.api(&self.host),
+            owner: owner.to_string(),
+            repository: repository.to_string(),
+        })
+    }
+
+    /// REST API root for a Gitea or Forgejo remote.
+    pub fn gitea_api(&self, hosts: &Hosts) -> String {
+        hosts
+            .api(&self.host)
+            .unwrap_or_else(|| format!("https://{}/api/v1", self.host))
+    }
+
+    /// REST API root for a GitHub remote. One configured in `hosts` comes
+    /// first, then `GITHUB_API_URL` as set in GitHub Actions; GitHub
+    /// Enterprise serves it under `/api/v3`.
+    pub fn github_api(&self, hosts: &Hosts) -> String {
+        if let Some(api) = hosts.api(&self.host) {
+            return api;
+        }
+        if let Ok(api) = std::env::var("GITHUB_API_URL") {
+            return api.trim_end_matches('/').to_string();
+        }
+
+        if self.host == "github.com" {
+            "https://api.github.com".to_string()
+        } else {
+            format!("https://{}/api/v3", self.host)
+        }
+    }
+
+    /// REST API root for a GitLab remote.
+    pub fn gitlab_api(&self, hosts: &Hosts) -> String {
+        hosts
+            .api(&self.host)
+            .unwrap_or_else(|| format!("https://{}/api/v4", self.host))
+    }
+
+    /// Recognizes Azure DevOps remotes: `dev.azure.com/org/project/_git/repo`,
+    /// `ssh.dev.azure.com:v3/org/project/repo` and the older
+    /// `org.visualstudio.com/project/_git/repo`.
+    pub fn azure_repo(&self) -> Option<AzureRepo> {
+        let parts: Vec<&str> = self.path.split('/').collect();
+
+        let (organization, project, repository) = match (self.host.as_str(), parts.as_slice()) {
+            ("dev.azure.com", [organization, project, "_git", repository]) => {
+                (*organization, *project, *repository)
+            }
+            (
+                "ssh.dev.azure.com" | "vs-ssh.visualstudio.com",
+                ["v3", organization, project, repository],
+            ) => (*organization, *project, *repository),
+            (host, [.., project, "_git", repository]) if host.ends_with(".visualstudio.com") => (
+                host.strip_suffix(".visualstudio.com")?,
+                *project,
+                *repository,
+            ),
+            _ => return None,
+        };
+
+        Some(AzureRepo {
+            organization: organization.to_string(),
+            project: project.to_string(),
+            repository: repository.to_string(),
+        })
+    }
+}
+
+/// An Azure DevOps repository, which lives inside a project of an
+/// organization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AzureRepo {
+    pub organization: String,
+    pub project: String,
+    pub repository: String,
+}
+
+impl AzureRepo {
+    /// The project's root URL. `SYSTEM_COLLECTIONURI`, as set in Azure
+    /// Pipelines, takes precedence so Azure DevOps Server works too.
+    pub fn project_url(&self) -> String {
+        let collection = std::env::var("SYSTEM_COLLECTIONURI")
+            .unwrap_or_else(|_| format!("https://dev.azure.com/{}", self.organization));
+        format!("{}/{}", collection.trim_end_matches('/'), self.project)
+    }
+
+    pub fn repository_url(&self) -> String {
+        format!("{}/_git/{}", self.project_url(), self.repository)
+    }
+
+    pub fn commit_url(&self, hash: &str) -> String {
+        format!("{}/commit/{}", self.repository_url(), hash)
+    }
+
+    /// The history leading up to `hash`, newest first.
+    pub fn commits_url(&self, hash: &str) -> String {
+        format!("{}/commits?itemVersion=GC{}", self.repository_url(), hash)
+    }
+
+    pub fn pull_request_url(&self, id: u64) -> String {
+        format!("{}/pullrequest/{}", self.repository_url(), id)
+    }
+
+    /// From the tag `base` to the commit `target`; `GT` and `GC` mark the
+    /// versions as a tag and a commit rather than branches.
+    pub fn compare_url(&self, base: &str, target: &str) -> String {
+        format!(
+            "{}/branchCompare?baseVersion=GT{}&targetVersion=GC{}",
+            self.repository_url(),
+            base,
+            target
+        )
+    }
+
+    /// Prefix of a work item link; append the item number.
+    pub fn work_item_url(&self) -> String {
+        format!("{}/_workitems/edit/", self.project_url())
+    }
+}
+
+/// Parses the common remote URL shapes: `https://host/owner/repo.git`,
+/// `ssh://git@host:22/owner/repo.git` and scp-like `git@host:owner/repo.git`.
+pub fn parse_remote(url: &str) -> Option<Remote> {
+    let url = url.trim();
+
+    let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority.rsplit('@').next()?;
+        let host = host.split(':').next()?;
+        (host, path)
+    } else {
+        let (authority, path) = url.split_once(':')?;
+        let host = authority.rsplit('@').next()?;
+        (host, path)
+    };
+
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    Some(Remote {
+        host: host.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// A Bitbucket repository. On Bitbucket Cloud `owner` is the workspace; on
+/// Bitbucket Server it is the project key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitbucketRepo {
+    pub server: bool,
+    pub host: String,
+    /// The REST root configured for a server, if not the usual one.
+    pub api: Option<String>,
+    pub owner: String,
+    pub repository: String,
+}
+
+impl BitbucketRepo {
+    pub fn repository_url(&self) -> String {
+        if self.server {
+            format!(
+                "https://{}/projects/{}/repos/{}",
+                self.host, self.owner, self.repository
+            )
+        } else {
+            format!("https://bitbucket.org/{}/{}", self.owner, self.repository)
+        }
+    }
+
+    pub fn commit_url(&self, hash: &str) -> String {
+        format!("{}/commits/{}", self.repository_url(), hash)
+    }
+
+    /// Cloud puts the newer side first, separated by a carriage return.
+    pub fn compare_url(&self, base: &str, target: &str) -> String {
+        if self.server {
+            format!(
+                "{}/compare/commits?sourceBranch={}&targetBranch={}",
+                self.repository_url(),
+                target,
+                base
+            )
+        } else {
+            format!(
+                "{}/branches/compare/{}%0D{}",
+                self.repository_url(),
+                target,
+                base
+            )
+        }
+    }
+
+    /// The REST endpoint listing the pull requests that contain a commit.
+    pub fn commit_pull_requests_api(&self, hash: &str) -> String {
+        if self.server {
+            let api = self
+                .api
+                .clone()
+                .unwrap_or_else(|| format!("https://{}/rest/api/latest", self.host));
+            format!(
+                "{}/projects/{}/repos/{}/commits/{}/pull-requests",
+                api, self.owner, self.repository, hash
+            )
+        } else {
+            format!(
+                "https://api.bitbucket.org/2.0/repositories/{}/{}/commit/{}/pullrequests",
+                self.owner, self.repository, hash
+            )
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::{project_anchor, Groups, Section, Sections, Ticket};
+use crate::model::Project;
+
+pub fn header(date: &str) -> String {
+    format!("= Changelog for {}\n\n", date)
+}
+
+pub fn toc(groups: &Groups) -> String {
+    let mut toc = String::from(".Contents\n");
+
+    for (group, projects) in groups {
+        let bullet = match group {
+            Some(group) => {
+                toc.push_str(&format!("* {}\n", group));
+                "**"
+            }
+            None => "*",
+        };
+        for project in projects {
+            toc.push_str(&format!(
+                "{} <<{},{}>>\n",
+                bullet,
+                project_anchor(project),
+                project.title()
+            ));
+        }
+    }
+
+    toc.push('\n');
+    toc
+}
+
+/// A discrete heading, since level 0 sections are only valid in books.
+pub fn group(name: &str) -> String {
+    format!("[discrete]\n= {}\n\n", name)
+}
+
+/// Every mentioned ticket with the commits that mention it, after the
+/// projects.
+pub fn tickets(tickets: &[Ticket]) -> String {
+    let mut changelog = String::from("[[tickets]]\n== Tickets\n\n");
+
+    for ticket in tickets {
+        if ticket.url.is_empty() {
+            changelog.push_str(&format!("* {}\n", ticket.name));
+        } else {
+            changelog.push_str(&format!("* link:{}[{}]\n", ticket.url, ticket.name));
+        }
+        for mention in &ticket.mentions {
+            changelog.push_str(&format!(
+                "** {}: {} {}\n",
+                mention.project.name,
+                mention.commit.message,
+                link(&format!("#{}", mention.short_hash()), &mention.link)
+            ));
+        }
+    }
+
+    changelog.push('\n');
+    changelog
+}
+
+/// Every project's security fixes, ahead of the projects.
+pub fn security(sections: &Sections, section: &Section) -> String {
+    let mut changelog = format!("[[security]]\n== {}\n\n", section.kind.title());
+    push_entries(&mut changelog, sections, section);
+    changelog
+}
+
+pub fn project(project: &Project, sections: &Sections) -> String {
+    let mut changelog = String::new();
+
+    changelog.push_str(&format!(
+        "[[{}]]\n== {}\n\n",
+        project_anchor(project),
+        project.title()
+    ));
+
+    if project.has_about() {
+        let mut about = vec![];
+        if let Some(description) = &project.description {
+            about.push(description.clone());
+        }
+        if let Some(homepage) = &project.homepage {
+            about.push(format!("link:{}[Homepage]", homepage));
+        }
+        changelog.push_str(&format!("{}\n\n", about.join(" · ")));
+    }
+
+    for section in &sections.sections {
+        changelog.push_str(&format!(
+            "[[{}]]\n=== {}\n\n",
+            section.anchor(project),
+            section.kind.title()
+        ));
+        push_entries(&mut changelog, sections, section);
+    }
+
+    changelog
+}
+
+// Entries are followed by a blank line so the next section title is not
+// folded into the last list item.
+fn push_entries(changelog: &mut String, sections: &Sections, section: &Section) {
+    for entry in &section.entries {
+        changelog.push_str(&format!(
+            "* {} {}\n",
+            sections.autolink(&entry.message, str::to_string, |text, url| format!(
+                "link:{}[{}]",
+                url, text
+            )),
+            link(&format!("#{}", entry.short_hash()), &entry.link)
+        ));
+
+        // `+` attaches the admonition block to the list item.
+        if let Some(note) = &entry.breaking_change {
+            changelog.push_str(&format!("+\n[IMPORTANT]\n====\n{}\n====\n", note));
+        }
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!("* link:{}[{}]\n", more.link, more.text()));
+    }
+
+    changelog.push('\n');
+}
+
+/// `text` linked to `url`, or left as is when there is nowhere to link.
+fn link(text: &str, url: &str) -> String {
+    if url.is_empty() {
+        text.to_string()
+    } else {
+        format!("link:{}[{}]", url, text)
+    }
+}
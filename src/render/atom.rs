@@ -0,0 +1,92 @@
+use super::html::{escape, link};
+use super::{Entry, SectionKind, Sections};
+use crate::model::Project;
+use std::collections::BTreeMap;
+
+pub fn header(updated: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+         \x20 <id>urn:chenjirogu:changelog</id>\n\
+         \x20 <title>Changelog</title>\n\
+         \x20 <updated>{}</updated>\n",
+        updated
+    )
+}
+
+/// Emits one feed entry per day with changelog-worthy commits, so readers
+/// get a new item for each day of work rather than one ever-growing entry.
+pub fn project(project: &Project, sections: &Sections) -> String {
+    let mut days: BTreeMap<&str, BTreeMap<SectionKind, Vec<&Entry>>> = BTreeMap::new();
+
+    for section in &sections.sections {
+        for entry in &section.entries {
+            days.entry(day(entry))
+                .or_default()
+                .entry(section.kind)
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let mut feed = String::new();
+
+    for (day, day_sections) in days.iter().rev() {
+        let entries = day_sections.values().flatten();
+        let updated = entries
+            .clone()
+            .map(|entry| entry.commit.date.as_str())
+            .max()
+            .unwrap_or(day);
+        let author = entries
+            .map(|entry| &*entry.commit.author_name)
+            .next()
+            .unwrap_or_default();
+
+        let mut content = String::new();
+        for (kind, entries) in day_sections {
+            push_html_section(&mut content, kind.title(), entries);
+        }
+
+        feed.push_str(&format!(
+            "  <entry>\n    <id>urn:chenjirogu:{}:{}</id>\n    <title>{} — {}</title>\n    <updated>{}</updated>\n    <author><name>{}</name></author>\n    <content type=\"html\">{}</content>\n  </entry>\n",
+            escape(&project.name),
+            day,
+            escape(&project.name),
+            day,
+            updated,
+            escape(author),
+            escape(&content)
+        ));
+    }
+
+    feed
+}
+
+pub fn footer() -> String {
+    "</feed>\n".to_string()
+}
+
+fn day<'a>(entry: &Entry<'a>) -> &'a str {
+    entry.commit.date.get(0..10).unwrap_or(&entry.commit.date)
+}
+
+fn push_html_section(content: &mut String, title: &str, entries: &[&Entry]) {
+    content.push_str(&format!("<h3>{}</h3><ul>", title));
+    for entry in entries {
+        content.push_str(&format!(
+            "<li>{} {}{}</li>",
+            escape(&entry.message),
+            link(&format!("#{}", entry.short_hash()), &entry.link),
+            entry
+                .breaking_change
+                .as_deref()
+                .map(|note| format!(
+                    "<blockquote><strong>Breaking change:</strong> {}</blockquote>",
+                    escape(note).replace('\n', "<br>")
+                ))
+                .unwrap_or_default()
+        ));
+    }
+    content.push_str("</ul>");
+}
@@ -0,0 +1,82 @@
+use crate::model::Project;
+use crate::yaml;
+use chrono::prelude::*;
+use serde::Serialize;
+
+/// The static site generator a markdown document is written for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrontMatter {
+    Hugo,
+    Jekyll,
+    Zola,
+}
+
+impl FrontMatter {
+    pub fn parse(name: &str) -> Option<FrontMatter> {
+        match name {
+            "hugo" => Some(FrontMatter::Hugo),
+            "jekyll" => Some(FrontMatter::Jekyll),
+            "zola" => Some(FrontMatter::Zola),
+            _ => None,
+        }
+    }
+}
+
+/// The date is a string in YAML and a native datetime in TOML.
+#[derive(Serialize)]
+struct Page<'a, D> {
+    title: &'a str,
+    date: D,
+    tags: Vec<&'a str>,
+}
+
+/// Zola only knows tags as a taxonomy.
+#[derive(Serialize)]
+struct ZolaPage<'a> {
+    title: &'a str,
+    date: toml::value::Datetime,
+    taxonomies: Taxonomies<'a>,
+}
+
+#[derive(Serialize)]
+struct Taxonomies<'a> {
+    tags: Vec<&'a str>,
+}
+
+/// Hugo and Zola get TOML between `+++` lines, Jekyll YAML between `---`
+/// lines. Projects become tags.
+pub fn render(
+    kind: FrontMatter,
+    title: &str,
+    now: &DateTime<FixedOffset>,
+    projects: &[&Project],
+) -> String {
+    let date = now.to_rfc3339_opts(SecondsFormat::Secs, false);
+    let datetime: toml::value::Datetime = date.parse().expect("RFC 3339 is a TOML datetime");
+    let tags: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+
+    match kind {
+        FrontMatter::Hugo => format!(
+            "+++\n{}+++\n\n",
+            toml::to_string(&Page {
+                title,
+                date: datetime,
+                tags
+            })
+            .expect("Failed to serialize TOML")
+        ),
+        FrontMatter::Zola => format!(
+            "+++\n{}+++\n\n",
+            toml::to_string(&ZolaPage {
+                title,
+                date: datetime,
+                taxonomies: Taxonomies { tags },
+            })
+            .expect("Failed to serialize TOML")
+        ),
+        FrontMatter::Jekyll => format!(
+            "---\n{}---\n\n",
+            yaml::to_string(&Page { title, date, tags }).expect("Failed to serialize YAML")
+        ),
+    }
+}
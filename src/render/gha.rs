@@ -0,0 +1,73 @@
+use super::{Section, Sections};
+use crate::model::Project;
+
+/// Groups each project's entries in the log and raises one notice per
+/// project, so the counts show up in the workflow run's annotations.
+pub fn project(project: &Project, sections: &Sections) -> String {
+    if sections.is_empty() {
+        return String::new();
+    }
+
+    let mut changelog = String::new();
+
+    let counts: Vec<String> = sections
+        .sections
+        .iter()
+        .map(|section| {
+            format!(
+                "{} {}",
+                section.total(),
+                section.kind.title().to_lowercase()
+            )
+        })
+        .collect();
+
+    changelog.push_str(&format!(
+        "::notice title={}::{}\n",
+        escape_property(&project.name),
+        escape_data(&counts.join(", "))
+    ));
+    changelog.push_str(&format!("::group::{}\n", escape_data(&project.name)));
+
+    for section in &sections.sections {
+        push_entries(&mut changelog, section);
+    }
+
+    changelog.push_str("::endgroup::\n");
+    changelog
+}
+
+fn push_entries(changelog: &mut String, section: &Section) {
+    changelog.push_str(&format!("{}:\n", section.kind.title()));
+    for entry in &section.entries {
+        changelog.push_str(&format!(
+            "  - {} ({})\n",
+            escape_data(&entry.message),
+            entry.reference()
+        ));
+
+        if let Some(note) = &entry.breaking_change {
+            for line in format!("Breaking change: {}", note).lines() {
+                changelog.push_str(&format!("      {}\n", escape_data(line)));
+            }
+        }
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!(
+            "  {} ({})\n",
+            escape_data(&more.text()),
+            more.link
+        ));
+    }
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,198 @@
+use super::{project_anchor, Groups, Section, SectionKind, Sections, Ticket};
+use crate::model::Project;
+
+pub fn header(date: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Changelog for {0}</title>\n</head>\n<body>\n<h1>Changelog for {0}</h1>\n",
+        date
+    )
+}
+
+pub fn toc(groups: &Groups) -> String {
+    let mut toc = String::from("<nav>\n<ul>\n");
+
+    for (group, projects) in groups {
+        if let Some(group) = group {
+            toc.push_str(&format!("<li>{}\n<ul>\n", escape(group)));
+        }
+        for project in projects {
+            toc.push_str(&format!(
+                "<li><a href=\"#{}\">{}</a></li>\n",
+                project_anchor(project),
+                escape(&project.title())
+            ));
+        }
+        if group.is_some() {
+            toc.push_str("</ul>\n</li>\n");
+        }
+    }
+
+    toc.push_str("</ul>\n</nav>\n");
+    toc
+}
+
+pub fn group(name: &str) -> String {
+    format!("<h1>{}</h1>\n", escape(name))
+}
+
+/// Every mentioned ticket with the commits that mention it, after the
+/// projects.
+pub fn tickets(tickets: &[Ticket]) -> String {
+    let mut changelog = String::from("<h2 id=\"tickets\">Tickets</h2>\n<ul>\n");
+
+    for ticket in tickets {
+        if ticket.url.is_empty() {
+            changelog.push_str(&format!("<li>{}\n<ul>\n", escape(&ticket.name)));
+        } else {
+            changelog.push_str(&format!(
+                "<li><a href=\"{}\">{}</a>\n<ul>\n",
+                escape(&ticket.url),
+                escape(&ticket.name)
+            ));
+        }
+        for mention in &ticket.mentions {
+            changelog.push_str(&format!(
+                "<li>{}: {} {}</li>\n",
+                escape(&mention.project.name),
+                escape(&mention.commit.message),
+                link(&format!("#{}", mention.short_hash()), &mention.link)
+            ));
+        }
+        changelog.push_str("</ul>\n</li>\n");
+    }
+
+    changelog.push_str("</ul>\n");
+    changelog
+}
+
+/// Every project's security fixes, ahead of the projects.
+pub fn security(sections: &Sections, section: &Section) -> String {
+    let mut changelog = format!("<h2 id=\"security\">{}</h2>\n", section.kind.title());
+    push_entries(&mut changelog, sections, section);
+    changelog
+}
+
+/// Renders a project as an XHTML fragment, which is also what Confluence's
+/// storage format expects.
+pub fn project(project: &Project, sections: &Sections) -> String {
+    let mut changelog = String::new();
+
+    changelog.push_str(&format!(
+        "<h2 id=\"{}\">{}</h2>\n",
+        project_anchor(project),
+        escape(&project.title())
+    ));
+
+    if project.has_about() {
+        let mut about = vec![];
+        if let Some(description) = &project.description {
+            about.push(escape(description));
+        }
+        if let Some(homepage) = &project.homepage {
+            about.push(format!("<a href=\"{}\">Homepage</a>", escape(homepage)));
+        }
+        changelog.push_str(&format!("<p>{}</p>\n", about.join(" &middot; ")));
+    }
+
+    for section in &sections.sections {
+        changelog.push_str(&format!(
+            "<h3 id=\"{}\">{}</h3>\n",
+            section.anchor(project),
+            section.kind.title()
+        ));
+
+        if section.kind == SectionKind::Dependencies {
+            push_dependency_table(&mut changelog, section);
+        } else {
+            push_entries(&mut changelog, sections, section);
+        }
+    }
+
+    changelog
+}
+
+pub fn footer() -> String {
+    "</body>\n</html>\n".to_string()
+}
+
+fn push_entries(changelog: &mut String, sections: &Sections, section: &Section) {
+    changelog.push_str("<ul>\n");
+
+    for entry in &section.entries {
+        changelog.push_str(&format!(
+            "<li>{} {}{}</li>\n",
+            sections.autolink(&entry.message, escape, |text, url| format!(
+                "<a href=\"{}\">{}</a>",
+                escape(url),
+                escape(text)
+            )),
+            link(&format!("#{}", entry.short_hash()), &entry.link),
+            entry
+                .breaking_change
+                .as_deref()
+                .map(breaking_change)
+                .unwrap_or_default()
+        ));
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            escape(&more.link),
+            more.text()
+        ));
+    }
+
+    changelog.push_str("</ul>\n");
+}
+
+fn breaking_change(note: &str) -> String {
+    format!(
+        "<blockquote><strong>Breaking change:</strong> {}</blockquote>",
+        escape(note).replace('\n', "<br>\n")
+    )
+}
+
+fn push_dependency_table(changelog: &mut String, section: &Section) {
+    changelog.push_str("<table>\n<tr><th>Package</th><th>From</th><th>To</th></tr>\n");
+
+    for entry in &section.entries {
+        let (package, from, to) = match &entry.bump {
+            Some(bump) => (bump.package, bump.from, bump.to),
+            None => (entry.message.as_ref(), "", ""),
+        };
+        changelog.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            link(package, &entry.link),
+            escape(from),
+            escape(to)
+        ));
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!(
+            "<tr><td colspan=\"3\"><a href=\"{}\">{}</a></td></tr>\n",
+            escape(&more.link),
+            more.text()
+        ));
+    }
+
+    changelog.push_str("</table>\n");
+}
+
+/// `text` escaped and linked to `url`, or just escaped when there is nowhere
+/// to link.
+pub(super) fn link(text: &str, url: &str) -> String {
+    if url.is_empty() {
+        escape(text)
+    } else {
+        format!("<a href=\"{}\">{}</a>", escape(url), escape(text))
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,63 @@
+use super::{markdown, SectionKind, Sections};
+
+/// Whether an existing changelog follows keepachangelog.com, whose releases
+/// are `## [version] - date` headings under a single title.
+pub fn is_keep_a_changelog(changelog: &str) -> bool {
+    changelog.contains("keepachangelog.com")
+        || changelog.lines().any(|line| line.starts_with("## ["))
+}
+
+/// A release in the file's own style, with the `[YANKED]` or `[HOTFIX]`
+/// marker after the date where one was asked for.
+pub fn release(sections: &Sections, version: &str, date: &str, marker: Option<&str>) -> String {
+    let mut release = format!("## [{}] - {}", version, date);
+    if let Some(marker) = marker {
+        release.push_str(&format!(" {}", marker));
+    }
+    release.push_str("\n\n");
+
+    for section in &sections.sections {
+        release.push_str(&format!("### {}\n", title(section.kind)));
+        markdown::push_entries(&mut release, sections, &section.entries);
+        release.push('\n');
+    }
+
+    release
+}
+
+/// Puts the release above the newest one, below the title and intro, so
+/// the markers on earlier releases stay as they are.
+pub fn insert(changelog: &str, release: &str) -> String {
+    let mut offset = 0;
+    for line in changelog.split_inclusive('\n') {
+        // An Unreleased section stays on top.
+        if line.starts_with("## ") && !line.starts_with("## [Unreleased]") {
+            return format!(
+                "{}{}{}",
+                &changelog[..offset],
+                release,
+                &changelog[offset..]
+            );
+        }
+        offset += line.len();
+    }
+
+    let separator = if changelog.is_empty() || changelog.ends_with("\n\n") {
+        ""
+    } else if changelog.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    format!("{}{}{}", changelog, separator, release)
+}
+
+/// The section names keepachangelog.com uses.
+fn title(kind: SectionKind) -> &'static str {
+    match kind {
+        SectionKind::Security => "Security",
+        SectionKind::BugFixes => "Fixed",
+        SectionKind::Features => "Added",
+        SectionKind::Dependencies => "Changed",
+    }
+}
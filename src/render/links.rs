@@ -0,0 +1,368 @@
+use super::RenderOptions;
+use crate::model::{Commit, Project};
+use crate::remote::{parse_remote, Remote};
+use std::ops::Range;
+
+/// One kind of issue mention in a project's entries and the URL prefix its
+/// id is appended to.
+pub struct IssueLinks {
+    pub pattern: IssuePattern,
+    pub url: String,
+}
+
+pub enum IssuePattern {
+    /// A fixed prefix and a number: Gitea's `#123`, Azure Boards' `AB#123`.
+    Prefixed(&'static str),
+    /// A team key and a number, as in Linear's `ENG-421`. With `teams` only
+    /// those keys match, in any case so branch-style `eng-421` links too;
+    /// without, any uppercase key does.
+    Keyed { teams: Vec<String> },
+}
+
+impl IssueLinks {
+    /// If a mention starts `text`, returns its length and the id to link.
+    fn match_at(&self, text: &str) -> Option<(usize, String)> {
+        match &self.pattern {
+            IssuePattern::Prefixed(prefix) => {
+                let digits = digits(text.strip_prefix(prefix)?);
+                (digits > 0).then(|| {
+                    (
+                        prefix.len() + digits,
+                        text[prefix.len()..][..digits].to_string(),
+                    )
+                })
+            }
+            IssuePattern::Keyed { teams } => {
+                let (key, rest) = text.split_once('-')?;
+                let known = if teams.is_empty() {
+                    key.len() >= 2 && key.chars().all(|c| c.is_ascii_uppercase())
+                } else {
+                    teams.iter().any(|team| team.eq_ignore_ascii_case(key))
+                };
+                let digits = digits(rest);
+                if !known || digits == 0 {
+                    return None;
+                }
+
+                let end = key.len() + 1 + digits;
+                Some((end, text[..end].to_ascii_uppercase()))
+            }
+        }
+    }
+}
+
+fn digits(text: &str) -> usize {
+    text.find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len())
+}
+
+/// An issue mentioned in a message.
+pub struct Mention {
+    pub range: Range<usize>,
+    /// The issue as written, except that keys are uppercased: `#12`,
+    /// `AB#34` or `ENG-421`.
+    pub name: String,
+    /// Empty for a pattern with nowhere to link to.
+    pub url: String,
+}
+
+/// Every mention in `message` matched by `issue_links`. Mentions must start
+/// a word, so `x#3` or `SUBENG-4` are not.
+pub fn mentions(issue_links: &[IssueLinks], message: &str) -> Vec<Mention> {
+    let mut mentions = vec![];
+    let mut end = 0;
+    let mut previous: Option<char> = None;
+
+    for (i, c) in message.char_indices() {
+        let standalone = !previous.is_some_and(|p| p.is_alphanumeric());
+        previous = Some(c);
+
+        if i < end || !standalone {
+            continue;
+        }
+
+        let mention = issue_links.iter().find_map(|links| {
+            let (len, id) = links.match_at(&message[i..])?;
+            Some(Mention {
+                range: i..i + len,
+                name: match links.pattern {
+                    IssuePattern::Prefixed(_) => message[i..i + len].to_string(),
+                    IssuePattern::Keyed { .. } => id.clone(),
+                },
+                url: if links.url.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}{}", links.url, id)
+                },
+            })
+        });
+
+        if let Some(mention) = mention {
+            end = mention.range.end;
+            mentions.push(mention);
+        }
+    }
+
+    mentions
+}
+
+/// Renders `message` with every mention matched by `issue_links` linked.
+pub fn autolink(
+    issue_links: &[IssueLinks],
+    message: &str,
+    text: impl Fn(&str) -> String,
+    link: impl Fn(&str, &str) -> String,
+) -> String {
+    if issue_links.is_empty() {
+        return text(message);
+    }
+
+    let mut rendered = String::new();
+    let mut plain = 0;
+
+    for mention in mentions(issue_links, message) {
+        rendered.push_str(&text(&message[plain..mention.range.start]));
+        rendered.push_str(&link(&message[mention.range.clone()], &mention.url));
+        plain = mention.range.end;
+    }
+
+    rendered.push_str(&text(&message[plain..]));
+    rendered
+}
+
+/// The host's page for `commit`, or an empty string for a project without
+/// a remote, whose entries show the bare hash instead.
+pub fn commit_link(project: &Project, options: &RenderOptions, commit: &Commit) -> String {
+    if project.remote.is_empty() {
+        return String::new();
+    }
+
+    let remote = parse_remote(&project.remote);
+
+    if let Some(azure) = remote.as_ref().and_then(|remote| remote.azure_repo()) {
+        return azure.commit_url(&commit.hash);
+    }
+
+    if let Some(bitbucket) = remote
+        .as_ref()
+        .and_then(|remote| remote.bitbucket_repo(&options.hosts))
+    {
+        return bitbucket.commit_url(&commit.hash);
+    }
+
+    match remote {
+        Some(remote) if remote.is_gitea(&options.hosts) => {
+            format!("{}/commit/{}", remote.web_url(), commit.hash)
+        }
+        Some(remote) if options.hosts.configured.contains_key(&remote.host) => {
+            format!(
+                "{}{}/{}",
+                remote.web_url(),
+                commits_path(&remote, options),
+                commit.hash
+            )
+        }
+        Some(remote) => format!("{}/commits/{}", remote.web_url(), commit.hash),
+        None => format!("{}/commits/{}", project.remote, commit.hash),
+    }
+}
+
+/// The host's view of every change from before the project's oldest
+/// collected commit up to its newest. Those are the last and first commits
+/// as the log lists them, children before parents: author dates, which a
+/// rebase keeps, can put a commit before its parent.
+pub fn compare_link(project: &Project, options: &RenderOptions) -> String {
+    let (Some(newest), Some(oldest)) = (project.commits.first(), project.commits.last()) else {
+        return String::new();
+    };
+    let parent = format!("{}^", oldest.hash);
+    let base = options.release_tags.get(&project.name).unwrap_or(&parent);
+    let remote = parse_remote(&project.remote);
+
+    // Azure DevOps takes plain commit ids, so without a release tag there is
+    // no naming the oldest one's parent; the history up to the newest is
+    // shown instead.
+    if let Some(azure) = remote.as_ref().and_then(|remote| remote.azure_repo()) {
+        return match options.release_tags.get(&project.name) {
+            Some(tag) => azure.compare_url(tag, &newest.hash),
+            None => azure.commits_url(&newest.hash),
+        };
+    }
+
+    if let Some(bitbucket) = remote
+        .as_ref()
+        .and_then(|remote| remote.bitbucket_repo(&options.hosts))
+    {
+        return bitbucket.compare_url(base, &newest.hash);
+    }
+
+    match remote {
+        Some(remote) if remote.is_gitea(&options.hosts) => {
+            format!("{}/compare/{}...{}", remote.web_url(), base, newest.hash)
+        }
+        Some(remote) if options.hosts.configured.contains_key(&remote.host) => {
+            let prefix = if remote.is_gitlab(&options.hosts) {
+                "/-"
+            } else {
+                ""
+            };
+            format!(
+                "{}{}/compare/{}...{}",
+                remote.web_url(),
+                prefix,
+                base,
+                newest.hash
+            )
+        }
+        Some(remote) => format!("{}/compare/{}...{}", remote.web_url(), base, newest.hash),
+        None => format!("{}/compare/{}...{}", project.remote, base, newest.hash),
+    }
+}
+
+/// Where a host configured in `[hosts]` shows a commit; its remote may be
+/// an SSH URL, so links start from the web URL instead.
+fn commits_path(remote: &Remote, options: &RenderOptions) -> &'static str {
+    if remote.is_gitlab(&options.hosts) {
+        "/-/commit"
+    } else {
+        "/commits"
+    }
+}
+
+/// The mentions to link in a project: its host's issues or work items, and
+/// Linear issues when a workspace is configured.
+pub fn issue_links(project: &Project, options: &RenderOptions) -> Vec<IssueLinks> {
+    let mut issue_links = vec![];
+
+    if let Some(remote) = parse_remote(&project.remote) {
+        if let Some(azure) = remote.azure_repo() {
+            issue_links.push(IssueLinks {
+                pattern: IssuePattern::Prefixed("AB#"),
+                url: azure.work_item_url(),
+            });
+        } else if remote.is_gitea(&options.hosts) {
+            issue_links.push(IssueLinks {
+                pattern: IssuePattern::Prefixed("#"),
+                url: format!("{}/issues/", remote.web_url()),
+            });
+        }
+    }
+
+    if let Some(linear) = &options.linear {
+        issue_links.push(IssueLinks {
+            pattern: IssuePattern::Keyed {
+                teams: linear.teams.clone(),
+            },
+            url: format!("https://linear.app/{}/issue/", linear.workspace),
+        });
+    }
+
+    issue_links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{project_document, Format};
+    use std::path::PathBuf;
+
+    fn project(remote: &str) -> Project {
+        Project {
+            name: "alpha".to_string(),
+            path: PathBuf::new(),
+            commits: vec![Commit {
+                hash: "0123456789abcdef".to_string(),
+                message: "fix: crash on start".to_string(),
+                author_name: "Ana".into(),
+                author_email: "ana@example.com".into(),
+                date: "2024-05-01T10:00:00+00:00".to_string(),
+                committer_date: String::new(),
+                body: String::new(),
+                pull_request: None,
+                files: vec![],
+            }],
+            remote: remote.to_string(),
+            team: None,
+            description: None,
+            emoji: None,
+            homepage: None,
+            version: None,
+            anchor_group: None,
+        }
+    }
+
+    #[test]
+    fn commits_link_to_their_host() {
+        let project = project("https://github.com/acme/alpha");
+
+        assert_eq!(
+            commit_link(&project, &RenderOptions::default(), &project.commits[0]),
+            "https://github.com/acme/alpha/commits/0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn compare_links_span_the_history_as_logged() {
+        let mut project = project("https://github.com/acme/alpha");
+        // Rebased onto the first commit, the second keeps its older date.
+        let mut child = project.commits[0].clone();
+        child.hash = "fedcba9876543210".to_string();
+        child.date = "2024-04-01T10:00:00+00:00".to_string();
+        project.commits.insert(0, child);
+
+        assert_eq!(
+            compare_link(&project, &RenderOptions::default()),
+            "https://github.com/acme/alpha/compare/0123456789abcdef^...fedcba9876543210"
+        );
+    }
+
+    #[test]
+    fn links_start_from_the_web_url_of_any_remote() {
+        for remote in [
+            "https://github.com/acme/alpha.git",
+            "git@github.com:acme/alpha.git",
+        ] {
+            let project = project(remote);
+            let options = RenderOptions::default();
+
+            assert_eq!(
+                commit_link(&project, &options, &project.commits[0]),
+                "https://github.com/acme/alpha/commits/0123456789abcdef"
+            );
+            assert_eq!(
+                compare_link(&project, &options),
+                "https://github.com/acme/alpha/compare/0123456789abcdef^...0123456789abcdef"
+            );
+        }
+    }
+
+    #[test]
+    fn azure_compare_links_name_plain_commits() {
+        let project = project("git@ssh.dev.azure.com:v3/acme/tools/alpha");
+
+        assert_eq!(
+            compare_link(&project, &RenderOptions::default()),
+            "https://dev.azure.com/acme/tools/_git/alpha/commits?itemVersion=GC0123456789abcdef"
+        );
+
+        let options = RenderOptions {
+            release_tags: [("alpha".to_string(), "v1.2.0".to_string())].into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            compare_link(&project, &options),
+            "https://dev.azure.com/acme/tools/_git/alpha/branchCompare\
+             ?baseVersion=GTv1.2.0&targetVersion=GC0123456789abcdef"
+        );
+    }
+
+    #[test]
+    fn commits_without_a_remote_show_the_plain_hash() {
+        let project = project("");
+        let options = RenderOptions::default();
+
+        assert_eq!(commit_link(&project, &options, &project.commits[0]), "");
+        let changelog = project_document(Format::Markdown, &options, &project);
+        assert!(changelog.contains(" - crash on start #01234567\n"));
+    }
+}
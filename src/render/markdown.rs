@@ -0,0 +1,202 @@
+use super::{project_anchor, Entry, Groups, Section, SectionKind, Sections, Ticket};
+use crate::model::Project;
+
+pub fn header(date: &str) -> String {
+    format!("# Changelog for {}\n\n", date)
+}
+
+pub fn toc(groups: &Groups) -> String {
+    let mut toc = String::from("**Contents**\n\n");
+
+    for (group, projects) in groups {
+        let indent = match group {
+            Some(group) => {
+                toc.push_str(&format!("- {}\n", group));
+                "  "
+            }
+            None => "",
+        };
+        for project in projects {
+            toc.push_str(&format!(
+                "{}- [{}](#{})\n",
+                indent,
+                project.title(),
+                project_anchor(project)
+            ));
+        }
+    }
+
+    toc.push('\n');
+    toc
+}
+
+pub fn group(name: &str) -> String {
+    format!("# {}\n\n", name)
+}
+
+/// Every mentioned ticket with the commits that mention it, after the
+/// projects.
+pub fn tickets(tickets: &[Ticket]) -> String {
+    let mut changelog = String::from("## <a id=\"tickets\"></a>Tickets\n");
+
+    for ticket in tickets {
+        if ticket.url.is_empty() {
+            changelog.push_str(&format!(" - {}\n", ticket.name));
+        } else {
+            changelog.push_str(&format!(" - [{}]({})\n", ticket.name, ticket.url));
+        }
+        for mention in &ticket.mentions {
+            changelog.push_str(&format!(
+                "   - {}: {} {}\n",
+                mention.project.name,
+                mention.commit.message,
+                link(&format!("#{}", mention.short_hash()), &mention.link)
+            ));
+        }
+    }
+
+    changelog.push('\n');
+    changelog
+}
+
+pub fn project(project: &Project, sections: &Sections, collapsible: bool) -> String {
+    if collapsible {
+        return collapsible_project(project, sections);
+    }
+
+    let mut changelog = String::new();
+
+    changelog.push_str(&format!(
+        "## <a id=\"{}\"></a>{}\n",
+        project_anchor(project),
+        project.title()
+    ));
+    push_about(&mut changelog, project);
+    push_sections(&mut changelog, project, sections);
+
+    changelog.push('\n');
+    changelog
+}
+
+/// Every project's security fixes, ahead of the projects.
+pub fn security(sections: &Sections, section: &Section) -> String {
+    let mut changelog = format!(
+        "## <a id=\"security\"></a>{} {}\n",
+        section.kind.emoji(),
+        section.kind.title()
+    );
+    push_entries(&mut changelog, sections, &section.entries);
+
+    changelog.push('\n');
+    changelog
+}
+
+/// The project in a `<details>` block that GitHub shows folded, with the
+/// number of entries in the summary line.
+fn collapsible_project(project: &Project, sections: &Sections) -> String {
+    let entries: usize = sections.sections.iter().map(|s| s.total()).sum();
+    let mut changelog = format!(
+        "<details>\n<summary><a id=\"{}\"></a><strong>{}</strong> ({} {})</summary>\n\n",
+        project_anchor(project),
+        project.title(),
+        entries,
+        if entries == 1 { "change" } else { "changes" }
+    );
+    push_about(&mut changelog, project);
+    push_sections(&mut changelog, project, sections);
+
+    changelog.push_str("\n</details>\n\n");
+    changelog
+}
+
+/// The project's description and homepage as a paragraph under its heading.
+pub(super) fn push_about(changelog: &mut String, project: &Project) {
+    let mut about = vec![];
+    if let Some(description) = &project.description {
+        about.push(description.clone());
+    }
+    if let Some(homepage) = &project.homepage {
+        about.push(format!("[Homepage]({})", homepage));
+    }
+
+    if !about.is_empty() {
+        changelog.push_str(&format!("{}\n\n", about.join(" · ")));
+    }
+}
+
+// Anchors go inside the headings: an HTML line of its own would start an
+// HTML block that swallows the heading after it.
+pub(super) fn push_sections(changelog: &mut String, project: &Project, sections: &Sections) {
+    for section in &sections.sections {
+        changelog.push_str(&format!(
+            "### <a id=\"{}\"></a>{} {}\n",
+            section.anchor(project),
+            section.kind.emoji(),
+            section.kind.title()
+        ));
+
+        if section.kind == SectionKind::Dependencies {
+            push_dependency_table(changelog, &section.entries);
+        } else {
+            push_entries(changelog, sections, &section.entries);
+        }
+
+        // The note continues the list or table above it.
+        if let Some(more) = &section.more {
+            if section.kind == SectionKind::Dependencies {
+                changelog.push_str(&format!("| [{}]({}) | | |\n", more.text(), more.link));
+            } else {
+                changelog.push_str(&format!(" - [{}]({})\n", more.text(), more.link));
+            }
+        }
+    }
+}
+
+fn push_dependency_table(changelog: &mut String, entries: &[Entry]) {
+    changelog.push_str("| Package | From | To |\n| --- | --- | --- |\n");
+
+    for entry in entries {
+        // Entries put here by a PR label rather than a bump have no versions.
+        let (package, from, to) = match &entry.bump {
+            Some(bump) => (bump.package, bump.from, bump.to),
+            None => (entry.message.as_ref(), "", ""),
+        };
+        changelog.push_str(&format!(
+            "| {} | {} | {} |\n",
+            link(package, &entry.link),
+            from,
+            to
+        ));
+    }
+}
+
+pub(super) fn push_entries(changelog: &mut String, sections: &Sections, entries: &[Entry]) {
+    for entry in entries {
+        changelog.push_str(&format!(
+            " - {} {}\n",
+            sections.autolink(&entry.message, str::to_string, |text, url| format!(
+                "[{}]({})",
+                text, url
+            )),
+            link(&format!("#{}", entry.short_hash()), &entry.link)
+        ));
+
+        // A quote indented to the item's text stays inside the item.
+        if let Some(note) = &entry.breaking_change {
+            let note = format!("**Breaking change:** {}", note);
+            for line in note.lines() {
+                changelog.push_str(format!("   > {}", line).trim_end());
+                changelog.push('\n');
+            }
+        }
+    }
+}
+
+/// `text` linked to `url`, or left as is when there is nowhere to link.
+fn link(text: &str, url: &str) -> String {
+    if url.is_empty() {
+        text.to_string()
+    } else {
+        format!("[{}]({})", text, url)
+    }
+}
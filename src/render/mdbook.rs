@@ -0,0 +1,48 @@
+use super::{groups, markdown, sections, RenderOptions};
+use crate::model::ProjectList;
+use crate::project_file_name;
+
+/// Lays the changelog out as mdBook chapters: a `SUMMARY.md` fragment to
+/// paste into the book's summary, an intro chapter and one chapter per
+/// project, all relative to a `changelog/` directory under the book's `src`.
+pub fn book(date: &str, options: &RenderOptions, projects: &ProjectList) -> Vec<(String, String)> {
+    let title = format!("Changelog for {}", date);
+    let mut summary = format!("- [{}](changelog/README.md)\n", title);
+    let mut intro = format!("# {}\n\n", title);
+    let mut files = vec![];
+
+    for (group, projects) in groups(&projects.projects.iter().collect::<Vec<_>>()) {
+        // Groups become draft chapters, which mdBook lists without a page.
+        let indent = match group {
+            Some(group) => {
+                summary.push_str(&format!("  - [{}]()\n", group));
+                intro.push_str(&format!("## {}\n\n", group));
+                "    "
+            }
+            None => "  ",
+        };
+
+        for project in projects {
+            let file_name = format!("{}.md", project_file_name(&project.name));
+            summary.push_str(&format!(
+                "{}- [{}](changelog/{})\n",
+                indent, project.name, file_name
+            ));
+            intro.push_str(&format!(" - [{}]({})\n", project.name, file_name));
+
+            let sections = sections(project, options);
+            let mut chapter = format!("# {}\n\n", project.title());
+            markdown::push_about(&mut chapter, project);
+            markdown::push_sections(&mut chapter, project, &sections);
+            files.push((file_name, chapter));
+        }
+
+        if group.is_some() {
+            intro.push('\n');
+        }
+    }
+
+    files.insert(0, ("README.md".to_string(), intro));
+    files.insert(0, ("SUMMARY.md".to_string(), summary));
+    files
+}
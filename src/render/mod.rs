@@ -0,0 +1,1143 @@
+mod asciidoc;
+mod atom;
+mod front_matter;
+mod gha;
+mod html;
+mod keep_a_changelog;
+mod links;
+mod markdown;
+mod mdbook;
+mod normalize;
+mod pdf;
+mod rst;
+mod slack;
+mod text;
+mod theme;
+
+use crate::config::{ConventionConfig, LinearConfig};
+use crate::convention::{self, Subject};
+use crate::diagnostics::Diagnostic;
+use crate::json;
+use crate::model::{Bump, Commit, Project, ProjectList, Summary};
+use crate::remote::Hosts;
+use crate::yaml;
+use chrono::prelude::*;
+pub use front_matter::FrontMatter;
+pub use normalize::Normalize;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+pub use theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    AsciiDoc,
+    Rst,
+    Atom,
+    Pdf,
+    Html,
+    MdBook,
+    Slack,
+    Text,
+    Json,
+    Yaml,
+    Toml,
+    Gha,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "md" | "markdown" => Some(Format::Markdown),
+            "adoc" | "asciidoc" => Some(Format::AsciiDoc),
+            "rst" | "restructuredtext" => Some(Format::Rst),
+            "atom" => Some(Format::Atom),
+            "pdf" => Some(Format::Pdf),
+            "html" => Some(Format::Html),
+            "mdbook" => Some(Format::MdBook),
+            "slack" => Some(Format::Slack),
+            "text" | "txt" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "gha" => Some(Format::Gha),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown | Format::MdBook => "md",
+            Format::AsciiDoc => "adoc",
+            Format::Rst => "rst",
+            Format::Atom => "xml",
+            Format::Pdf => "pdf",
+            Format::Html => "html",
+            Format::Slack => "slack.txt",
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+            Format::Gha => "gha.txt",
+        }
+    }
+
+    /// Whether a document in this format is a single self-contained unit
+    /// that cannot simply be prepended to an existing changelog.
+    pub fn is_standalone(self) -> bool {
+        matches!(
+            self,
+            Format::Atom | Format::Pdf | Format::Html | Format::Json | Format::Yaml | Format::Toml
+        )
+    }
+}
+
+/// An annotation on the release a changelog is generated for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReleaseMarker {
+    /// Pulled after publishing, e.g. for a serious bug.
+    Yanked,
+    /// Cut outside the usual schedule to ship an urgent fix.
+    Hotfix,
+}
+
+impl ReleaseMarker {
+    /// The marker as keepachangelog.com writes it after the date.
+    pub fn text(self) -> &'static str {
+        match self {
+            ReleaseMarker::Yanked => "[YANKED]",
+            ReleaseMarker::Hotfix => "[HOTFIX]",
+        }
+    }
+}
+
+/// The headings a document arranges its projects under.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    /// Their `team`, when any project has one.
+    #[default]
+    Team,
+    /// Each ticket the commits mention, with every project's commits
+    /// mentioning it beneath; see `tickets`.
+    Ticket,
+}
+
+impl GroupBy {
+    pub fn parse(name: &str) -> Option<GroupBy> {
+        match name {
+            "team" => Some(GroupBy::Team),
+            "ticket" => Some(GroupBy::Ticket),
+            _ => None,
+        }
+    }
+}
+
+/// Settings that change how collected commits are turned into a document.
+#[derive(Debug, Default)]
+pub struct RenderOptions {
+    /// Emit ANSI colors; only the text format uses them.
+    pub color: bool,
+    pub theme: Theme,
+    /// List the bullet lines of a squash commit's body as separate entries.
+    pub expand_squash: bool,
+    /// Pull request labels that file a commit under a section; see
+    /// `Config::labels`.
+    pub labels: BTreeMap<String, SectionKind>,
+    /// Self-hosted instances, for building links.
+    pub hosts: Hosts,
+    pub linear: Option<LinearConfig>,
+    /// Start documents with links to each project, in formats with anchors.
+    pub toc: bool,
+    /// End documents with every mentioned ticket and the commits that
+    /// mention it, in the formats with a table of contents.
+    pub ticket_index: bool,
+    /// What projects are arranged under in whole documents.
+    pub group_by: GroupBy,
+    /// Fold each project into a `<details>` block in markdown.
+    pub collapsible: bool,
+    /// Start markdown documents with front matter for this generator.
+    pub front_matter: Option<FrontMatter>,
+    /// The date documents are generated for, in their headings and feed
+    /// timestamps.
+    pub generated_at: DateTime<FixedOffset>,
+    /// Longest entry message, in characters, before it is cut short.
+    pub max_entry_length: Option<usize>,
+    /// Most entries shown per section; the newest are kept.
+    pub max_per_section: Option<usize>,
+    /// Rewrites of entry text from `[normalize]`.
+    pub normalize: Normalize,
+    /// Shown after the date in document titles and release headings.
+    pub release_marker: Option<ReleaseMarker>,
+    /// Each project's previous release tag, where compare links start
+    /// once `[release]` is configured.
+    pub release_tags: BTreeMap<String, String>,
+    /// Each project's `[convention]`, for grouping the scopes it does not
+    /// know.
+    pub conventions: BTreeMap<String, ConventionConfig>,
+}
+
+/// A single changelog line, shared by every output format.
+pub struct Entry<'a> {
+    pub commit: &'a Commit,
+    pub message: Cow<'a, str>,
+    /// Empty for a project without a remote.
+    pub link: String,
+    /// Set for entries in the dependencies section.
+    pub bump: Option<Bump<'a>>,
+    /// The commit's `BREAKING CHANGE:` footer, rendered as a note under
+    /// the entry.
+    pub breaking_change: Option<String>,
+    /// The commit's conventional scope, by which entries are grouped within
+    /// a section; `unscoped` for one outside the project's `[convention]`
+    /// when it groups those.
+    pub scope: Option<&'a str>,
+}
+
+impl Entry<'_> {
+    /// Imported data can carry hashes shorter than this.
+    pub fn short_hash(&self) -> &str {
+        self.commit.hash.get(..8).unwrap_or(&self.commit.hash)
+    }
+
+    /// The link, or `#` and the short hash for a commit with nowhere to
+    /// link to, for formats that show one or the other.
+    pub fn reference(&self) -> Cow<'_, str> {
+        if self.link.is_empty() {
+            Cow::Owned(format!("#{}", self.short_hash()))
+        } else {
+            Cow::Borrowed(&self.link)
+        }
+    }
+}
+
+/// Cuts `text` to at most `max` characters, ending it with `…` when
+/// anything was cut. Counting characters rather than bytes keeps multi-byte
+/// text from being split inside a character.
+pub fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    match text.char_indices().nth(max) {
+        None => Cow::Borrowed(text),
+        Some(_) => {
+            let end = text
+                .char_indices()
+                .nth(max.saturating_sub(1))
+                .map_or(text.len(), |(i, _)| i);
+            Cow::Owned(format!("{}…", text[..end].trim_end()))
+        }
+    }
+}
+
+/// Changelog sections, in the order they are rendered. Configuration names
+/// them in lowercase, e.g. `bugfixes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+    Security,
+    BugFixes,
+    Features,
+    Dependencies,
+}
+
+impl SectionKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            SectionKind::Security => "Security",
+            SectionKind::BugFixes => "Bugfixes",
+            SectionKind::Features => "Features",
+            SectionKind::Dependencies => "Dependencies",
+        }
+    }
+
+    /// Emoji shortcode shown before the title where the format renders them.
+    pub fn emoji(self) -> &'static str {
+        match self {
+            SectionKind::Security => ":lock:",
+            SectionKind::BugFixes => ":bug:",
+            SectionKind::Features => ":rocket:",
+            SectionKind::Dependencies => ":package:",
+        }
+    }
+}
+
+/// A stable heading id: lowercase letters and digits, with every other run
+/// of characters turned into a single `-`, so emoji and renames of the
+/// displayed title do not break links.
+pub fn anchor(parts: &[&str]) -> String {
+    let mut anchor = String::new();
+
+    for c in parts.join("-").chars() {
+        if c.is_alphanumeric() {
+            anchor.extend(c.to_lowercase());
+        } else if !anchor.is_empty() && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+
+    anchor.trim_end_matches('-').to_string()
+}
+
+/// The id of a project's heading, qualified by its group for copies of the
+/// project that are rendered under several.
+pub fn project_anchor(project: &Project) -> String {
+    match &project.anchor_group {
+        Some(group) => anchor(&[group, &project.name]),
+        None => anchor(&[&project.name]),
+    }
+}
+
+pub struct Section<'a> {
+    pub kind: SectionKind,
+    pub entries: Vec<Entry<'a>>,
+    /// Set when `--max-per-section` left out the oldest entries.
+    pub more: Option<More>,
+}
+
+impl Section<'_> {
+    /// Section ids are qualified by project, as each project has its own.
+    pub fn anchor(&self, project: &Project) -> String {
+        anchor(&[&project_anchor(project), self.kind.title()])
+    }
+
+    /// Entries including those left out.
+    pub fn total(&self) -> usize {
+        self.entries.len() + self.more.as_ref().map_or(0, |more| more.count)
+    }
+}
+
+/// The entries left out of a section, and where to see them all.
+pub struct More {
+    pub count: usize,
+    /// The host's compare view over all of the project's commits.
+    pub link: String,
+}
+
+impl More {
+    pub fn text(&self) -> String {
+        format!("…and {} more", self.count)
+    }
+}
+
+/// The non-empty sections of a project, sorted by kind.
+#[derive(Default)]
+pub struct Sections<'a> {
+    pub sections: Vec<Section<'a>>,
+    /// How issue mentions in entries are linked for this project.
+    pub issue_links: Vec<IssueLinks>,
+}
+
+impl<'a> Sections<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Renders an entry message with issue mentions linked. `text` renders
+    /// the plain runs and `link(text, url)` the mentions, so each format can
+    /// escape and link in its own syntax.
+    pub fn autolink(
+        &self,
+        message: &str,
+        text: impl Fn(&str) -> String,
+        link: impl Fn(&str, &str) -> String,
+    ) -> String {
+        links::autolink(&self.issue_links, message, text, link)
+    }
+
+    fn push(&mut self, kind: SectionKind, entry: Entry<'a>) {
+        match self
+            .sections
+            .binary_search_by(|section| section.kind.cmp(&kind))
+        {
+            Ok(i) => self.sections[i].entries.push(entry),
+            Err(i) => self.sections.insert(
+                i,
+                Section {
+                    kind,
+                    entries: vec![entry],
+                    more: None,
+                },
+            ),
+        }
+    }
+}
+
+pub fn sections<'a>(project: &'a Project, options: &RenderOptions) -> Sections<'a> {
+    let mut sections = Sections {
+        issue_links: links::issue_links(project, options),
+        ..Sections::default()
+    };
+    // Repeated bumps of one package collapse into a single oldest-to-newest
+    // entry, so commits are taken newest first.
+    let mut bumps: BTreeMap<&str, (Bump, &Commit)> = BTreeMap::new();
+    let convention = options.conventions.get(&project.name);
+    let mut commits: Vec<&Commit> = project
+        .commits
+        .iter()
+        .filter(|commit| !commit.is_skipped())
+        .collect();
+    commits.sort_by(|a, b| Commit::newest_first(a, b));
+
+    for commit in commits {
+        // Security fixes go first whatever their type, bumps included.
+        if commit.is_security_fix() {
+            let subject = Subject::parse(&commit.message)
+                .map_or(commit.message.as_str(), |subject| subject.description);
+            sections.push(
+                SectionKind::Security,
+                Entry {
+                    commit,
+                    message: Cow::Borrowed(commit.trailer("Changelog").unwrap_or(subject)),
+                    link: links::commit_link(project, options, commit),
+                    bump: None,
+                    breaking_change: commit.breaking_change(),
+                    scope: scope(commit, convention),
+                },
+            );
+            continue;
+        }
+
+        if let Some(bump) = commit.dependency_bump() {
+            bumps
+                .entry(bump.package)
+                .and_modify(|(newest, _)| newest.from = bump.from)
+                .or_insert((bump, commit));
+            continue;
+        }
+
+        let labelled = commit
+            .pull_request
+            .as_ref()
+            .filter(|_| !options.labels.is_empty());
+
+        // Once labels are configured, a commit with a known pull request is
+        // filed by its labels alone, and left out if none of them is mapped.
+        if let Some(pull_request) = labelled {
+            let Some(&kind) = pull_request
+                .labels
+                .iter()
+                .find_map(|label| options.labels.get(label))
+            else {
+                continue;
+            };
+
+            let subject = Subject::parse(&commit.message)
+                .map_or(commit.message.as_str(), |subject| subject.description);
+            let message = commit.trailer("Changelog").unwrap_or(subject);
+            sections.push(
+                kind,
+                Entry {
+                    commit,
+                    message: Cow::Borrowed(message),
+                    link: links::commit_link(project, options, commit),
+                    bump: None,
+                    breaking_change: commit.breaking_change(),
+                    scope: scope(commit, convention),
+                },
+            );
+            continue;
+        }
+
+        // The same parser `lint-commits` checks subjects with, so any scope
+        // and a `!` marking a breaking change are taken.
+        if let Some(subject) = Subject::parse(&commit.message) {
+            let kind = match subject.kind {
+                "feat" => SectionKind::Features,
+                "fix" => SectionKind::BugFixes,
+                _ => continue,
+            };
+
+            if options.expand_squash {
+                let bullets = squash_bullets(commit);
+                if !bullets.is_empty() {
+                    for message in bullets {
+                        sections.push(
+                            kind,
+                            Entry {
+                                commit,
+                                message: Cow::Borrowed(message),
+                                link: links::commit_link(project, options, commit),
+                                bump: None,
+                                breaking_change: None,
+                                scope: scope(commit, convention),
+                            },
+                        );
+                    }
+                    continue;
+                }
+            }
+
+            // A `Changelog:` trailer lets the author word the entry for
+            // readers instead of reusing the terse subject.
+            let message = commit.trailer("Changelog").unwrap_or(subject.description);
+            sections.push(
+                kind,
+                Entry {
+                    commit,
+                    message: Cow::Borrowed(message),
+                    link: links::commit_link(project, options, commit),
+                    bump: None,
+                    breaking_change: commit.breaking_change(),
+                    scope: scope(commit, convention),
+                },
+            );
+        }
+    }
+
+    for (package, (bump, commit)) in bumps {
+        let message = if bump.from.is_empty() {
+            format!("{} → {}", package, bump.to)
+        } else {
+            format!("{} {} → {}", package, bump.from, bump.to)
+        };
+
+        sections.push(
+            SectionKind::Dependencies,
+            Entry {
+                commit,
+                message: Cow::Owned(message),
+                link: links::commit_link(project, options, commit),
+                bump: Some(bump),
+                breaking_change: None,
+                scope: None,
+            },
+        );
+    }
+
+    for entry in sections.sections.iter_mut().flat_map(|s| &mut s.entries) {
+        if entry.bump.is_none() {
+            if let Some(message) = options.normalize.apply(&entry.message) {
+                entry.message = Cow::Owned(message);
+            }
+        }
+    }
+
+    if let Some(max) = options.max_per_section {
+        let link = links::compare_link(project, options);
+        // Every security fix is listed.
+        for section in &mut sections.sections {
+            if section.kind != SectionKind::Security && section.entries.len() > max {
+                section.more = Some(More {
+                    count: section.entries.len() - max,
+                    link: link.clone(),
+                });
+                section.entries.truncate(max);
+            }
+        }
+    }
+
+    if let Some(max) = options.max_entry_length {
+        for entry in sections.sections.iter_mut().flat_map(|s| &mut s.entries) {
+            if let Cow::Owned(message) = truncate(&entry.message, max) {
+                entry.message = Cow::Owned(message);
+            }
+        }
+    }
+
+    // Entries without a scope first, then each scope's, newest first
+    // within each.
+    for section in &mut sections.sections {
+        section.entries.sort_by_key(|entry| entry.scope);
+    }
+
+    sections
+}
+
+/// The scope an entry for `commit` is grouped under.
+fn scope<'a>(commit: &'a Commit, convention: Option<&ConventionConfig>) -> Option<&'a str> {
+    let scope = commit.scope()?;
+    match convention {
+        Some(convention)
+            if convention.group_unknown_scopes
+                && convention::unknown_scope(&commit.message, convention).is_some() =>
+        {
+            Some("unscoped")
+        }
+        _ => Some(scope),
+    }
+}
+
+/// The `* ` and `- ` lines of a squash merge body, which list the squashed
+/// commits. Their own conventional prefixes are dropped since the entries go
+/// under the squash commit's type.
+fn squash_bullets(commit: &Commit) -> Vec<&str> {
+    commit
+        .body
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            line.strip_prefix("* ").or_else(|| line.strip_prefix("- "))
+        })
+        .map(|bullet| {
+            let bullet = bullet.trim();
+            Subject::parse(bullet).map_or(bullet, |subject| subject.description)
+        })
+        .filter(|bullet| !bullet.is_empty())
+        .collect()
+}
+
+fn header(format: Format, options: &RenderOptions, now: &DateTime<FixedOffset>) -> String {
+    let mut date = now.format("%Y-%m-%d").to_string();
+    if let Some(marker) = options.release_marker {
+        date = format!("{} {}", date, marker.text());
+    }
+
+    match format {
+        Format::Markdown | Format::MdBook => markdown::header(&date),
+        Format::AsciiDoc => asciidoc::header(&date),
+        Format::Rst => rst::header(&date),
+        Format::Atom => atom::header(&now.to_rfc3339_opts(SecondsFormat::Secs, false)),
+        Format::Html => html::header(&date),
+        Format::Slack => slack::header(&date),
+        Format::Text => text::header(&date, options),
+        Format::Gha => String::new(),
+        Format::Pdf | Format::Json | Format::Yaml | Format::Toml => {
+            unreachable!("{:?} documents are rendered as a whole", format)
+        }
+    }
+}
+
+fn project(format: Format, options: &RenderOptions, project: &Project) -> String {
+    let mut sections = sections(project, options);
+    if lifts_security(format) {
+        sections
+            .sections
+            .retain(|section| section.kind != SectionKind::Security);
+    }
+
+    match format {
+        Format::Markdown | Format::MdBook => {
+            markdown::project(project, &sections, options.collapsible)
+        }
+        Format::AsciiDoc => asciidoc::project(project, &sections),
+        Format::Rst => rst::project(project, &sections),
+        Format::Atom => atom::project(project, &sections),
+        Format::Html => html::project(project, &sections),
+        Format::Slack => slack::project(project, &sections),
+        Format::Text => text::project(project, &sections, options),
+        Format::Gha => gha::project(project, &sections),
+        Format::Pdf | Format::Json | Format::Yaml | Format::Toml => {
+            unreachable!("{:?} documents are rendered as a whole", format)
+        }
+    }
+}
+
+/// Formats that gather every project's security fixes in one section at
+/// the top of the document. The others list them first in each project.
+fn lifts_security(format: Format) -> bool {
+    matches!(
+        format,
+        Format::Markdown
+            | Format::AsciiDoc
+            | Format::Rst
+            | Format::Html
+            | Format::Slack
+            | Format::Text
+    )
+}
+
+/// The security fixes of all projects, each after its project's name when
+/// there are several projects.
+fn security_section<'a>(options: &RenderOptions, projects: &[&'a Project]) -> Option<Section<'a>> {
+    let entries: Vec<Entry> = projects
+        .iter()
+        .flat_map(|project| {
+            let sections = sections(project, options);
+            let security = sections
+                .sections
+                .into_iter()
+                .find(|section| section.kind == SectionKind::Security);
+            security
+                .into_iter()
+                .flat_map(|section| section.entries)
+                .map(|mut entry| {
+                    if projects.len() > 1 {
+                        entry.message = Cow::Owned(format!("{}: {}", project.name, entry.message));
+                    }
+                    entry
+                })
+        })
+        .collect();
+
+    (!entries.is_empty()).then_some(Section {
+        kind: SectionKind::Security,
+        entries,
+        more: None,
+    })
+}
+
+fn security(format: Format, options: &RenderOptions, section: &Section) -> String {
+    // Issue mentions are linked per project, which a mixed section is not.
+    let sections = Sections::default();
+
+    match format {
+        Format::Markdown => markdown::security(&sections, section),
+        Format::AsciiDoc => asciidoc::security(&sections, section),
+        Format::Rst => rst::security(&sections, section),
+        Format::Html => html::security(&sections, section),
+        Format::Slack => slack::security(&sections, section),
+        Format::Text => text::security(section, options),
+        _ => unreachable!("{:?} lists security fixes per project", format),
+    }
+}
+
+fn group(format: Format, options: &RenderOptions, name: &str) -> String {
+    match format {
+        Format::Markdown | Format::MdBook => markdown::group(name),
+        Format::AsciiDoc => asciidoc::group(name),
+        Format::Rst => rst::group(name),
+        Format::Html => html::group(name),
+        Format::Slack => slack::group(name),
+        Format::Text => text::group(name, options),
+        // Feed entries and workflow log groups cannot nest.
+        Format::Atom | Format::Gha => String::new(),
+        Format::Pdf | Format::Json | Format::Yaml | Format::Toml => {
+            unreachable!("{:?} documents are rendered as a whole", format)
+        }
+    }
+}
+
+/// Projects under their group headings, as returned by `groups`.
+type Groups<'a> = Vec<(Option<&'a str>, Vec<&'a Project>)>;
+
+fn toc(format: Format, groups: &Groups) -> String {
+    match format {
+        Format::Markdown | Format::MdBook => markdown::toc(groups),
+        Format::AsciiDoc => asciidoc::toc(groups),
+        Format::Rst => rst::toc(groups),
+        Format::Html => html::toc(groups),
+        _ => String::new(),
+    }
+}
+
+/// A ticket mentioned by commits in any of the projects.
+pub struct Ticket<'a> {
+    /// As in `links::Mention`, with a project-local `#12` qualified by its
+    /// project, as in `web#12`.
+    pub name: String,
+    /// Empty when nothing says where the ticket lives.
+    pub url: String,
+    pub mentions: Vec<TicketMention<'a>>,
+}
+
+pub struct TicketMention<'a> {
+    pub project: &'a Project,
+    pub commit: &'a Commit,
+    /// The commit's link, empty for a project without a remote.
+    pub link: String,
+}
+
+impl TicketMention<'_> {
+    pub fn short_hash(&self) -> &str {
+        self.commit.hash.get(..8).unwrap_or(&self.commit.hash)
+    }
+}
+
+/// Every ticket the projects' commits mention in their subject or body, by
+/// name. Besides the issues each project links, Jira-style keys like
+/// `OPS-12` count too.
+pub fn tickets<'a>(options: &RenderOptions, projects: &[&'a Project]) -> Vec<Ticket<'a>> {
+    let mut tickets: BTreeMap<String, Ticket> = BTreeMap::new();
+
+    for project in projects {
+        let mut issue_links = links::issue_links(project, options);
+        let keyed = issue_links
+            .iter()
+            .any(|links| matches!(links.pattern, links::IssuePattern::Keyed { .. }));
+        if !keyed {
+            issue_links.push(IssueLinks {
+                pattern: links::IssuePattern::Keyed { teams: vec![] },
+                url: String::new(),
+            });
+        }
+
+        for commit in project.commits.iter().filter(|c| !c.is_skipped()) {
+            let mut mentions = links::mentions(&issue_links, &commit.message);
+            mentions.extend(links::mentions(&issue_links, &commit.body));
+
+            let mut names: Vec<String> = mentions
+                .into_iter()
+                .map(|mention| {
+                    let name = if mention.name.starts_with('#') {
+                        format!("{}{}", project.name, mention.name)
+                    } else {
+                        mention.name
+                    };
+                    tickets.entry(name.clone()).or_insert_with(|| Ticket {
+                        name: name.clone(),
+                        url: mention.url,
+                        mentions: vec![],
+                    });
+                    name
+                })
+                .collect();
+            // A commit is listed once under a ticket however often it
+            // mentions it.
+            names.sort();
+            names.dedup();
+
+            for name in names {
+                if let Some(ticket) = tickets.get_mut(&name) {
+                    ticket.mentions.push(TicketMention {
+                        project,
+                        commit,
+                        link: links::commit_link(project, options, commit),
+                    });
+                }
+            }
+        }
+    }
+
+    tickets.into_values().collect()
+}
+
+/// The projects with each ticket's commits, by ticket, and those with the
+/// commits that mention none last under "Other".
+fn ticket_groups(options: &RenderOptions, projects: &[&Project]) -> Vec<(String, Vec<Project>)> {
+    let tickets = tickets(options, projects);
+    let mut mentioned = HashSet::new();
+    let mut groups = vec![];
+
+    for ticket in &tickets {
+        let mut ticket_projects: Vec<Project> = vec![];
+        for mention in &ticket.mentions {
+            mentioned.insert((&mention.project.name, &mention.commit.hash));
+            let commit = mention.commit.clone();
+            match ticket_projects
+                .iter_mut()
+                .find(|project| project.name == mention.project.name)
+            {
+                Some(project) => project.commits.push(commit),
+                None => {
+                    let mut copy = mention.project.with_commits(vec![commit]);
+                    copy.anchor_group = Some(ticket.name.clone());
+                    ticket_projects.push(copy);
+                }
+            }
+        }
+        groups.push((ticket.name.clone(), ticket_projects));
+    }
+
+    let other: Vec<Project> = projects
+        .iter()
+        .map(|project| {
+            project.with_commits(
+                project
+                    .commits
+                    .iter()
+                    .filter(|commit| !mentioned.contains(&(&project.name, &commit.hash)))
+                    .cloned()
+                    .collect(),
+            )
+        })
+        .filter(|project| !project.commits.is_empty())
+        .map(|mut project| {
+            project.anchor_group = Some("Other".to_string());
+            project
+        })
+        .collect();
+    if !other.is_empty() {
+        groups.push(("Other".to_string(), other));
+    }
+
+    groups
+}
+
+fn ticket_index(format: Format, tickets: &[Ticket]) -> String {
+    if tickets.is_empty() {
+        return String::new();
+    }
+
+    match format {
+        Format::Markdown | Format::MdBook => markdown::tickets(tickets),
+        Format::AsciiDoc => asciidoc::tickets(tickets),
+        Format::Rst => rst::tickets(tickets),
+        Format::Html => html::tickets(tickets),
+        _ => String::new(),
+    }
+}
+
+/// Splits projects by `team`, in team order, with the projects that have
+/// none last under "Other". Without any teams there is a single group with
+/// no heading.
+fn groups<'a>(projects: &[&'a Project]) -> Groups<'a> {
+    if projects.iter().all(|project| project.team.is_none()) {
+        return vec![(None, projects.to_vec())];
+    }
+
+    let mut teams: BTreeMap<&str, Vec<&Project>> = BTreeMap::new();
+    let mut other = vec![];
+
+    for project in projects {
+        match &project.team {
+            Some(team) => teams.entry(team).or_default().push(*project),
+            None => other.push(*project),
+        }
+    }
+
+    let mut groups: Vec<_> = teams
+        .into_iter()
+        .map(|(team, projects)| (Some(team), projects))
+        .collect();
+    if !other.is_empty() {
+        groups.push((Some("Other"), other));
+    }
+    groups
+}
+
+/// Closes whatever `header` opened; only needed by formats with a wrapping
+/// element.
+fn footer(format: Format) -> String {
+    match format {
+        Format::Atom => atom::footer(),
+        Format::Html => html::footer(),
+        _ => String::new(),
+    }
+}
+
+pub fn document(format: Format, options: &RenderOptions, projects: &ProjectList) -> String {
+    render_projects(
+        format,
+        options,
+        &projects.projects.iter().collect::<Vec<_>>(),
+        projects.diagnostics.as_slice(),
+    )
+}
+
+pub fn project_document(format: Format, options: &RenderOptions, project: &Project) -> String {
+    render_projects(format, options, &[project], &[])
+}
+
+/// Returns the mdBook chapter files as `(file name, contents)` pairs.
+pub fn mdbook(options: &RenderOptions, projects: &ProjectList) -> Vec<(String, String)> {
+    mdbook::book(
+        &options.generated_at.format("%Y-%m-%d").to_string(),
+        options,
+        projects,
+    )
+}
+
+/// Renders the projects as a bare XHTML fragment for Confluence's storage
+/// format, without the document wrapper of the html format.
+pub fn storage(options: &RenderOptions, projects: &ProjectList) -> String {
+    html_fragment(options, &projects.projects.iter().collect::<Vec<_>>())
+}
+
+pub fn html_fragment(options: &RenderOptions, projects: &[&Project]) -> String {
+    projects
+        .iter()
+        .map(|project| html::project(project, &sections(project, options)))
+        .collect()
+}
+
+/// The project's changes as a release of a keepachangelog.com file, named
+/// `version` and dated like documents.
+pub fn keep_a_changelog_release(
+    options: &RenderOptions,
+    project: &Project,
+    version: &str,
+) -> String {
+    keep_a_changelog::release(
+        &sections(project, options),
+        version,
+        &options.generated_at.format("%Y-%m-%d").to_string(),
+        options.release_marker.map(ReleaseMarker::text),
+    )
+}
+
+pub use html::escape as escape_html;
+pub use keep_a_changelog::{insert as insert_release, is_keep_a_changelog};
+pub use links::{commit_link, mentions, IssueLinks, IssuePattern};
+
+/// The collected data itself, as exported by the data formats. It has the
+/// same shape as `ProjectList` so exports can be loaded back, with its
+/// counts under `meta`.
+#[derive(Serialize)]
+struct Data<'a> {
+    meta: Summary,
+    projects: &'a [&'a Project],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    diagnostics: &'a [Diagnostic],
+}
+
+/// `diagnostics` are only part of the data formats.
+fn render_projects(
+    format: Format,
+    options: &RenderOptions,
+    projects: &[&Project],
+    diagnostics: &[Diagnostic],
+) -> String {
+    let now = options.generated_at;
+    let data = Data {
+        meta: Summary::new(projects.iter().copied()),
+        projects,
+        diagnostics,
+    };
+
+    match format {
+        Format::Json => return json::to_string_pretty(&data).expect("Failed to serialize JSON"),
+        Format::Yaml => return yaml::to_string(&data).expect("Failed to serialize YAML"),
+        Format::Toml => return toml::to_string_pretty(&data).expect("Failed to serialize TOML"),
+        _ => {}
+    }
+
+    // Grouped by ticket, each project appears under every ticket its
+    // commits mention, as a copy holding just those commits.
+    let by_ticket = match options.group_by {
+        GroupBy::Ticket => ticket_groups(options, projects),
+        GroupBy::Team => vec![],
+    };
+    let groups = match options.group_by {
+        GroupBy::Ticket => by_ticket
+            .iter()
+            .map(|(ticket, projects)| (Some(ticket.as_str()), projects.iter().collect()))
+            .collect(),
+        GroupBy::Team => groups(projects),
+    };
+
+    if format == Format::Pdf {
+        let projects: Vec<_> = groups
+            .into_iter()
+            .flat_map(|(group, projects)| {
+                projects
+                    .into_iter()
+                    .map(move |project| (group, project, sections(project, options)))
+            })
+            .collect();
+        return pdf::document(&now.format("%Y-%m-%d").to_string(), &projects);
+    }
+
+    let mut changelog = String::new();
+    if let (Format::Markdown, Some(kind)) = (format, options.front_matter) {
+        let title = format!("Changelog for {}", now.format("%Y-%m-%d"));
+        changelog.push_str(&front_matter::render(kind, &title, &now, projects));
+    }
+
+    changelog.push_str(&header(format, options, &now));
+
+    if lifts_security(format) {
+        if let Some(section) = security_section(options, projects) {
+            changelog.push_str(&security(format, options, &section));
+        }
+    }
+
+    if options.toc {
+        changelog.push_str(&toc(format, &groups));
+    }
+
+    for (name, projects) in groups {
+        if let Some(name) = name {
+            changelog.push_str(&group(format, options, name));
+        }
+
+        for project in projects {
+            changelog.push_str(&self::project(format, options, project));
+        }
+    }
+
+    if options.ticket_index {
+        changelog.push_str(&ticket_index(format, &tickets(options, projects)));
+    }
+
+    changelog.push_str(&footer(format));
+    changelog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn project(messages: &[(&str, &str)]) -> Project {
+        let commits = messages
+            .iter()
+            .enumerate()
+            .map(|(i, (message, body))| Commit {
+                hash: format!("{:016x}", i + 1),
+                message: message.to_string(),
+                author_name: "Ana".into(),
+                author_email: "ana@example.com".into(),
+                date: format!("2024-05-{:02}T10:00:00+00:00", 20 - i),
+                committer_date: String::new(),
+                body: body.to_string(),
+                pull_request: None,
+                files: vec![],
+            })
+            .collect();
+
+        Project {
+            name: "alpha".to_string(),
+            path: PathBuf::new(),
+            commits,
+            remote: String::new(),
+            team: None,
+            description: None,
+            emoji: None,
+            homepage: None,
+            version: None,
+            anchor_group: None,
+        }
+    }
+
+    fn entries<'a>(sections: &'a Sections, kind: SectionKind) -> Vec<&'a str> {
+        sections
+            .sections
+            .iter()
+            .filter(|section| section.kind == kind)
+            .flat_map(|section| &section.entries)
+            .map(|entry| entry.message.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn subjects_are_filed_as_lint_commits_parses_them() {
+        let project = project(&[
+            ("feat(api): list users", ""),
+            ("fix: handle names like foo: bar", ""),
+            ("fix(db)!: drop the legacy table", ""),
+            ("docs(api): explain paging", ""),
+            ("Fix: not conventional", ""),
+        ]);
+        let sections = sections(&project, &RenderOptions::default());
+
+        assert_eq!(entries(&sections, SectionKind::Features), ["list users"]);
+        assert_eq!(
+            entries(&sections, SectionKind::BugFixes),
+            ["handle names like foo: bar", "drop the legacy table"]
+        );
+    }
+
+    #[test]
+    fn scoped_breaking_changes_keep_their_footer() {
+        let project = project(&[(
+            "feat(api)!: page every list",
+            "Lists were unbounded.\n\n\
+             BREAKING CHANGE: list endpoints return at most 100 items.\n\
+             Follow the `next` link for the rest:\n\
+             \n\
+             \x20   GET /users?page=2\n\
+             Reviewed-by: Bob",
+        )]);
+        let options = RenderOptions::default();
+        let sections = sections(&project, &options);
+        let entry = &sections.sections[0].entries[0];
+
+        assert_eq!(entry.message, "page every list");
+        assert_eq!(
+            entry.breaking_change.as_deref(),
+            Some(
+                "list endpoints return at most 100 items.\n\
+                 Follow the `next` link for the rest:\n\
+                 \n\
+                 \x20   GET /users?page=2"
+            )
+        );
+        assert!(
+            project_document(Format::Markdown, &options, &project).contains(
+                " - page every list #00000000\n\
+             \x20  > **Breaking change:** list endpoints return at most 100 items.\n\
+             \x20  > Follow the `next` link for the rest:\n\
+             \x20  >\n\
+             \x20  >     GET /users?page=2\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use crate::config::{LintConfig, NormalizeConfig};
+use crate::regex::{self, Regex};
+
+/// Rewrites of entry text, resolved from the `[normalize]` config, so that
+/// subjects written in different habits read alike in the changelog.
+#[derive(Debug, Default)]
+pub struct Normalize {
+    rules: Vec<(Regex, String)>,
+    capitalize: bool,
+    strip_trailing_period: bool,
+}
+
+impl Normalize {
+    pub fn new(config: &NormalizeConfig) -> Result<Normalize, String> {
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.find)
+                    .map(|regex| (regex, rule.replace.clone()))
+                    .map_err(|e| format!("Invalid [[normalize.rules]] find {:?}: {}", rule.find, e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Normalize {
+            rules,
+            capitalize: config.capitalize,
+            strip_trailing_period: config.strip_trailing_period,
+        })
+    }
+
+    /// The rewritten message, or `None` when nothing changed.
+    pub fn apply(&self, message: &str) -> Option<String> {
+        let mut text = message.to_string();
+
+        for (regex, replacement) in &self.rules {
+            text = regex.replace_all(&text, replacement);
+        }
+        // Rules that strip a prefix tend to leave the separator's space.
+        if !self.rules.is_empty() {
+            text = text.trim().to_string();
+        }
+
+        if self.strip_trailing_period && text.ends_with('.') && !text.ends_with("..") {
+            text.pop();
+        }
+
+        if self.capitalize {
+            let mut chars = text.chars();
+            if let Some(first) = chars.next() {
+                text = first.to_uppercase().chain(chars).collect();
+            }
+        }
+
+        (text != message).then_some(text)
+    }
+
+    /// Adds what `--fix` corrects: the misspellings from `[lint]`, replaced
+    /// as whole words, and lowercase first letters.
+    pub fn with_fixes(mut self, config: &LintConfig) -> Normalize {
+        for (wrong, right) in &config.corrections {
+            self.rules.push((
+                Regex::new(&format!(r"\b{}\b", regex::escape(wrong)))
+                    .expect("an escaped word is a valid pattern"),
+                right.replace('$', "$$"),
+            ));
+        }
+        self.capitalize = true;
+        self
+    }
+}
@@ -0,0 +1,213 @@
+use super::Sections;
+use crate::model::Project;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+
+enum Style {
+    Title,
+    Group,
+    Project,
+    Section,
+    Entry,
+}
+
+impl Style {
+    fn font(&self) -> &'static str {
+        match self {
+            Style::Entry => "F1",
+            _ => "F2",
+        }
+    }
+
+    fn size(&self) -> f32 {
+        match self {
+            Style::Title => 18.0,
+            Style::Group => 16.0,
+            Style::Project => 14.0,
+            Style::Section => 12.0,
+            Style::Entry => 10.0,
+        }
+    }
+}
+
+/// Lays the changelog out as plain text lines in the standard Helvetica
+/// fonts, which every PDF reader ships, so no font embedding is needed.
+/// Each project comes with the group it is listed under, whose heading is
+/// printed where the group changes.
+pub fn document(date: &str, projects: &[(Option<&str>, &Project, Sections)]) -> String {
+    let mut lines = vec![(Style::Title, format!("Changelog for {}", date))];
+    let mut current_group = None;
+
+    for (group, project, sections) in projects {
+        if *group != current_group {
+            current_group = *group;
+            if let Some(group) = group {
+                lines.push((Style::Group, group.to_string()));
+            }
+        }
+
+        lines.push((Style::Project, project.title()));
+
+        if project.has_about() {
+            let about: Vec<&str> = [&project.description, &project.homepage]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            lines.push((Style::Entry, about.join(" - ")));
+        }
+
+        for section in &sections.sections {
+            lines.push((Style::Section, section.kind.title().to_string()));
+            for entry in &section.entries {
+                lines.push((
+                    Style::Entry,
+                    format!("- {} (#{})", entry.message, entry.short_hash()),
+                ));
+                if let Some(note) = &entry.breaking_change {
+                    let note = format!("Breaking change: {}", note);
+                    for line in note.lines() {
+                        lines.push((Style::Entry, format!("    {}", line)));
+                    }
+                }
+            }
+            if let Some(more) = &section.more {
+                lines.push((Style::Entry, more.text()));
+            }
+        }
+    }
+
+    write_pdf(&paginate(lines))
+}
+
+fn paginate(lines: Vec<(Style, String)>) -> Vec<String> {
+    let mut pages = vec![];
+    let mut content = String::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    for (style, text) in lines {
+        let size = style.size();
+
+        for line in wrap(&text, size) {
+            let leading = size * 1.5;
+            if y - leading < MARGIN {
+                pages.push(std::mem::take(&mut content));
+                y = PAGE_HEIGHT - MARGIN;
+            }
+            y -= leading;
+
+            content.push_str(&format!(
+                "BT /{} {} Tf {} {} Td ({}) Tj ET\n",
+                style.font(),
+                size,
+                MARGIN,
+                y,
+                escape(&line)
+            ));
+        }
+    }
+
+    pages.push(content);
+    pages
+}
+
+// Helvetica averages roughly half an em per character; good enough to keep
+// lines inside the margins without shipping font metrics.
+fn wrap(text: &str, size: f32) -> Vec<String> {
+    let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN) / (size * 0.5)) as usize;
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split(' ') {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+            line.push_str("  ");
+        } else if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+
+    lines.push(line);
+    lines
+}
+
+// Text is written in WinAnsiEncoding, so Latin-1 characters are emitted as
+// octal escapes and anything beyond it degrades to `?`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            _ => escaped.push('?'),
+        }
+    }
+
+    escaped
+}
+
+fn write_pdf(pages: &[String]) -> String {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+
+    let mut kids = vec![];
+    for content in pages {
+        let page_id = objects.len() + 1;
+        kids.push(format!("{} 0 R", page_id));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            page_id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        kids.len()
+    );
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+
+    pdf
+}
@@ -0,0 +1,190 @@
+use super::{project_anchor, Groups, Section, Sections, Ticket};
+use crate::model::Project;
+
+pub fn header(date: &str) -> String {
+    let title = format!("Changelog for {}", date);
+    format!("{}\n{}\n\n", title, underline(&title, '='))
+}
+
+/// Links go through the labels in front of each project heading.
+pub fn toc(groups: &Groups) -> String {
+    let mut toc = String::from("**Contents**\n\n");
+
+    for (group, projects) in groups {
+        let indent = match group {
+            Some(group) => {
+                toc.push_str(&format!("- {}\n\n", escape(group)));
+                "  "
+            }
+            None => "",
+        };
+        for project in projects {
+            toc.push_str(&format!(
+                "{}- `{} <{}_>`_\n",
+                indent,
+                escape(&project.title()),
+                project_anchor(project)
+            ));
+        }
+        toc.push('\n');
+    }
+
+    toc
+}
+
+/// Underlined with `*`, which first appears between the title's `=` and
+/// the projects' `-` and so ranks between them.
+pub fn group(name: &str) -> String {
+    let name = escape(name);
+    format!("{}\n{}\n\n", name, underline(&name, '*'))
+}
+
+/// Every mentioned ticket with the commits that mention it, after the
+/// projects. The nested list needs blank lines around it.
+pub fn tickets(tickets: &[Ticket]) -> String {
+    let mut changelog = String::from(".. _tickets:\n\nTickets\n-------\n\n");
+
+    for ticket in tickets {
+        if ticket.url.is_empty() {
+            changelog.push_str(&format!("- {}\n\n", escape(&ticket.name)));
+        } else {
+            changelog.push_str(&format!(
+                "- `{} <{}>`__\n\n",
+                escape(&ticket.name),
+                ticket.url
+            ));
+        }
+        for mention in &ticket.mentions {
+            changelog.push_str(&format!(
+                "  - {}: {} {}\n",
+                escape(&mention.project.name),
+                escape(&mention.commit.message),
+                link(&format!("#{}", mention.short_hash()), &mention.link)
+            ));
+        }
+        changelog.push('\n');
+    }
+
+    changelog
+}
+
+/// Every project's security fixes, ahead of the projects.
+pub fn security(sections: &Sections, section: &Section) -> String {
+    let title = section.kind.title();
+    let mut changelog = format!(".. _security:\n\n{}\n{}\n\n", title, underline(title, '-'));
+    push_entries(&mut changelog, sections, section);
+    changelog
+}
+
+pub fn project(project: &Project, sections: &Sections) -> String {
+    let mut changelog = String::new();
+
+    let name = escape(&project.title());
+    changelog.push_str(&format!(
+        ".. _{}:\n\n{}\n{}\n\n",
+        project_anchor(project),
+        name,
+        underline(&name, '-')
+    ));
+
+    if project.has_about() {
+        let mut about = vec![];
+        if let Some(description) = &project.description {
+            about.push(escape(description));
+        }
+        if let Some(homepage) = &project.homepage {
+            about.push(format!("`Homepage <{}>`__", homepage));
+        }
+        changelog.push_str(&format!("{}\n\n", about.join(" · ")));
+    }
+
+    for section in &sections.sections {
+        let title = section.kind.title();
+        changelog.push_str(&format!(
+            ".. _{}:\n\n{}\n{}\n\n",
+            section.anchor(project),
+            title,
+            underline(title, '~')
+        ));
+        push_entries(&mut changelog, sections, section);
+    }
+
+    changelog
+}
+
+// Anonymous hyperlink references (`__`) keep repeated link texts from
+// clashing as duplicate targets across projects.
+fn push_entries(changelog: &mut String, sections: &Sections, section: &Section) {
+    for entry in &section.entries {
+        changelog.push_str(&format!(
+            "- {} {}\n",
+            sections.autolink(&entry.message, escape, |text, url| format!(
+                "`{} <{}>`__",
+                text, url
+            )),
+            link(&format!("#{}", entry.short_hash()), &entry.link)
+        ));
+
+        // Indented under the item's text, the directive belongs to it.
+        if let Some(note) = &entry.breaking_change {
+            changelog.push_str("\n  .. important:: Breaking change\n\n");
+            for line in note.lines() {
+                changelog.push_str(format!("     {}", escape(line)).trim_end());
+                changelog.push('\n');
+            }
+            changelog.push('\n');
+        }
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!("- `{} <{}>`__\n", more.text(), more.link));
+    }
+
+    changelog.push('\n');
+}
+
+// docutils rejects underlines shorter than the title they decorate.
+// docutils measures titles in columns, where emoji and CJK take two.
+fn underline(title: &str, c: char) -> String {
+    let width: usize = title
+        .chars()
+        .map(|ch| match ch as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum();
+    c.to_string().repeat(width)
+}
+
+/// `text` as an anonymous hyperlink to `url`, or left as is when there is
+/// nowhere to link.
+fn link(text: &str, url: &str) -> String {
+    if url.is_empty() {
+        text.to_string()
+    } else {
+        format!("`{} <{}>`__", text, url)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`' | '_' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
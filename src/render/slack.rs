@@ -0,0 +1,84 @@
+use super::{Section, Sections};
+use crate::model::Project;
+
+pub fn header(date: &str) -> String {
+    format!("*Changelog for {}*\n\n", date)
+}
+
+/// Slack has no heading levels, so groups stand out by case.
+pub fn group(name: &str) -> String {
+    format!("*{}*\n\n", escape(&name.to_uppercase()))
+}
+
+/// Every project's security fixes, ahead of the projects.
+pub fn security(sections: &Sections, section: &Section) -> String {
+    let mut changelog = format!("{} *{}*\n", section.kind.emoji(), section.kind.title());
+    push_entries(&mut changelog, sections, section);
+
+    changelog.push('\n');
+    changelog
+}
+
+pub fn project(project: &Project, sections: &Sections) -> String {
+    let mut changelog = String::new();
+
+    changelog.push_str(&format!("*{}*\n", escape(&project.title())));
+
+    if project.has_about() {
+        let mut about = vec![];
+        if let Some(description) = &project.description {
+            about.push(format!("_{}_", escape(description)));
+        }
+        if let Some(homepage) = &project.homepage {
+            about.push(format!("<{}|Homepage>", homepage));
+        }
+        changelog.push_str(&format!("{}\n", about.join(" · ")));
+    }
+
+    for section in &sections.sections {
+        changelog.push_str(&format!(
+            "{} *{}*\n",
+            section.kind.emoji(),
+            section.kind.title()
+        ));
+        push_entries(&mut changelog, sections, section);
+    }
+
+    changelog.push('\n');
+    changelog
+}
+
+fn push_entries(changelog: &mut String, sections: &Sections, section: &Section) {
+    for entry in &section.entries {
+        changelog.push_str(&format!(
+            "• {} {}\n",
+            sections.autolink(&entry.message, escape, |text, url| format!(
+                "<{}|{}>",
+                url, text
+            )),
+            if entry.link.is_empty() {
+                format!("#{}", entry.short_hash())
+            } else {
+                format!("<{}|#{}>", entry.link, entry.short_hash())
+            }
+        ));
+
+        if let Some(note) = &entry.breaking_change {
+            let note = format!("*Breaking change:* {}", escape(note));
+            for line in note.lines() {
+                changelog.push_str(&format!(">{}\n", line));
+            }
+        }
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!("• <{}|{}>\n", more.link, more.text()));
+    }
+}
+
+// Slack only requires these three to be escaped in mrkdwn text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,112 @@
+use super::{RenderOptions, Section, SectionKind, Sections};
+use crate::model::Project;
+
+const RESET: &str = "\x1b[0m";
+
+/// `style` is one of the escape sequences of `options.theme`.
+fn paint(text: &str, style: &str, options: &RenderOptions) -> String {
+    if options.color && !style.is_empty() {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn header(date: &str, options: &RenderOptions) -> String {
+    format!(
+        "{}\n\n",
+        paint(
+            &format!("Changelog for {}", date),
+            &options.theme.title,
+            options
+        )
+    )
+}
+
+pub fn group(name: &str, options: &RenderOptions) -> String {
+    format!(
+        "{}\n\n",
+        paint(&name.to_uppercase(), &options.theme.group, options)
+    )
+}
+
+/// Every project's security fixes, ahead of the projects.
+pub fn security(section: &Section, options: &RenderOptions) -> String {
+    let mut changelog = format!(
+        "{}\n",
+        paint(section.kind.title(), &options.theme.security, options)
+    );
+    push_entries(&mut changelog, section, options);
+
+    changelog.push('\n');
+    changelog
+}
+
+pub fn project(project: &Project, sections: &Sections, options: &RenderOptions) -> String {
+    let mut changelog = String::new();
+
+    changelog.push_str(&format!(
+        "{}\n",
+        paint(&project.title(), &options.theme.project, options)
+    ));
+
+    if project.has_about() {
+        let about: Vec<&str> = [&project.description, &project.homepage]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        changelog.push_str(&format!(
+            "  {}\n",
+            paint(&about.join(" · "), &options.theme.about, options)
+        ));
+    }
+
+    for section in &sections.sections {
+        let color = match section.kind {
+            SectionKind::Security => &options.theme.security,
+            SectionKind::BugFixes => &options.theme.bugfixes,
+            SectionKind::Features => &options.theme.features,
+            SectionKind::Dependencies => &options.theme.dependencies,
+        };
+
+        changelog.push_str(&format!(
+            "  {}\n",
+            paint(section.kind.title(), color, options)
+        ));
+        push_entries(&mut changelog, section, options);
+    }
+
+    changelog.push('\n');
+    changelog
+}
+
+fn push_entries(changelog: &mut String, section: &Section, options: &RenderOptions) {
+    for entry in &section.entries {
+        changelog.push_str(&format!(
+            "    - {} {}\n",
+            entry.message,
+            paint(
+                &format!("({})", entry.reference()),
+                &options.theme.link,
+                options
+            )
+        ));
+
+        if let Some(note) = &entry.breaking_change {
+            let note = format!("Breaking change: {}", note);
+            for line in note.lines() {
+                changelog.push_str(format!("        {}", line).trim_end());
+                changelog.push('\n');
+            }
+        }
+    }
+
+    if let Some(more) = &section.more {
+        changelog.push_str(&format!(
+            "    {} {}\n",
+            more.text(),
+            paint(&format!("({})", more.link), &options.theme.link, options)
+        ));
+    }
+}
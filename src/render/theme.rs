@@ -0,0 +1,81 @@
+use crate::config::ThemeConfig;
+
+/// ANSI escape sequences for each part of the text format, resolved from
+/// the `[theme]` config.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub title: String,
+    pub group: String,
+    pub project: String,
+    pub about: String,
+    pub security: String,
+    pub features: String,
+    pub bugfixes: String,
+    pub dependencies: String,
+    /// The commit link, which carries the hash.
+    pub link: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(&ThemeConfig::default()).expect("the default theme is valid")
+    }
+}
+
+impl Theme {
+    /// Each part is a space-separated list of styles, e.g. `"bold red"` or
+    /// `"208"` for a 256-color code; unset parts keep their defaults.
+    pub fn new(config: &ThemeConfig) -> Result<Theme, String> {
+        let style =
+            |value: &Option<String>, default: &str| sgr(value.as_deref().unwrap_or(default));
+
+        Ok(Theme {
+            title: style(&config.title, "bold")?,
+            group: style(&config.group, "bold")?,
+            project: style(&config.project, "bold")?,
+            about: style(&config.about, "dim")?,
+            security: style(&config.security, "bold red")?,
+            features: style(&config.features, "green")?,
+            bugfixes: style(&config.bugfixes, "red")?,
+            dependencies: style(&config.dependencies, "blue")?,
+            link: style(&config.link, "dim")?,
+        })
+    }
+}
+
+/// `none` leaves the part unstyled.
+fn sgr(styles: &str) -> Result<String, String> {
+    let codes = styles
+        .split_whitespace()
+        .filter(|style| *style != "none")
+        .map(|style| {
+            let code = match style {
+                "bold" => "1",
+                "dim" => "2",
+                "italic" => "3",
+                "underline" => "4",
+                "black" => "30",
+                "red" => "31",
+                "green" => "32",
+                "yellow" => "33",
+                "blue" => "34",
+                "magenta" => "35",
+                "cyan" => "36",
+                "white" => "37",
+                _ => {
+                    return match style.parse::<u8>() {
+                        Ok(color) => Ok(format!("38;5;{}", color)),
+                        Err(_) => Err(format!("Unknown style {} in [theme]", style)),
+                    }
+                }
+            };
+            Ok(code.to_string())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if codes.is_empty() {
+        Ok(String::new())
+    } else {
+        Ok(format!("\x1b[{}m", codes.join(";")))
+    }
+}
@@ -0,0 +1,81 @@
+use crate::model::ProjectList;
+use chrono::{DateTime, Datelike};
+use std::collections::BTreeMap;
+
+/// How `report` buckets commits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+impl Period {
+    pub fn parse(name: &str) -> Option<Period> {
+        match name {
+            "day" => Some(Period::Day),
+            "week" => Some(Period::Week),
+            _ => None,
+        }
+    }
+
+    /// The bucket a commit date falls in, in the author's own time zone.
+    fn key(self, date: &str) -> Option<String> {
+        let date = DateTime::parse_from_rfc3339(date).ok()?;
+
+        Some(match self {
+            Period::Day => date.format("%Y-%m-%d (%a)").to_string(),
+            Period::Week => {
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+        })
+    }
+}
+
+/// A markdown table of commit counts and subjects per period and project,
+/// oldest first, e.g. for filling in a timesheet.
+pub fn report(projects: &ProjectList, period: Period) -> String {
+    let mut rows: BTreeMap<(String, &str), Vec<&str>> = BTreeMap::new();
+
+    for project in &projects.projects {
+        for commit in &project.commits {
+            let Some(key) = period.key(&commit.date) else {
+                continue;
+            };
+            rows.entry((key, &project.name))
+                .or_default()
+                .push(&commit.message);
+        }
+    }
+
+    let heading = match period {
+        Period::Day => "Day",
+        Period::Week => "Week",
+    };
+    let mut report = format!(
+        "| {} | Project | Commits | Summary |\n| --- | --- | --- | --- |\n",
+        heading
+    );
+    let mut total = 0;
+
+    for ((key, project), mut messages) in rows {
+        // Logs are newest first; a timesheet reads better in order of work.
+        messages.reverse();
+        total += messages.len();
+
+        report.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            key,
+            escape(project),
+            messages.len(),
+            escape(&messages.join("; "))
+        ));
+    }
+
+    report.push_str(&format!("| **Total** | | **{}** | |\n", total));
+    report
+}
+
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
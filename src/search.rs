@@ -0,0 +1,26 @@
+use crate::model::ProjectList;
+use crate::render::{self, RenderOptions};
+
+/// One block per matching commit: where and when, the subject, and a link.
+pub fn results(projects: &ProjectList, options: &RenderOptions) -> String {
+    let mut results = String::new();
+
+    for project in &projects.projects {
+        for commit in &project.commits {
+            results.push_str(&format!(
+                "{} {} {} {} ({})\n",
+                project.name,
+                commit.hash.get(..8).unwrap_or(&commit.hash),
+                commit.date.get(..10).unwrap_or(&commit.date),
+                commit.message,
+                commit.author_name,
+            ));
+            let link = render::commit_link(project, options, commit);
+            if !link.is_empty() {
+                results.push_str(&format!("  {}\n", link));
+            }
+        }
+    }
+
+    results
+}
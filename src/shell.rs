@@ -0,0 +1,20 @@
+use std::process::Command;
+
+/// A command line run through the platform shell, so configured commands
+/// can carry arguments, pipes and redirections.
+pub fn command(line: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(line);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(line);
+        shell
+    }
+}
+
+/// `text` as a single word for a POSIX shell script.
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
@@ -0,0 +1,165 @@
+use crate::json;
+use crate::model::{Project, ProjectList};
+use crate::project_file_name;
+use crate::render::{self, RenderOptions};
+use chrono::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+const RUNS_DIR: &str = "runs";
+const SITE_DIR: &str = "site";
+
+/// Stores this run next to the earlier ones so the site keeps growing as a
+/// living changelog instead of only showing the latest window.
+pub fn save_run(projects: &ProjectList) {
+    fs::create_dir_all(RUNS_DIR).expect("Failed to create runs directory");
+
+    let file_name =
+        Path::new(RUNS_DIR).join(format!("{}.json", Local::now().format("%Y-%m-%dT%H%M%S")));
+    let data = json::to_string_pretty(projects).expect("Failed to serialize run");
+    fs::write(&file_name, data)
+        .unwrap_or_else(|_| panic!("Failed to write {}", file_name.display()));
+}
+
+fn load_runs() -> Vec<Project> {
+    let mut runs: Vec<_> = fs::read_dir(RUNS_DIR)
+        .expect("Failed to read runs directory")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    runs.sort();
+
+    let mut projects: BTreeMap<String, Project> = BTreeMap::new();
+    let mut seen = HashSet::new();
+
+    for run in runs {
+        let data =
+            fs::read_to_string(&run).unwrap_or_else(|_| panic!("Failed to read {}", run.display()));
+        let run: ProjectList = json::from_str(&data)
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", run.display(), e));
+
+        for project in run.projects {
+            let merged = projects
+                .entry(project.name.clone())
+                .or_insert_with(|| Project {
+                    name: project.name.clone(),
+                    path: project.path.clone(),
+                    commits: vec![],
+                    remote: project.remote.clone(),
+                    team: project.team.clone(),
+                    description: project.description.clone(),
+                    emoji: project.emoji.clone(),
+                    homepage: project.homepage.clone(),
+                    version: project.version.clone(),
+                    anchor_group: None,
+                });
+
+            for commit in project.commits {
+                if seen.insert((project.name.clone(), commit.hash.clone())) {
+                    merged.commits.push(commit);
+                }
+            }
+        }
+    }
+
+    for project in projects.values_mut() {
+        project.commits.sort_by(|a, b| b.date.cmp(&a.date));
+    }
+
+    projects.into_values().collect()
+}
+
+fn iso_week(date: &str) -> Option<String> {
+    let date = DateTime::parse_from_rfc3339(date).ok()?;
+    let week = date.iso_week();
+    Some(format!("{}-W{:02}", week.year(), week.week()))
+}
+
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<nav><a href=\"{1}index.html\">Changelog</a></nav>\n<h1>{0}</h1>\n{2}</body>\n</html>\n",
+        title, root, body
+    )
+}
+
+fn write(path: &Path, contents: String) {
+    fs::write(path, contents).unwrap_or_else(|_| panic!("Failed to write {}", path.display()));
+}
+
+/// Renders `site/` from every saved run: an index, one page per project and
+/// one archive page per ISO week.
+pub fn build(options: &RenderOptions) {
+    let projects = load_runs();
+    let site = Path::new(SITE_DIR);
+
+    fs::create_dir_all(site.join("projects")).expect("Failed to create site directory");
+    fs::create_dir_all(site.join("weeks")).expect("Failed to create site directory");
+
+    let mut weeks: BTreeMap<String, Vec<Project>> = BTreeMap::new();
+
+    for project in &projects {
+        write(
+            &site
+                .join("projects")
+                .join(format!("{}.html", project_file_name(&project.name))),
+            page(
+                &project.name,
+                "../",
+                &render::html_fragment(options, &[project]),
+            ),
+        );
+
+        for commit in &project.commits {
+            let Some(week) = iso_week(&commit.date) else {
+                continue;
+            };
+
+            let week_projects = weeks.entry(week).or_default();
+            if week_projects.last().map(|p| &p.name) != Some(&project.name) {
+                week_projects.push(Project {
+                    name: project.name.clone(),
+                    path: project.path.clone(),
+                    commits: vec![],
+                    remote: project.remote.clone(),
+                    team: project.team.clone(),
+                    description: project.description.clone(),
+                    emoji: project.emoji.clone(),
+                    homepage: project.homepage.clone(),
+                    version: project.version.clone(),
+                    anchor_group: None,
+                });
+            }
+
+            let week_project = week_projects.last_mut().expect("pushed above");
+            week_project.commits.push(commit.clone());
+        }
+    }
+
+    for (week, week_projects) in &weeks {
+        let week_projects: Vec<&Project> = week_projects.iter().collect();
+        write(
+            &site.join("weeks").join(format!("{}.html", week)),
+            page(week, "../", &render::html_fragment(options, &week_projects)),
+        );
+    }
+
+    let mut index = String::from("<h2>Projects</h2>\n<ul>\n");
+    for project in &projects {
+        index.push_str(&format!(
+            "<li><a href=\"projects/{}.html\">{}</a></li>\n",
+            project_file_name(&project.name),
+            render::escape_html(&project.name)
+        ));
+    }
+    index.push_str("</ul>\n<h2>Weeks</h2>\n<ul>\n");
+    for week in weeks.keys().rev() {
+        index.push_str(&format!(
+            "<li><a href=\"weeks/{0}.html\">{0}</a></li>\n",
+            week
+        ));
+    }
+    index.push_str("</ul>\n");
+
+    write(&site.join("index.html"), page("Changelog", "", &index));
+}
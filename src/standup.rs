@@ -0,0 +1,58 @@
+use crate::config::HttpConfig;
+use crate::http;
+use crate::json::quote;
+use crate::model::ProjectList;
+use chrono::prelude::*;
+
+/// Days back to the last business day: Friday on Mondays and weekends,
+/// yesterday otherwise.
+pub fn days_back(today: NaiveDate) -> i64 {
+    match today.weekday() {
+        Weekday::Mon => 3,
+        Weekday::Sun => 2,
+        _ => 1,
+    }
+}
+
+/// A "what I did" list of every commit since the start of `since`, in
+/// local time, so the morning's work before a standup is included too.
+pub fn summary(projects: &ProjectList, since: NaiveDate) -> String {
+    let mut summary = format!("Since {}:\n", since.format("%a %Y-%m-%d"));
+    let mut empty = true;
+
+    for project in &projects.projects {
+        // Oldest first, in the order the work happened.
+        for commit in project.commits.iter().rev() {
+            let Ok(date) = DateTime::parse_from_rfc3339(&commit.date) else {
+                continue;
+            };
+            if date.with_timezone(&Local).date_naive() < since || commit.is_skipped() {
+                continue;
+            }
+
+            summary.push_str(&format!("- {}: {}\n", project.name, commit.message));
+            empty = false;
+        }
+    }
+
+    if empty {
+        summary.push_str("- Nothing committed\n");
+    }
+    summary
+}
+
+/// Posts the summary to the Slack incoming webhook in `SLACK_WEBHOOK_URL`.
+pub fn post_to_slack(http_config: &HttpConfig, summary: &str) -> Result<(), String> {
+    let webhook = std::env::var("SLACK_WEBHOOK_URL")
+        .map_err(|_| "SLACK_WEBHOOK_URL is not set".to_string())?;
+
+    http::request(
+        http_config,
+        "POST",
+        &webhook,
+        &[],
+        Some(&format!("{{\"text\":{}}}", quote(summary))),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,521 @@
+use crate::config::CalendarConfig;
+use crate::convention::Subject;
+use crate::json;
+use crate::model::ProjectList;
+use crate::render::Format;
+use crate::yaml;
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Serialize)]
+pub struct Stats {
+    authors: Vec<AuthorStats>,
+}
+
+#[derive(Serialize)]
+struct AuthorStats {
+    author: String,
+    commits: usize,
+    active_days: usize,
+    /// Over the whole window, including days without commits.
+    commits_per_day: f64,
+    /// Most consecutive days with at least one commit.
+    longest_streak: usize,
+    /// Consecutive days with commits up to today, or up to yesterday when
+    /// nothing is committed yet today.
+    current_streak: usize,
+    weeks: Vec<WeekStats>,
+}
+
+#[derive(Serialize)]
+struct WeekStats {
+    week: String,
+    commits: usize,
+    /// Change from the week before; absent for the first week.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta: Option<i64>,
+}
+
+/// Per-author activity across all projects. `window_days` is the length of
+/// the collection window; without one the span of the commits is used.
+pub fn stats(projects: &ProjectList, window_days: i64, today: NaiveDate) -> Stats {
+    let mut days_by_author: BTreeMap<&str, Vec<NaiveDate>> = BTreeMap::new();
+
+    for project in &projects.projects {
+        for commit in &project.commits {
+            // The commit's own calendar day, as the author saw it.
+            let Ok(date) = DateTime::parse_from_rfc3339(&commit.date) else {
+                continue;
+            };
+            days_by_author
+                .entry(&commit.author_name)
+                .or_default()
+                .push(date.date_naive());
+        }
+    }
+
+    let span = days_by_author
+        .values()
+        .flatten()
+        .min()
+        .map(|first| (today - *first).num_days() + 1)
+        .unwrap_or(1);
+    let window_days = if window_days > 0 { window_days } else { span };
+
+    let authors = days_by_author
+        .into_iter()
+        .map(|(author, dates)| {
+            let active: BTreeSet<NaiveDate> = dates.iter().copied().collect();
+
+            // Quiet weeks count as zero, so each delta is against the
+            // calendar week before.
+            let mut weeks: BTreeMap<String, usize> = BTreeMap::new();
+            let mut monday = *active.first().expect("authors have commits");
+            monday -= Duration::days(monday.weekday().num_days_from_monday() as i64);
+            while monday <= today {
+                weeks.insert(week_name(monday), 0);
+                monday += Duration::days(7);
+            }
+            for date in &dates {
+                *weeks.entry(week_name(*date)).or_default() += 1;
+            }
+
+            let mut previous = None;
+            let weeks = weeks
+                .into_iter()
+                .map(|(week, commits)| {
+                    let delta = previous.map(|previous: usize| commits as i64 - previous as i64);
+                    previous = Some(commits);
+                    WeekStats {
+                        week,
+                        commits,
+                        delta,
+                    }
+                })
+                .collect();
+
+            AuthorStats {
+                author: author.to_string(),
+                commits: dates.len(),
+                active_days: active.len(),
+                commits_per_day: (dates.len() as f64 / window_days as f64 * 100.0).round() / 100.0,
+                longest_streak: longest_streak(&active),
+                current_streak: current_streak(&active, today),
+                weeks,
+            }
+        })
+        .collect();
+
+    Stats { authors }
+}
+
+fn week_name(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+fn longest_streak(active: &BTreeSet<NaiveDate>) -> usize {
+    let mut longest = 0;
+    let mut streak = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in active {
+        streak = match previous {
+            Some(previous) if *day - previous == Duration::days(1) => streak + 1,
+            _ => 1,
+        };
+        longest = longest.max(streak);
+        previous = Some(*day);
+    }
+
+    longest
+}
+
+fn current_streak(active: &BTreeSet<NaiveDate>, today: NaiveDate) -> usize {
+    let mut day = if active.contains(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+
+    let mut streak = 0;
+    while active.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+    streak
+}
+
+/// `n` followed by the singular or plural noun.
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// The data formats serialize a view as it is; anything else gets `None`
+/// and a plain text summary instead.
+fn serialize<T: Serialize>(value: &T, format: Format) -> Option<String> {
+    match format {
+        Format::Json => Some(json::to_string_pretty(value).expect("Failed to serialize JSON")),
+        Format::Yaml => Some(yaml::to_string(value).expect("Failed to serialize YAML")),
+        Format::Toml => Some(toml::to_string_pretty(value).expect("Failed to serialize TOML")),
+        _ => None,
+    }
+}
+
+impl Stats {
+    pub fn render(&self, format: Format) -> String {
+        serialize(self, format).unwrap_or_else(|| self.text())
+    }
+
+    fn text(&self) -> String {
+        let mut text = String::new();
+
+        for author in &self.authors {
+            text.push_str(&format!(
+                "{}: {} on {}, {:.2}/day, longest streak {}, current {}\n",
+                author.author,
+                count(author.commits, "commit", "commits"),
+                count(author.active_days, "day", "days"),
+                author.commits_per_day,
+                count(author.longest_streak, "day", "days"),
+                count(author.current_streak, "day", "days")
+            ));
+
+            for week in &author.weeks {
+                match week.delta {
+                    Some(delta) => text.push_str(&format!(
+                        "  {}  {:>4}  ({:+})\n",
+                        week.week, week.commits, delta
+                    )),
+                    None => text.push_str(&format!("  {}  {:>4}\n", week.week, week.commits)),
+                }
+            }
+        }
+
+        text
+    }
+}
+
+#[derive(Serialize)]
+pub struct TimeOfDay {
+    /// Everyone together first, then each author.
+    authors: Vec<AuthorTimes>,
+}
+
+#[derive(Serialize)]
+struct AuthorTimes {
+    author: String,
+    commits: usize,
+    /// Commits on weekends or outside the working hours.
+    after_hours: usize,
+    after_hours_share: f64,
+    /// Commits per hour of the day, from midnight.
+    hours: Vec<usize>,
+    /// Commits per weekday, from Monday.
+    weekdays: Vec<usize>,
+}
+
+impl AuthorTimes {
+    fn new(author: &str) -> AuthorTimes {
+        AuthorTimes {
+            author: author.to_string(),
+            commits: 0,
+            after_hours: 0,
+            after_hours_share: 0.0,
+            hours: vec![0; 24],
+            weekdays: vec![0; 7],
+        }
+    }
+
+    fn add(&mut self, date: &DateTime<FixedOffset>, after_hours: bool) {
+        self.commits += 1;
+        self.hours[date.hour() as usize] += 1;
+        self.weekdays[date.weekday().num_days_from_monday() as usize] += 1;
+        if after_hours {
+            self.after_hours += 1;
+        }
+        self.after_hours_share =
+            (self.after_hours as f64 / self.commits as f64 * 100.0).round() / 100.0;
+    }
+}
+
+/// Buckets commits by hour and weekday in the author's own time zone,
+/// which is what tells whether they were working late.
+pub fn time_of_day(projects: &ProjectList, calendar: &CalendarConfig) -> TimeOfDay {
+    let [start, end] = calendar.working_hours;
+    let mut everyone = AuthorTimes::new("*");
+    let mut authors: BTreeMap<&str, AuthorTimes> = BTreeMap::new();
+
+    for commit in projects.projects.iter().flat_map(|p| &p.commits) {
+        let Ok(date) = DateTime::parse_from_rfc3339(&commit.date) else {
+            continue;
+        };
+        let after_hours = matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            || date.hour() < start
+            || date.hour() >= end;
+
+        everyone.add(&date, after_hours);
+        authors
+            .entry(&commit.author_name)
+            .or_insert_with(|| AuthorTimes::new(&commit.author_name))
+            .add(&date, after_hours);
+    }
+
+    let mut all = vec![everyone];
+    all.extend(authors.into_values());
+    TimeOfDay { authors: all }
+}
+
+impl TimeOfDay {
+    pub fn render(&self, format: Format) -> String {
+        serialize(self, format).unwrap_or_else(|| self.text())
+    }
+
+    /// Histograms for everyone, then each author's after-hours share.
+    fn text(&self) -> String {
+        let everyone = &self.authors[0];
+        let mut text = String::from("Commits by hour\n");
+        let max = everyone.hours.iter().max().copied().unwrap_or(0).max(1);
+        for (hour, count) in everyone.hours.iter().enumerate() {
+            text.push_str(&format!(
+                "  {:02}:00 {:>4}{}\n",
+                hour,
+                count,
+                bar(*count, max)
+            ));
+        }
+
+        text.push_str("\nCommits by weekday\n");
+        let max = everyone.weekdays.iter().max().copied().unwrap_or(0).max(1);
+        let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+        for (name, count) in names.iter().zip(&everyone.weekdays) {
+            text.push_str(&format!("  {}   {:>4}{}\n", name, count, bar(*count, max)));
+        }
+
+        text.push_str("\nAfter hours\n");
+        for author in &self.authors[1..] {
+            text.push_str(&format!(
+                "  {}: {} of {} commits ({:.0}%)\n",
+                author.author,
+                author.after_hours,
+                author.commits,
+                author.after_hours_share * 100.0
+            ));
+        }
+
+        text
+    }
+}
+
+#[derive(Serialize)]
+pub struct Comparison {
+    authors: Vec<String>,
+    rows: Vec<ComparisonRow>,
+    /// Each author's commits across all projects, in `authors` order.
+    totals: Vec<usize>,
+}
+
+#[derive(Serialize)]
+struct ComparisonRow {
+    project: String,
+    /// The conventional commit type, or `other` for subjects without one.
+    kind: String,
+    /// In `authors` order.
+    commits: Vec<usize>,
+}
+
+/// Each author's commits per project and commit type, side by side.
+pub fn compare(projects: &ProjectList, authors: &[String]) -> Comparison {
+    let mut rows = vec![];
+    let mut totals = vec![0; authors.len()];
+
+    for project in &projects.projects {
+        let mut kinds: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for commit in &project.commits {
+            let Some(index) = authors
+                .iter()
+                .position(|author| **author == *commit.author_name)
+            else {
+                continue;
+            };
+            let kind = Subject::parse(&commit.message).map_or("other", |subject| subject.kind);
+            kinds.entry(kind).or_insert_with(|| vec![0; authors.len()])[index] += 1;
+            totals[index] += 1;
+        }
+
+        rows.extend(kinds.into_iter().map(|(kind, commits)| ComparisonRow {
+            project: project.name.clone(),
+            kind: kind.to_string(),
+            commits,
+        }));
+    }
+
+    Comparison {
+        authors: authors.to_vec(),
+        rows,
+        totals,
+    }
+}
+
+impl Comparison {
+    pub fn render(&self, format: Format) -> String {
+        serialize(self, format).unwrap_or_else(|| self.text())
+    }
+
+    /// A table with a column per author, the project named on its first
+    /// row only.
+    fn text(&self) -> String {
+        let project_width = self
+            .rows
+            .iter()
+            .map(|row| row.project.chars().count())
+            .chain(["Total".len()])
+            .max()
+            .unwrap_or(0);
+        let kind_width = self
+            .rows
+            .iter()
+            .map(|row| row.kind.chars().count())
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = self
+            .authors
+            .iter()
+            .map(|author| author.chars().count().max(4))
+            .collect();
+
+        let cells = |counts: &[usize]| -> String {
+            counts
+                .iter()
+                .zip(&widths)
+                .map(|(count, width)| format!("  {:>width$}", count, width = width))
+                .collect()
+        };
+
+        let mut text = format!(
+            "{:project_width$}  {:kind_width$}",
+            "",
+            "",
+            project_width = project_width,
+            kind_width = kind_width
+        );
+        for (author, width) in self.authors.iter().zip(&widths) {
+            text.push_str(&format!("  {:>width$}", author, width = width));
+        }
+        text.push('\n');
+
+        let mut previous = None;
+        for row in &self.rows {
+            let project = if previous == Some(&row.project) {
+                ""
+            } else {
+                row.project.as_str()
+            };
+            previous = Some(&row.project);
+            text.push_str(&format!(
+                "{:project_width$}  {:kind_width$}{}\n",
+                project,
+                row.kind,
+                cells(&row.commits),
+                project_width = project_width,
+                kind_width = kind_width
+            ));
+        }
+
+        text.push_str(&format!(
+            "{:project_width$}  {:kind_width$}{}\n",
+            "Total",
+            "",
+            cells(&self.totals),
+            project_width = project_width,
+            kind_width = kind_width
+        ));
+        text
+    }
+}
+
+/// A bar scaled to the largest bucket, with its leading space.
+fn bar(count: usize, max: usize) -> String {
+    match count {
+        0 => String::new(),
+        _ => format!(" {}", "#".repeat((count * 40).div_ceil(max))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Commit, Project};
+    use std::path::PathBuf;
+
+    fn projects(dates: &[&str]) -> ProjectList {
+        let commits = dates
+            .iter()
+            .enumerate()
+            .map(|(i, date)| Commit {
+                hash: format!("{:016x}", i),
+                message: "fix: something".to_string(),
+                author_name: "Ana".into(),
+                author_email: "ana@example.com".into(),
+                date: format!("{}T10:00:00+02:00", date),
+                committer_date: String::new(),
+                body: String::new(),
+                pull_request: None,
+                files: vec![],
+            })
+            .collect();
+
+        ProjectList {
+            projects: vec![Project {
+                name: "alpha".to_string(),
+                path: PathBuf::new(),
+                commits,
+                remote: String::new(),
+                team: None,
+                description: None,
+                emoji: None,
+                homepage: None,
+                version: None,
+                anchor_group: None,
+            }],
+            diagnostics: Default::default(),
+        }
+    }
+
+    #[test]
+    fn streaks_cross_months_and_quiet_weeks_count_as_zero() {
+        let projects = projects(&[
+            "2024-02-12",
+            "2024-02-02",
+            "2024-02-01",
+            "2024-01-31",
+            "2024-01-31",
+            "2024-01-30",
+        ]);
+        let today = NaiveDate::from_ymd_opt(2024, 2, 13).expect("a valid date");
+
+        assert_eq!(
+            stats(&projects, 0, today).render(Format::Text),
+            "Ana: 6 commits on 5 days, 0.40/day, longest streak 4 days, current 1 day\n\
+             \x20 2024-W05     5\n\
+             \x20 2024-W06     0  (-5)\n\
+             \x20 2024-W07     1  (+1)\n"
+        );
+    }
+
+    #[test]
+    fn current_streaks_run_to_today_or_yesterday() {
+        let active: BTreeSet<NaiveDate> = ["2024-02-28", "2024-02-29", "2024-03-01"]
+            .iter()
+            .map(|date| date.parse().expect("a valid date"))
+            .collect();
+        let day = |date: &str| date.parse().expect("a valid date");
+
+        assert_eq!(current_streak(&active, day("2024-03-01")), 3);
+        assert_eq!(current_streak(&active, day("2024-03-02")), 3);
+        assert_eq!(current_streak(&active, day("2024-03-03")), 0);
+        assert_eq!(longest_streak(&active), 3);
+    }
+}
@@ -0,0 +1,455 @@
+use crate::encoding;
+use crate::model::{Commit, Interner};
+use chrono::NaiveDate;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Which commits to collect from a repository.
+pub enum Selection<'a> {
+    /// Everything committed in the last given number of days.
+    Days(i64),
+    /// Exactly these commits, e.g. the merge commits of a milestone.
+    Commits(&'a [String]),
+    /// The whole history.
+    All,
+    /// Everything from the start of `since` up to, not including, `until`,
+    /// in local time.
+    Dates { since: NaiveDate, until: NaiveDate },
+    /// Everything on the working copy's history since the tagged commit.
+    SinceTag(&'a str),
+}
+
+/// A version control system projects can be collected from.
+pub trait VcsBackend {
+    /// URL of the default remote, or an empty string without one.
+    fn remote(&self, path: &Path) -> String;
+
+    /// Passes the selected commits to `each` as they are read, newest
+    /// first, so a long history is never held whole. With `grep`, an
+    /// extended regular expression, only those whose message matches it.
+    /// Author names and emails come from `names`, shared across projects.
+    /// Fails when the history cannot be read, e.g. for a missing path or a
+    /// repository without commits yet.
+    fn log(
+        &self,
+        path: &Path,
+        selection: &Selection,
+        grep: Option<&str>,
+        names: &mut Interner,
+        each: &mut dyn FnMut(Commit),
+    ) -> Result<(), String>;
+
+    /// The branch changelogs are normally cut from, if it can be told.
+    fn default_branch(&self, path: &Path) -> Option<String>;
+
+    /// Date of the newest commit, RFC 3339, or `None` for an empty history.
+    fn last_commit_date(&self, path: &Path) -> Option<String>;
+
+    /// Every tag on the history of the working copy, in no particular
+    /// order.
+    fn tags(&self, path: &Path) -> Vec<String>;
+}
+
+/// Picks the backend for a working copy: Mercurial when it has an `.hg`
+/// directory, git otherwise.
+pub fn backend(path: &Path) -> Box<dyn VcsBackend> {
+    if path.join(".hg").is_dir() {
+        Box::new(Mercurial)
+    } else {
+        Box::new(Git)
+    }
+}
+
+pub struct Git;
+
+impl VcsBackend for Git {
+    fn remote(&self, path: &Path) -> String {
+        // A repository without an origin has no links, which is not an error.
+        output(
+            Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(["remote", "get-url", "origin"]),
+        )
+        .map(|remote| remote.trim().to_string())
+        .unwrap_or_default()
+    }
+
+    fn log(
+        &self,
+        path: &Path,
+        selection: &Selection,
+        grep: Option<&str>,
+        names: &mut Interner,
+        each: &mut dyn FnMut(Commit),
+    ) -> Result<(), String> {
+        let mut command = Command::new("git");
+        command
+            .arg("-C")
+            .arg(path)
+            // Windows installs may log in the ANSI code page otherwise.
+            .args([
+                "-c",
+                "core.quotePath=false",
+                "-c",
+                "i18n.logOutputEncoding=UTF-8",
+                "log",
+                "--name-only",
+            ])
+            .arg("--pretty=format:%x1e%H%x1f%s%x1f%an%x1f%ae%x1f%aI%x1f%cI%x1f%b%x1d");
+
+        if let Some(grep) = grep {
+            command
+                .arg("--extended-regexp")
+                .arg(format!("--grep={}", grep));
+        }
+
+        match selection {
+            Selection::Days(days) => {
+                command.arg("--since").arg(format!("{} days ago", days));
+            }
+            Selection::All => {}
+            Selection::Commits([]) => return Ok(()),
+            Selection::Commits(hashes) => {
+                command.arg("--no-walk").args(*hashes);
+            }
+            Selection::Dates { since, until } => {
+                command
+                    .arg("--since")
+                    .arg(format!("{} 00:00", since))
+                    .arg("--until")
+                    .arg(format!("{} 00:00", until));
+            }
+            Selection::SinceTag(tag) => {
+                command.arg(format!("refs/tags/{}..HEAD", tag));
+            }
+        }
+
+        stream(&mut command, |record| {
+            if let Some(commit) = git_commit(record, names) {
+                each(commit);
+            }
+        })
+        .map_err(|e| format!("git log failed: {}", e))
+    }
+
+    fn default_branch(&self, path: &Path) -> Option<String> {
+        // origin/HEAD is only set for clones; a local repository falls back
+        // to whatever is checked out.
+        output(Command::new("git").arg("-C").arg(path).args([
+            "symbolic-ref",
+            "--short",
+            "refs/remotes/origin/HEAD",
+        ]))
+        .map(|branch| branch.trim().trim_start_matches("origin/").to_string())
+        .or_else(|_| {
+            output(Command::new("git").arg("-C").arg(path).args([
+                "symbolic-ref",
+                "--short",
+                "HEAD",
+            ]))
+            .map(|branch| branch.trim().to_string())
+        })
+        .ok()
+    }
+
+    fn last_commit_date(&self, path: &Path) -> Option<String> {
+        output(
+            Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(["log", "-1", "--format=%aI"]),
+        )
+        .ok()
+        .map(|date| date.trim().to_string())
+        .filter(|date| !date.is_empty())
+    }
+
+    fn tags(&self, path: &Path) -> Vec<String> {
+        output(
+            Command::new("git")
+                .arg("-C")
+                .arg(path)
+                .args(["tag", "--merged", "HEAD"]),
+        )
+        .map(|tags| tags.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+    }
+}
+
+pub struct Mercurial;
+
+impl VcsBackend for Mercurial {
+    fn remote(&self, path: &Path) -> String {
+        output(
+            Command::new("hg")
+                .arg("-R")
+                .arg(path)
+                .args(["paths", "default"]),
+        )
+        .map(|remote| remote.trim().to_string())
+        .unwrap_or_default()
+    }
+
+    fn log(
+        &self,
+        path: &Path,
+        selection: &Selection,
+        grep: Option<&str>,
+        names: &mut Interner,
+        each: &mut dyn FnMut(Commit),
+    ) -> Result<(), String> {
+        let mut command = Command::new("hg");
+        command.arg("-R").arg(path).args(["log", "--template"]).arg(
+            "\\x1e{node}\\x1f{author|person}\\x1f{author|email}\\x1f{date|rfc3339date}\\x1f{join(files, '\\x1d')}\\x1f{desc}",
+        );
+
+        // hg takes the union of repeated --rev options, so everything that
+        // narrows the log goes into a single revset of intersected parts.
+        let mut revset = vec![];
+        match selection {
+            Selection::Days(days) => {
+                command.arg("--date").arg(format!("-{}", days));
+            }
+            Selection::All => {}
+            Selection::Commits([]) => return Ok(()),
+            Selection::Commits(hashes) => {
+                let hashes: Vec<String> = hashes.iter().map(|hash| quote_revset(hash)).collect();
+                revset.push(format!("({})", hashes.join(" + ")));
+            }
+            Selection::Dates { since, until } => {
+                // Mercurial's date ranges include both ends.
+                command.arg("--date").arg(format!(
+                    "{} to {}",
+                    since,
+                    until.pred_opt().unwrap_or(*until)
+                ));
+            }
+            Selection::SinceTag(tag) => {
+                revset.push(format!("only(., {})", quote_revset(tag)));
+            }
+        }
+
+        // hg intersects --rev with --date, so grep() narrows the window too.
+        if let Some(grep) = grep {
+            revset.push(format!("grep({})", quote_revset(grep)));
+        }
+        // A revset lists in its own order, oldest first for these, where
+        // the log is newest first.
+        if !revset.is_empty() {
+            command
+                .arg("--rev")
+                .arg(format!("sort({}, -rev)", revset.join(" and ")));
+        }
+
+        // Mercurial stores UTF-8 and transcodes it to HGENCODING on output.
+        command.env("HGENCODING", "utf-8");
+
+        stream(&mut command, |record| {
+            if let Some(commit) = hg_commit(record, names) {
+                each(commit);
+            }
+        })
+        .map_err(|e| format!("hg log failed: {}", e))
+    }
+
+    fn default_branch(&self, _path: &Path) -> Option<String> {
+        // Mercurial's mainline is a named branch that is always called this.
+        Some("default".to_string())
+    }
+
+    fn last_commit_date(&self, path: &Path) -> Option<String> {
+        output(Command::new("hg").arg("-R").arg(path).args([
+            "log",
+            "--limit",
+            "1",
+            "--template",
+            "{date|rfc3339date}",
+        ]))
+        .ok()
+        .filter(|date| !date.is_empty())
+    }
+
+    fn tags(&self, path: &Path) -> Vec<String> {
+        // `tip` always names the newest changeset; it is not a release.
+        output(Command::new("hg").arg("-R").arg(path).args([
+            "log",
+            "-r",
+            "::. and tag()",
+            "--template",
+            "{join(tags, '\\n')}\\n",
+        ]))
+        .map(|tags| {
+            tags.lines()
+                .filter(|tag| !tag.is_empty() && *tag != "tip")
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+}
+
+/// `text` as a quoted string in a Mercurial revset.
+fn quote_revset(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// A commit from a `git log` record. Fields are separated by US, since
+/// subjects and bodies can contain commas and newlines; GS ends the body,
+/// and the changed files follow it one per line. Each field and file name
+/// is decoded on its own, so one in a legacy encoding leaves the rest be.
+fn git_commit(record: &[u8], names: &mut Interner) -> Option<Commit> {
+    let fields: Vec<&[u8]> = record.splitn(7, |&byte| byte == 0x1f).collect();
+    let [hash, subject, name, email, date, committer_date, rest] = fields.as_slice() else {
+        return None;
+    };
+    let mut rest = rest.splitn(2, |&byte| byte == 0x1d);
+    let body = rest.next().unwrap_or_default();
+    let files = rest.next().unwrap_or_default();
+
+    Some(Commit {
+        hash: encoding::decode(hash),
+        message: encoding::decode(subject),
+        author_name: names.intern(&encoding::decode(name)),
+        author_email: names.intern(&encoding::decode(email)),
+        date: encoding::decode(date),
+        committer_date: encoding::decode(committer_date),
+        body: encoding::decode(body).trim_end().to_string(),
+        pull_request: None,
+        files: files
+            .split(|&byte| byte == b'\n')
+            .filter(|file| !file.is_empty())
+            .map(encoding::decode)
+            .collect(),
+    })
+}
+
+/// A commit from an `hg log` record, laid out like git's but with the
+/// files, separated by GS, before the description. Mercurial has one
+/// description where git has a subject and body.
+fn hg_commit(record: &[u8], names: &mut Interner) -> Option<Commit> {
+    let fields: Vec<&[u8]> = record.splitn(6, |&byte| byte == 0x1f).collect();
+    let [hash, name, email, date, files, description] = fields.as_slice() else {
+        return None;
+    };
+    let description = encoding::decode(description);
+    let (subject, body) = description.split_once('\n').unwrap_or((&description, ""));
+
+    Some(Commit {
+        hash: encoding::decode(hash),
+        message: subject.trim_end().to_string(),
+        author_name: names.intern(&encoding::decode(name)),
+        author_email: names.intern(&encoding::decode(email)),
+        date: encoding::decode(date),
+        // Mercurial keeps a single date per changeset.
+        committer_date: String::new(),
+        body: body.trim().to_string(),
+        pull_request: None,
+        files: files
+            .split(|&byte| byte == 0x1d)
+            .filter(|file| !file.is_empty())
+            .map(encoding::decode)
+            .collect(),
+    })
+}
+
+/// Runs a command whose output is records each starting with RS, passing
+/// them to `each` as they arrive; a failure is described by its stderr.
+fn stream(command: &mut Command, mut each: impl FnMut(&[u8])) -> Result<(), String> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to execute: {}", e))?;
+
+    // Drained alongside, so a chatty stderr cannot fill its pipe and stall
+    // the command.
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let errors = thread::spawn(move || {
+        let mut errors = vec![];
+        let _ = stderr.read_to_end(&mut errors);
+        errors
+    });
+
+    let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut record = vec![];
+    let mut started = false;
+    loop {
+        record.clear();
+        let read = stdout
+            .read_until(0x1e, &mut record)
+            .map_err(|e| format!("failed to read output: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        if record.last() == Some(&0x1e) {
+            record.pop();
+        }
+        // Whatever comes before the first RS is not a record.
+        if started {
+            each(&record);
+        }
+        started = true;
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("failed to execute: {}", e))?;
+    let errors = errors.join().unwrap_or_default();
+    if !status.success() {
+        return Err(encoding::decode(&errors).trim().to_string());
+    }
+
+    Ok(())
+}
+
+/// Runs a command for its stdout; a failure is described by its stderr.
+fn output(command: &mut Command) -> Result<String, String> {
+    output_bytes(command).map(|stdout| encoding::decode(&stdout))
+}
+
+fn output_bytes(command: &mut Command) -> Result<Vec<u8>, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("failed to execute: {}", e))?;
+
+    if !output.status.success() {
+        return Err(encoding::decode(&output.stderr).trim().to_string());
+    }
+
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_decoded_on_their_own() {
+        let mut record = b"0123abcd\x1ffix: credit Mu\xc3\xb1oz\x1fJos\xe9\x1fjose@example.com\x1f\
+                           2024-05-01T10:00:00+02:00\x1f2024-05-01T10:00:00+02:00\x1f\
+                           Thanks to Mu\xc3\xb1oz.\n\x1d\n"
+            .to_vec();
+        record.extend_from_slice(b"caf\xe9.txt\nna\xc3\xafve.txt\n");
+        let commit = git_commit(&record, &mut Interner::default()).expect("a full record");
+
+        assert_eq!(commit.message, "fix: credit Muñoz");
+        assert_eq!(&*commit.author_name, "José");
+        assert_eq!(commit.body, "Thanks to Muñoz.");
+        assert_eq!(commit.files, ["café.txt", "naïve.txt"]);
+    }
+
+    #[test]
+    fn mercurial_fields_are_decoded_on_their_own() {
+        let record = b"0123abcd\x1fJos\xe9\x1fjose@example.com\x1f2024-05-01T10:00:00+02:00\x1f\
+                       caf\xe9.txt\x1dna\xc3\xafve.txt\x1ffix: credit Mu\xc3\xb1oz\n\nThanks.";
+        let commit = hg_commit(record, &mut Interner::default()).expect("a full record");
+
+        assert_eq!(commit.message, "fix: credit Muñoz");
+        assert_eq!(&*commit.author_name, "José");
+        assert_eq!(commit.body, "Thanks.");
+        assert_eq!(commit.files, ["café.txt", "naïve.txt"]);
+    }
+}
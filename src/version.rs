@@ -0,0 +1,463 @@
+//! Semantic versions, and the release a project's changes call for.
+
+use crate::config::ReleaseConfig;
+use crate::model::Project;
+use crate::render::{self, RenderOptions, SectionKind};
+use crate::vcs;
+use chrono::{Datelike, NaiveDate};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+/// Which part of the version a release increments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Version {
+    /// The version in a tag such as `v1.2.3` or `1.2.3-rc.1`; any
+    /// pre-release or build suffix is dropped.
+    pub fn from_tag(tag: &str) -> Option<Version> {
+        let version = tag.strip_prefix('v').unwrap_or(tag);
+        let core = version.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse().ok());
+
+        let version = Version {
+            major: parts.next()??,
+            minor: parts.next()??,
+            patch: parts.next().unwrap_or(Some(0))?,
+        };
+        parts.next().is_none().then_some(version)
+    }
+
+    /// Before 1.0 a breaking change only bumps the minor version, as
+    /// semver.org leaves 0.x free to change.
+    pub fn bump(self, bump: Bump) -> Version {
+        match bump {
+            Bump::Major if self.major > 0 => Version {
+                major: self.major + 1,
+                minor: 0,
+                patch: 0,
+            },
+            Bump::Major | Bump::Minor => Version {
+                minor: self.minor + 1,
+                patch: 0,
+                ..self
+            },
+            Bump::Patch => Version {
+                patch: self.patch + 1,
+                ..self
+            },
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Bump {
+    pub fn name(self) -> &'static str {
+        match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        }
+    }
+}
+
+/// Major for a breaking change, minor for features and patch for anything
+/// else the changelog lists; `None` when it lists nothing to release.
+pub fn recommend(project: &Project, options: &RenderOptions) -> Option<Bump> {
+    let sections = render::sections(project, options);
+    if sections.is_empty() {
+        return None;
+    }
+
+    if project.commits.iter().any(|commit| commit.is_breaking()) {
+        Some(Bump::Major)
+    } else if sections
+        .sections
+        .iter()
+        .any(|section| section.kind == SectionKind::Features)
+    {
+        Some(Bump::Minor)
+    } else {
+        Some(Bump::Patch)
+    }
+}
+
+/// How a project numbers its releases.
+pub enum Scheme {
+    SemVer,
+    /// The `.`-separated segments of a calendar version format.
+    CalVer(Vec<String>),
+}
+
+const CALVER_TOKENS: [&str; 10] = [
+    "YYYY", "YY", "0Y", "MM", "0M", "WW", "0W", "DD", "0D", "MICRO",
+];
+
+impl Scheme {
+    pub fn parse(scheme: &str) -> Result<Scheme, String> {
+        if scheme == "semver" {
+            return Ok(Scheme::SemVer);
+        }
+
+        let segments: Vec<String> = scheme.split('.').map(str::to_string).collect();
+        let known = segments
+            .iter()
+            .all(|segment| CALVER_TOKENS.contains(&segment.as_str()));
+        if !known || segments.iter().all(|segment| segment == "MICRO") {
+            return Err(format!(
+                "Unknown release scheme {}, expected semver or a format like YYYY.0M.MICRO",
+                scheme
+            ));
+        }
+        Ok(Scheme::CalVer(segments))
+    }
+}
+
+/// A project's latest release.
+pub struct Release {
+    pub tag: String,
+    pub version: String,
+}
+
+/// The newest release tagged on the history of the project's working copy,
+/// skipping pre-releases unless `[release] prereleases` is set. Imported
+/// projects have no working copy to read tags from.
+pub fn previous(project: &Project, release: &ReleaseConfig) -> Option<Release> {
+    if project.path.as_os_str().is_empty() {
+        return None;
+    }
+
+    newest(
+        project,
+        release,
+        vcs::backend(&project.path).tags(&project.path),
+    )
+}
+
+/// The newest release among `tags` that is one of the project's.
+fn newest(project: &Project, release: &ReleaseConfig, tags: Vec<String>) -> Option<Release> {
+    tags.into_iter()
+        .filter_map(|tag| {
+            let version = version_in(release, project, &tag)?.to_string();
+            (release.prereleases || !is_prerelease(&version)).then_some(Release { tag, version })
+        })
+        .max_by_key(|release| order(&release.version))
+}
+
+/// The tag a release of `version` gets.
+pub fn tag(release: &ReleaseConfig, project: &Project, version: &str) -> String {
+    release
+        .tag_pattern
+        .as_deref()
+        .unwrap_or("v{version}")
+        .replace("{pkg}", package(project))
+        .replace("{version}", version)
+}
+
+/// The version after `previous`. Semantic versions start at 0.1.0 and
+/// calendar versions at today's date; `None` when `previous` does not
+/// follow the scheme.
+pub fn next(
+    scheme: &Scheme,
+    previous: Option<&str>,
+    bump: Bump,
+    today: NaiveDate,
+) -> Option<String> {
+    match scheme {
+        // The release a pre-release was a candidate for comes next.
+        Scheme::SemVer => match previous {
+            Some(previous) if is_prerelease(previous) => {
+                Some(Version::from_tag(previous)?.to_string())
+            }
+            Some(previous) => Some(Version::from_tag(previous)?.bump(bump).to_string()),
+            None => Some("0.1.0".to_string()),
+        },
+        Scheme::CalVer(segments) => Some(calver(segments, previous, today)),
+    }
+}
+
+/// Today's calendar version. MICRO counts up from the previous release
+/// when the dates match, and starts again at 0 when they do not.
+fn calver(segments: &[String], previous: Option<&str>, today: NaiveDate) -> String {
+    let date_part = |token: &str| match token {
+        "YYYY" => today.year().to_string(),
+        "YY" => (today.year() - 2000).to_string(),
+        "0Y" => format!("{:02}", today.year() - 2000),
+        "MM" => today.month().to_string(),
+        "0M" => format!("{:02}", today.month()),
+        "WW" => today.iso_week().week().to_string(),
+        "0W" => format!("{:02}", today.iso_week().week()),
+        "DD" => today.day().to_string(),
+        _ => format!("{:02}", today.day()),
+    };
+
+    let previous: Vec<&str> = previous.map_or(vec![], |previous| previous.split('.').collect());
+    let same_date = previous.len() == segments.len()
+        && segments.iter().zip(&previous).all(|(token, part)| {
+            token == "MICRO" || part.parse::<u32>().ok() == date_part(token).parse().ok()
+        });
+
+    segments
+        .iter()
+        .zip(previous.iter().map(Some).chain(std::iter::repeat(None)))
+        .map(|(token, part)| match token.as_str() {
+            "MICRO" if same_date => part
+                .and_then(|part| part.parse::<u64>().ok())
+                .map_or(0, |micro| micro + 1)
+                .to_string(),
+            "MICRO" => "0".to_string(),
+            token => date_part(token),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The version a tag names, or `None` for other tags.
+fn version_in<'a>(release: &ReleaseConfig, project: &Project, tag: &'a str) -> Option<&'a str> {
+    let patterns = match &release.tag_pattern {
+        Some(pattern) => vec![pattern.as_str()],
+        None => vec!["v{version}", "{version}"],
+    };
+
+    patterns.into_iter().find_map(|pattern| {
+        let pattern = pattern.replace("{pkg}", package(project));
+        let (prefix, suffix) = pattern.split_once("{version}")?;
+        let version = tag.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        version
+            .starts_with(|c: char| c.is_ascii_digit())
+            .then_some(version)
+    })
+}
+
+/// The name `{pkg}` stands for: a workspace package's own name, or the
+/// project's.
+fn package(project: &Project) -> &str {
+    project.name.rsplit('/').next().unwrap_or(&project.name)
+}
+
+/// Whether a version has a pre-release suffix, like `-rc.1` or `-beta`.
+pub fn is_prerelease(version: &str) -> bool {
+    let without_build = version.split('+').next().unwrap_or(version);
+    without_build.contains('-')
+}
+
+/// A part of a pre-release suffix; numbers sort before words.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier {
+    Number(u64),
+    Word(String),
+}
+
+/// Orders versions of either scheme by their numbers, with pre-releases
+/// before the release they lead to, as semver.org sets out.
+fn order(version: &str) -> (Vec<u64>, bool, Vec<Identifier>) {
+    let version = version.split('+').next().unwrap_or(version);
+    let (core, prerelease) = version.split_once('-').unwrap_or((version, ""));
+
+    let numbers = core
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    let identifiers = prerelease
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.parse() {
+            Ok(number) => Identifier::Number(number),
+            Err(_) => Identifier::Word(part.to_string()),
+        })
+        .collect();
+    (numbers, prerelease.is_empty(), identifiers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn project(name: &str) -> Project {
+        Project {
+            name: name.to_string(),
+            path: PathBuf::from("/src").join(name),
+            commits: vec![],
+            remote: String::new(),
+            team: None,
+            description: None,
+            emoji: None,
+            homepage: None,
+            version: None,
+            anchor_group: None,
+        }
+    }
+
+    fn release(tag_pattern: Option<&str>, prereleases: bool) -> ReleaseConfig {
+        ReleaseConfig {
+            tag_pattern: tag_pattern.map(str::to_string),
+            prereleases,
+            ..Default::default()
+        }
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        date.parse().expect("a valid date")
+    }
+
+    #[test]
+    fn semantic_versions_follow_the_bump() {
+        let next = |previous, bump| next(&Scheme::SemVer, previous, bump, day("2024-05-07"));
+
+        assert_eq!(next(Some("1.2.3"), Bump::Patch).as_deref(), Some("1.2.4"));
+        assert_eq!(next(Some("v1.2.3"), Bump::Minor).as_deref(), Some("1.3.0"));
+        assert_eq!(next(Some("1.2.3"), Bump::Major).as_deref(), Some("2.0.0"));
+        assert_eq!(next(Some("0.4.1"), Bump::Major).as_deref(), Some("0.5.0"));
+        assert_eq!(next(None, Bump::Major).as_deref(), Some("0.1.0"));
+        assert_eq!(next(Some("nightly"), Bump::Patch), None);
+    }
+
+    #[test]
+    fn prereleases_are_released_as_the_version_they_lead_to() {
+        let next = |previous, bump| next(&Scheme::SemVer, Some(previous), bump, day("2024-05-07"));
+
+        assert_eq!(next("2.0.0-rc.2", Bump::Patch).as_deref(), Some("2.0.0"));
+        assert_eq!(
+            next("1.3.0-beta+build.5", Bump::Major).as_deref(),
+            Some("1.3.0")
+        );
+    }
+
+    #[test]
+    fn calendar_versions_count_micro_within_a_date() {
+        let scheme = Scheme::parse("YYYY.0M.MICRO").expect("a valid scheme");
+        let next = |previous, today| next(&scheme, previous, Bump::Major, day(today));
+
+        assert_eq!(next(None, "2024-05-07").as_deref(), Some("2024.05.0"));
+        assert_eq!(
+            next(Some("2024.05.0"), "2024-05-07").as_deref(),
+            Some("2024.05.1")
+        );
+        assert_eq!(
+            next(Some("2024.5.9"), "2024-05-31").as_deref(),
+            Some("2024.05.10")
+        );
+        assert_eq!(
+            next(Some("2024.05.3"), "2024-06-01").as_deref(),
+            Some("2024.06.0")
+        );
+        assert_eq!(
+            next(Some("2023.05.3"), "2024-05-01").as_deref(),
+            Some("2024.05.0")
+        );
+
+        let weekly = Scheme::parse("0Y.0W").expect("a valid scheme");
+        assert_eq!(
+            super::next(&weekly, None, Bump::Patch, day("2025-01-02")).as_deref(),
+            Some("25.01")
+        );
+    }
+
+    #[test]
+    fn unknown_schemes_are_an_error() {
+        for scheme in ["MICRO", "YYYY.Q", "", "semver.MICRO"] {
+            assert!(Scheme::parse(scheme).is_err(), "{:?}", scheme);
+        }
+    }
+
+    #[test]
+    fn versions_order_by_number_then_prerelease() {
+        let mut versions = [
+            "1.10.0",
+            "2.0.0",
+            "1.9.0",
+            "2.0.0-rc.10",
+            "2.0.0-alpha",
+            "2.0.0-rc.2",
+            "2.0.0-alpha.1",
+            "2.0.0-beta",
+            "2024.05.10",
+            "2024.05.9",
+        ];
+        versions.sort_by_key(|version| order(version));
+
+        assert_eq!(
+            versions,
+            [
+                "1.9.0",
+                "1.10.0",
+                "2.0.0-alpha",
+                "2.0.0-alpha.1",
+                "2.0.0-beta",
+                "2.0.0-rc.2",
+                "2.0.0-rc.10",
+                "2.0.0",
+                "2024.05.9",
+                "2024.05.10",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_newest_tag_of_the_package_is_its_previous_release() {
+        let project = project("app/core");
+        let tagged = tags(&[
+            "core-v1.2.0",
+            "core-v1.10.0",
+            "core-v2.0.0-rc.1",
+            "web-v3.0.0",
+            "v9.0.0",
+            "core-vnext",
+        ]);
+        let newest = |release: &ReleaseConfig| {
+            newest(&project, release, tagged.clone()).map(|release| (release.tag, release.version))
+        };
+
+        assert_eq!(
+            newest(&release(Some("{pkg}-v{version}"), false)),
+            Some(("core-v1.10.0".to_string(), "1.10.0".to_string()))
+        );
+        assert_eq!(
+            newest(&release(Some("{pkg}-v{version}"), true)),
+            Some(("core-v2.0.0-rc.1".to_string(), "2.0.0-rc.1".to_string()))
+        );
+        assert_eq!(
+            newest(&release(None, false)),
+            Some(("v9.0.0".to_string(), "9.0.0".to_string()))
+        );
+        assert_eq!(
+            tag(
+                &release(Some("{pkg}-v{version}"), false),
+                &project,
+                "1.11.0"
+            ),
+            "core-v1.11.0"
+        );
+    }
+
+    #[test]
+    fn calendar_tags_are_found_with_or_without_a_prefix() {
+        let project = project("alpha");
+        let tagged = tags(&["2024.05.9", "v2024.05.10", "2024.04.12", "release-1"]);
+
+        assert_eq!(
+            newest(&project, &release(None, false), tagged).map(|release| release.tag),
+            Some("v2024.05.10".to_string())
+        );
+    }
+}
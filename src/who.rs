@@ -0,0 +1,67 @@
+use crate::glob;
+use crate::model::{Commit, ProjectList};
+use std::collections::BTreeMap;
+
+/// Whether a commit changed `path`: the file itself, anything under the
+/// directory, or anything matching it when it is a glob.
+pub fn touches(commit: &Commit, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+
+    commit.files.iter().any(|file| {
+        if path.contains(['*', '?']) {
+            glob::matches(path, file)
+        } else {
+            file == path
+                || file
+                    .strip_prefix(path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        }
+    })
+}
+
+#[derive(Default)]
+struct Author<'a> {
+    email: &'a str,
+    commits: usize,
+    last: &'a str,
+    projects: BTreeMap<&'a str, usize>,
+}
+
+/// Authors of the commits that changed `path`, most commits first.
+pub fn who(projects: &ProjectList, path: &str) -> String {
+    let mut authors: BTreeMap<&str, Author> = BTreeMap::new();
+
+    for project in &projects.projects {
+        for commit in project.commits.iter().filter(|c| touches(c, path)) {
+            let author = authors.entry(&commit.author_name).or_default();
+            author.email = &commit.author_email;
+            author.commits += 1;
+            author.last = author
+                .last
+                .max(commit.date.get(..10).unwrap_or(&commit.date));
+            *author.projects.entry(&project.name).or_default() += 1;
+        }
+    }
+
+    let mut authors: Vec<_> = authors.into_iter().collect();
+    authors.sort_by(|a, b| b.1.commits.cmp(&a.1.commits).then(a.0.cmp(b.0)));
+
+    authors
+        .into_iter()
+        .map(|(name, author)| {
+            let projects: Vec<String> = author
+                .projects
+                .iter()
+                .map(|(project, commits)| format!("{} {}", project, commits))
+                .collect();
+            format!(
+                "{:>5}  {} <{}>, last {} ({})\n",
+                author.commits,
+                name,
+                author.email,
+                author.last,
+                projects.join(", ")
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+//! Cargo and npm workspaces, whose packages are released on their own and
+//! so get a changelog each.
+
+use crate::glob;
+use crate::json;
+use crate::model::Project;
+use std::fs;
+use std::path::Path;
+use toml::Value;
+
+pub struct Package {
+    pub name: String,
+    /// Relative to the repository root, with `/` separators as in the
+    /// paths commits list.
+    pub dir: String,
+    /// From the package's Cargo.toml or package.json.
+    pub version: Option<String>,
+}
+
+/// The members of the Cargo workspace or npm workspaces at `root`, if it
+/// is one.
+pub fn packages(root: &Path) -> Vec<Package> {
+    let (patterns, excluded) = members(root);
+
+    let mut packages: Vec<Package> = patterns
+        .iter()
+        .flat_map(|pattern| expand(root, pattern))
+        .filter(|dir| !excluded.iter().any(|pattern| glob::matches(pattern, dir)))
+        .filter_map(|dir| package(root, dir))
+        .collect();
+    packages.sort_by(|a, b| a.dir.cmp(&b.dir));
+    packages.dedup_by(|a, b| a.dir == b.dir);
+    packages
+}
+
+/// Splits a project into one per package its commits touched, named
+/// `project/package`. Commits outside every package stay in the project,
+/// which is left out when that leaves it none.
+pub fn split(mut project: Project, packages: &[Package]) -> Vec<Project> {
+    let mut split = vec![];
+
+    for package in packages {
+        let prefix = format!("{}/", package.dir);
+        let commits: Vec<_> = project
+            .commits
+            .iter()
+            .filter(|commit| commit.files.iter().any(|file| file.starts_with(&prefix)))
+            .cloned()
+            .collect();
+        if commits.is_empty() {
+            continue;
+        }
+
+        split.push(Project {
+            name: format!("{}/{}", project.name, package.name),
+            path: project.path.join(&package.dir),
+            commits,
+            remote: project.remote.clone(),
+            team: project.team.clone(),
+            description: None,
+            emoji: project.emoji.clone(),
+            homepage: None,
+            version: package.version.clone(),
+            anchor_group: None,
+        });
+    }
+
+    project.commits.retain(|commit| {
+        !commit.files.iter().any(|file| {
+            packages
+                .iter()
+                .any(|package| file.starts_with(&format!("{}/", package.dir)))
+        })
+    });
+    if !project.commits.is_empty() {
+        split.insert(0, project);
+    }
+
+    split
+}
+
+/// The member and excluded directory patterns, from Cargo.toml's
+/// `[workspace]` or package.json's `workspaces`.
+fn members(root: &Path) -> (Vec<String>, Vec<String>) {
+    let cargo = read_toml(&root.join("Cargo.toml"));
+    if let Some(workspace) = cargo.as_ref().and_then(|cargo| cargo.get("workspace")) {
+        return (
+            strings(workspace.get("members")),
+            strings(workspace.get("exclude")),
+        );
+    }
+
+    let Some(npm) = read_json(&root.join("package.json")) else {
+        return (vec![], vec![]);
+    };
+    // Either a list, or `{ "packages": [...] }` as Yarn also allows.
+    let workspaces = npm.get("workspaces");
+    let patterns = match workspaces {
+        Some(Value::Table(table)) => strings(table.get("packages")),
+        other => strings(other),
+    };
+    // npm excludes with a leading `!`.
+    let (excluded, members): (Vec<String>, Vec<String>) =
+        patterns.into_iter().partition(|p| p.starts_with('!'));
+    let excluded = excluded
+        .iter()
+        .map(|pattern| pattern[1..].to_string())
+        .collect();
+    (members, excluded)
+}
+
+/// A member pattern's directories, where `*` matches within one path
+/// component.
+fn expand(root: &Path, pattern: &str) -> Vec<String> {
+    let mut dirs = vec![String::new()];
+
+    for component in pattern.trim_end_matches('/').split('/') {
+        if component.is_empty() || component == "." {
+            continue;
+        }
+        dirs = dirs
+            .into_iter()
+            .flat_map(|dir| {
+                let join = |name: &str| match dir.as_str() {
+                    "" => name.to_string(),
+                    dir => format!("{}/{}", dir, name),
+                };
+                if !component.contains(['*', '?']) {
+                    return vec![join(component)];
+                }
+                let Ok(entries) = fs::read_dir(root.join(&dir)) else {
+                    return vec![];
+                };
+                let mut names: Vec<String> = entries
+                    .filter_map(Result::ok)
+                    .filter(|entry| entry.path().is_dir())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .filter(|name| glob::matches(component, name))
+                    .collect();
+                names.sort();
+                names.iter().map(|name| join(name)).collect()
+            })
+            .collect();
+    }
+
+    dirs.retain(|dir| !dir.is_empty() && root.join(dir).is_dir());
+    dirs
+}
+
+fn package(root: &Path, dir: String) -> Option<Package> {
+    let path = root.join(&dir);
+
+    if let Some(cargo) = read_toml(&path.join("Cargo.toml")) {
+        let package = cargo.get("package")?;
+        // `version.workspace = true` takes the workspace's version.
+        let version = match package.get("version") {
+            Some(Value::String(version)) => Some(version.clone()),
+            Some(_) => read_toml(&root.join("Cargo.toml"))
+                .and_then(|root| {
+                    root.get("workspace")?
+                        .get("package")?
+                        .get("version")
+                        .cloned()
+                })
+                .and_then(|version| version.as_str().map(str::to_string)),
+            None => None,
+        };
+        return Some(Package {
+            name: package.get("name")?.as_str()?.to_string(),
+            dir,
+            version,
+        });
+    }
+
+    let npm = read_json(&path.join("package.json"))?;
+    Some(Package {
+        name: npm.get("name")?.as_str()?.to_string(),
+        dir,
+        version: npm
+            .get("version")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_toml(path: &Path) -> Option<Value> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    json::parse(&fs::read_to_string(path).ok()?).ok()
+}
@@ -0,0 +1,800 @@
+//! YAML support built on `toml::Value`, the same bridge the `json` module
+//! uses. Emitting covers everything serde produces; parsing covers the
+//! subset hand-written config files use.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use toml::{Table, Value};
+
+pub fn to_string<T: Serialize>(value: &T) -> Result<String, String> {
+    let value = Value::try_from(value).map_err(|e| e.to_string())?;
+    let mut yaml = String::new();
+
+    match &value {
+        Value::Table(table) if !table.is_empty() => write_table(&mut yaml, table, 0),
+        Value::Array(items) if !items.is_empty() => write_array(&mut yaml, items, 0),
+        scalar => {
+            yaml.push_str(&scalar_to_string(scalar));
+            yaml.push('\n');
+        }
+    }
+
+    Ok(yaml)
+}
+
+fn write_table(yaml: &mut String, table: &toml::Table, indent: usize) {
+    let pad = " ".repeat(indent);
+
+    for (key, value) in table {
+        yaml.push_str(&format!("{}{}:", pad, quote_if_needed(key)));
+
+        match value {
+            Value::Table(nested) if !nested.is_empty() => {
+                yaml.push('\n');
+                write_table(yaml, nested, indent + 2);
+            }
+            Value::Array(items) if !items.is_empty() => {
+                yaml.push('\n');
+                write_array(yaml, items, indent + 2);
+            }
+            scalar => {
+                yaml.push(' ');
+                yaml.push_str(&scalar_to_string(scalar));
+                yaml.push('\n');
+            }
+        }
+    }
+}
+
+fn write_array(yaml: &mut String, items: &[Value], indent: usize) {
+    let pad = " ".repeat(indent);
+
+    for item in items {
+        match item {
+            // The first key of a mapping shares the line with the dash.
+            Value::Table(table) if !table.is_empty() => {
+                let mut nested = String::new();
+                write_table(&mut nested, table, indent + 2);
+                yaml.push_str(&pad);
+                yaml.push_str("- ");
+                yaml.push_str(&nested[indent + 2..]);
+            }
+            Value::Array(nested) if !nested.is_empty() => {
+                yaml.push_str(&format!("{}-\n", pad));
+                write_array(yaml, nested, indent + 2);
+            }
+            scalar => {
+                yaml.push_str(&format!("{}- {}\n", pad, scalar_to_string(scalar)));
+            }
+        }
+    }
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => quote_if_needed(s),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) if f.is_nan() => ".nan".to_string(),
+        Value::Float(f) if f.is_infinite() => if *f > 0.0 { ".inf" } else { "-.inf" }.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Datetime(d) => quote_if_needed(&d.to_string()),
+        Value::Array(_) => "[]".to_string(),
+        Value::Table(_) => "{}".to_string(),
+    }
+}
+
+/// Leaves a string as a plain scalar when YAML would read it back as the
+/// same string, and double-quotes it otherwise.
+fn quote_if_needed(s: &str) -> String {
+    let ambiguous = matches!(
+        s.to_ascii_lowercase().as_str(),
+        "" | "~" | "null" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n"
+    ) || s.parse::<f64>().is_ok()
+        // Whatever this module's own parser reads as a non-string, e.g. `0x1F`.
+        || !matches!(plain(s), Some(Value::String(_)))
+        // Dates, times and sexagesimal numbers would load as non-strings.
+        || (s.starts_with(|c: char| c.is_ascii_digit()) && s.contains(['-', ':']));
+
+    let special_start =
+        s.starts_with(|c: char| c.is_whitespace() || "-?:,[]{}#&*!|>'\"%@`".contains(c));
+
+    let special_inside = s.ends_with(char::is_whitespace)
+        || s.ends_with(':')
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars().any(|c| c.is_control());
+
+    if ambiguous || special_start || special_inside {
+        crate::json::quote(s)
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    parse(text)?.try_into().map_err(|e| e.to_string())
+}
+
+/// Parses block mappings and sequences, flow collections, plain and quoted
+/// scalars, `|` and `>` block scalars and comments. Anchors, tags and
+/// multi-document streams are not supported. As in the `json` module,
+/// nulls are dropped.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut lines = vec![];
+
+    for (i, raw) in text.lines().enumerate() {
+        let content = raw.trim_start_matches(' ');
+        let indent = raw.len() - content.len();
+        let text = strip_comment(content).trim_end();
+
+        if content.starts_with('\t') && !text.is_empty() {
+            return Err(format!(
+                "line {}: tabs are not allowed for indentation",
+                i + 1
+            ));
+        }
+
+        // Directives and document markers carry nothing for a single document.
+        let marker = indent == 0 && (text == "---" || text == "..." || text.starts_with('%'));
+
+        lines.push(Line {
+            number: i + 1,
+            indent,
+            text: if marker {
+                String::new()
+            } else {
+                text.to_string()
+            },
+            raw: raw.to_string(),
+        });
+    }
+
+    let mut parser = Parser { lines, pos: 0 };
+    let value = parser.block(0)?;
+
+    if let Some(line) = parser.peek() {
+        return Err(format!("line {}: unexpected content", line.number));
+    }
+
+    Ok(value.unwrap_or(Value::Table(Table::new())))
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    /// The content after the indentation, without comments.
+    text: String,
+    /// The whole line, for block scalars where `#` is not a comment.
+    raw: String,
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    /// The next line with content.
+    fn peek(&mut self) -> Option<&Line> {
+        while self
+            .lines
+            .get(self.pos)
+            .is_some_and(|line| line.text.is_empty())
+        {
+            self.pos += 1;
+        }
+        self.lines.get(self.pos)
+    }
+
+    /// A node starting on the next line, if that is indented at least
+    /// `indent`.
+    fn block(&mut self, indent: usize) -> Result<Option<Value>, String> {
+        let Some(line) = self.peek() else {
+            return Ok(None);
+        };
+        if line.indent < indent {
+            return Ok(None);
+        }
+
+        let (indent, number, text) = (line.indent, line.number, line.text.clone());
+
+        if is_sequence_item(&text) {
+            self.sequence(indent).map(Some)
+        } else if split_key(&text).is_some() {
+            self.mapping(indent).map(Some)
+        } else {
+            self.pos += 1;
+            inline(&text, number)
+        }
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut table = Table::new();
+
+        while let Some(line) = self.peek() {
+            if line.indent < indent || (line.indent == indent && is_sequence_item(&line.text)) {
+                break;
+            }
+
+            let number = line.number;
+            if line.indent > indent {
+                return Err(format!("line {}: unexpected indentation", number));
+            }
+
+            let text = line.text.clone();
+            let (key, rest) = split_key(&text)
+                .ok_or_else(|| format!("line {}: expected `key: value`", number))?;
+            let key = match inline(key, number)? {
+                Some(Value::String(key)) => key,
+                Some(other) => other.to_string(),
+                None => return Err(format!("line {}: null keys are not supported", number)),
+            };
+            self.pos += 1;
+
+            let value = if rest.is_empty() {
+                match self.peek() {
+                    Some(next) if next.indent > indent => {
+                        let next_indent = next.indent;
+                        self.block(next_indent)?
+                    }
+                    // A sequence may sit at the same indentation as its key.
+                    Some(next) if next.indent == indent && is_sequence_item(&next.text) => {
+                        Some(self.sequence(indent)?)
+                    }
+                    _ => None,
+                }
+            } else if let Some(style) = block_scalar_style(rest) {
+                Some(Value::String(self.block_scalar(indent, style)))
+            } else {
+                inline(rest, number)?
+            };
+
+            if table.contains_key(&key) {
+                return Err(format!("line {}: duplicate key `{}`", number, key));
+            }
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+        }
+
+        Ok(Value::Table(table))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = vec![];
+
+        while let Some(line) = self.peek() {
+            if line.indent != indent || !is_sequence_item(&line.text) {
+                if line.indent > indent {
+                    return Err(format!("line {}: unexpected indentation", line.number));
+                }
+                break;
+            }
+
+            let number = line.number;
+            let text = line.text.clone();
+            let item = text[1..].trim_start();
+
+            let value = if item.is_empty() {
+                self.pos += 1;
+                self.block(indent + 1)?
+            } else if is_sequence_item(item) || split_key(item).is_some() {
+                // A nested node starting on the dash's line continues at the
+                // column it starts in, so parse it as if it began its own line.
+                let line = &mut self.lines[self.pos];
+                line.indent = indent + text.len() - item.len();
+                line.text = item.to_string();
+                let item_indent = line.indent;
+                self.block(item_indent)?
+            } else if let Some(style) = block_scalar_style(item) {
+                self.pos += 1;
+                Some(Value::String(self.block_scalar(indent, style)))
+            } else {
+                self.pos += 1;
+                inline(item, number)?
+            };
+
+            if let Some(value) = value {
+                items.push(value);
+            }
+        }
+
+        Ok(Value::Array(items))
+    }
+
+    /// The lines of a `|` or `>` scalar, indented deeper than its parent.
+    fn block_scalar(&mut self, parent_indent: usize, (folded, chomping): (bool, char)) -> String {
+        let mut content_indent = None;
+        let mut lines: Vec<&str> = vec![];
+
+        while let Some(line) = self.lines.get(self.pos) {
+            let blank = line.raw.trim().is_empty();
+            if !blank {
+                let indent = *content_indent.get_or_insert(line.indent);
+                if line.indent < indent || line.indent <= parent_indent {
+                    break;
+                }
+            }
+
+            lines.push(if blank {
+                ""
+            } else {
+                &line.raw[content_indent.unwrap_or(0)..]
+            });
+            self.pos += 1;
+        }
+
+        let trailing = lines
+            .iter()
+            .rev()
+            .take_while(|line| line.is_empty())
+            .count();
+        lines.truncate(lines.len() - trailing);
+
+        let mut text = if folded {
+            let mut text = String::new();
+            for (i, line) in lines.iter().enumerate() {
+                // Breaks between lines fold to spaces; each blank line
+                // stands for one newline.
+                if i > 0 {
+                    if line.is_empty() {
+                        text.push('\n');
+                    } else if !lines[i - 1].is_empty() {
+                        text.push(' ');
+                    }
+                }
+                text.push_str(line);
+            }
+            text
+        } else {
+            lines.join("\n")
+        };
+
+        match chomping {
+            '-' => {}
+            '+' => text.push_str(&"\n".repeat(trailing + 1)),
+            _ => text.push('\n'),
+        }
+        text
+    }
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// `|` or `>` with an optional `-`/`+` chomping indicator, as
+/// `(folded, chomping)`.
+fn block_scalar_style(text: &str) -> Option<(bool, char)> {
+    let mut chars = text.chars();
+    let folded = match chars.next()? {
+        '|' => false,
+        '>' => true,
+        _ => return None,
+    };
+
+    match chars.as_str() {
+        "" => Some((folded, ' ')),
+        "-" => Some((folded, '-')),
+        "+" => Some((folded, '+')),
+        _ => None,
+    }
+}
+
+/// Splits `key: value` at the first colon outside quotes and brackets that
+/// ends the text or is followed by a space.
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['[', '{']) {
+        return None;
+    }
+
+    let mut quote = None;
+    let mut depth = 0;
+    let mut previous = ' ';
+
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if previous != '\\' => {}
+            (Some(q), c) if c == q && previous != '\\' => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ':') if depth == 0 => {
+                let rest = &text[i + 1..];
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((text[..i].trim_end(), rest.trim_start()));
+                }
+            }
+            _ => {}
+        }
+        previous = if previous == '\\' && c == '\\' {
+            ' '
+        } else {
+            c
+        };
+    }
+
+    None
+}
+
+/// Cuts a comment: `#` at the start or after whitespace, outside quotes.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut escaped = false;
+
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '#' && previous.is_whitespace() => return &text[..i],
+            None if (c == '"' || c == '\'')
+                && (previous.is_whitespace() || "[{,:-".contains(previous)) =>
+            {
+                quote = Some(c)
+            }
+            None => {}
+        }
+        previous = c;
+    }
+
+    text
+}
+
+/// A value written on one line: a flow collection, a quoted scalar or a
+/// plain scalar.
+fn inline(text: &str, number: usize) -> Result<Option<Value>, String> {
+    if !text.starts_with(['[', '{', '"', '\'']) {
+        return Ok(plain(text.trim()));
+    }
+
+    let mut flow = Flow {
+        chars: text.chars().collect(),
+        pos: 0,
+    };
+    let value = flow
+        .value()
+        .map_err(|e| format!("line {}: {}", number, e))?;
+    flow.whitespace();
+
+    if flow.pos != flow.chars.len() {
+        return Err(format!("line {}: unexpected text after value", number));
+    }
+
+    Ok(value)
+}
+
+/// Resolves a plain scalar with the YAML 1.2 core schema.
+fn plain(text: &str) -> Option<Value> {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return None,
+        "true" | "True" | "TRUE" => return Some(Value::Boolean(true)),
+        "false" | "False" | "FALSE" => return Some(Value::Boolean(false)),
+        ".inf" | ".Inf" | ".INF" | "+.inf" => return Some(Value::Float(f64::INFINITY)),
+        "-.inf" | "-.Inf" | "-.INF" => return Some(Value::Float(f64::NEG_INFINITY)),
+        ".nan" | ".NaN" | ".NAN" => return Some(Value::Float(f64::NAN)),
+        _ => {}
+    }
+
+    let unsigned = text.trim_start_matches(['-', '+']);
+    if let Some(hex) = text.strip_prefix("0x") {
+        if let Ok(i) = i64::from_str_radix(hex, 16) {
+            return Some(Value::Integer(i));
+        }
+    }
+    if unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        if let Ok(i) = text.parse::<i64>() {
+            return Some(Value::Integer(i));
+        }
+        if let Ok(f) = text.parse::<f64>() {
+            return Some(Value::Float(f));
+        }
+    }
+
+    Some(Value::String(text.to_string()))
+}
+
+/// Parses flow collections and quoted scalars.
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Flow {
+    fn whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.whitespace();
+        if self.chars.get(self.pos) == Some(&expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}`", expected))
+        }
+    }
+
+    fn value(&mut self) -> Result<Option<Value>, String> {
+        self.whitespace();
+
+        match self.chars.get(self.pos).copied() {
+            Some('[') => self.sequence().map(Some),
+            Some('{') => self.mapping().map(Some),
+            Some('"') => self.double_quoted().map(|s| Some(Value::String(s))),
+            Some('\'') => self.single_quoted().map(|s| Some(Value::String(s))),
+            _ => Ok(plain(self.plain_text().trim())),
+        }
+    }
+
+    /// A plain scalar inside a flow collection ends at `,`, a closing
+    /// bracket, or a `: ` separator.
+    fn plain_text(&mut self) -> String {
+        let start = self.pos;
+
+        while let Some(&c) = self.chars.get(self.pos) {
+            let separator = c == ':'
+                && self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_none_or(|next| next.is_whitespace() || ",]}".contains(*next));
+            if ",]}".contains(c) || separator {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn sequence(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = vec![];
+
+        loop {
+            self.whitespace();
+            if self.chars.get(self.pos) == Some(&']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+
+            if let Some(value) = self.value()? {
+                items.push(value);
+            }
+
+            self.whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err("expected `,` or `]`".to_string()),
+            }
+        }
+    }
+
+    fn mapping(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut table = Table::new();
+
+        loop {
+            self.whitespace();
+            if self.chars.get(self.pos) == Some(&'}') {
+                self.pos += 1;
+                return Ok(Value::Table(table));
+            }
+
+            let key = match self.value()? {
+                Some(Value::String(key)) => key,
+                Some(other) => other.to_string(),
+                None => return Err("null keys are not supported".to_string()),
+            };
+            self.expect(':')?;
+
+            let value = match self.chars.get(self.pos) {
+                Some(c) if !c.is_whitespace() && *c != ',' && *c != '}' => {
+                    return Err("expected a space after `:`".to_string())
+                }
+                _ => self.value()?,
+            };
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+
+            self.whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return Err("expected `,` or `}`".to_string()),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut s = String::new();
+
+        loop {
+            let c = *self
+                .chars
+                .get(self.pos)
+                .ok_or("unterminated double-quoted string")?;
+            self.pos += 1;
+
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = *self.chars.get(self.pos).ok_or("unterminated escape")?;
+                    self.pos += 1;
+                    match escape {
+                        'n' => s.push('\n'),
+                        't' => s.push('\t'),
+                        'r' => s.push('\r'),
+                        '0' => s.push('\0'),
+                        '"' | '\\' | '/' | ' ' => s.push(escape),
+                        'x' | 'u' | 'U' => {
+                            let len = match escape {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let hex: String = self.chars.iter().skip(self.pos).take(len).collect();
+                            let code = u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .filter(|_| hex.len() == len)
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| format!("invalid escape \\{}{}", escape, hex))?;
+                            s.push(code);
+                            self.pos += len;
+                        }
+                        other => return Err(format!("invalid escape \\{}", other)),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut s = String::new();
+
+        loop {
+            let c = *self
+                .chars
+                .get(self.pos)
+                .ok_or("unterminated single-quoted string")?;
+            self.pos += 1;
+
+            if c == '\'' {
+                // A doubled quote is an escaped quote.
+                if self.chars.get(self.pos) == Some(&'\'') {
+                    self.pos += 1;
+                    s.push('\'');
+                } else {
+                    return Ok(s);
+                }
+            } else {
+                s.push(c);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: &str) -> String {
+        let mut table = Table::new();
+        table.insert("key".to_string(), Value::String(s.to_string()));
+        let yaml = to_string(&table).expect("serializable");
+        let parsed = parse(&yaml).unwrap_or_else(|e| panic!("{:?}: {}", yaml, e));
+        parsed["key"]
+            .as_str()
+            .unwrap_or_else(|| panic!("{:?} read back as {:?}", yaml, parsed["key"]))
+            .to_string()
+    }
+
+    #[test]
+    fn special_characters_are_quoted() {
+        assert_eq!(quote_if_needed("fix: crash"), r#""fix: crash""#);
+        assert_eq!(quote_if_needed("issue#12"), "issue#12");
+        assert_eq!(quote_if_needed("issue #12"), r##""issue #12""##);
+        assert_eq!(quote_if_needed("see #12 and # 3"), r##""see #12 and # 3""##);
+        assert_eq!(quote_if_needed("- item"), r#""- item""#);
+        assert_eq!(quote_if_needed("#12"), r##""#12""##);
+        assert_eq!(quote_if_needed("one\ntwo"), r#""one\ntwo""#);
+        assert_eq!(quote_if_needed("handle ü in names"), "handle ü in names");
+        assert_eq!(quote_if_needed("yes"), r#""yes""#);
+        assert_eq!(quote_if_needed("1.5"), r#""1.5""#);
+        assert_eq!(quote_if_needed("2024-05-01"), r#""2024-05-01""#);
+    }
+
+    #[test]
+    fn strings_read_back_unchanged() {
+        for s in [
+            "fix: crash on start",
+            "key:",
+            "a # not a comment",
+            "- leading dash",
+            "-",
+            "line one\nline two\n",
+            "tab\there",
+            "quote \" and backslash \\",
+            "'single'",
+            "handle ü and 🚀 in names",
+            "",
+            " padded ",
+            "null",
+            "0x1F",
+            "[not, a, list]",
+            "{not: a map}",
+            "*alias",
+            "&anchor",
+            "!tag",
+            "|",
+            ">",
+            "%directive",
+            "@at",
+            "`backtick",
+        ] {
+            assert_eq!(round_trip(s), s);
+        }
+    }
+
+    #[test]
+    fn keys_read_back_unchanged() {
+        for key in ["fix: a", "a # b", "- x", "#1", "ü", "[x]", "true", "0x1F"] {
+            let mut table = Table::new();
+            table.insert(key.to_string(), Value::Integer(1));
+            let yaml = to_string(&table).expect("serializable");
+
+            let parsed = parse(&yaml).unwrap_or_else(|e| panic!("{:?}: {}", yaml, e));
+            assert_eq!(parsed.as_table(), Some(&table), "{:?}", yaml);
+        }
+    }
+
+    #[test]
+    fn nested_collections_round_trip() {
+        let yaml = "projects:\n  - commits:\n      - \"fix: a\"\n      - \"feat: b\"\n    name: alpha\n  - name: beta\n";
+        let value = parse(yaml).expect("valid YAML");
+
+        assert_eq!(to_string(&value).expect("serializable"), yaml);
+    }
+
+    #[test]
+    fn parses_hand_written_config() {
+        let value = parse(
+            "# top comment\n\
+             paths:\n  alpha: ~/src/alpha  # trailing comment\n  'with: colon': /x\n\
+             bots:\n  exclude: true\n  names: [dependabot, 'renovate[bot]']\n\
+             template: |\n  line one\n  line two\n",
+        )
+        .expect("valid YAML");
+
+        assert_eq!(value["paths"]["alpha"].as_str(), Some("~/src/alpha"));
+        assert_eq!(value["paths"]["with: colon"].as_str(), Some("/x"));
+        assert_eq!(value["bots"]["exclude"].as_bool(), Some(true));
+        assert_eq!(value["bots"]["names"].as_array().map(Vec::len), Some(2));
+        assert_eq!(value["template"].as_str(), Some("line one\nline two\n"));
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for yaml in [
+            "key: \"unclosed",
+            "key: 'unclosed",
+            "\tkey: tab indented",
+            "key: [unclosed",
+            "key: {unclosed: 1",
+            "- item\nkey: mixed",
+        ] {
+            assert!(parse(yaml).is_err(), "{:?}", yaml);
+        }
+    }
+}